@@ -0,0 +1,103 @@
+//! Measures how `find` throughput scales with number of concurrent readers.
+//!
+//! Every reader runs the same number of queries, so with readers that do not block each
+//! other time per iteration stays flat until readers outnumber CPU cores.
+//!
+//! Blocking API is measured with threads and async API with tokio tasks.
+//!
+//! - cargo bench
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use memquery::{blocking, doc, query, r#async};
+
+const DOCUMENTS: u64 = 1000;
+const FINDS_PER_READER: u64 = 20;
+const READERS: [u64; 4] = [1, 2, 4, 8];
+
+fn collection() -> blocking::Collection {
+  let coll = blocking::Collection::new();
+  for i in 0..DOCUMENTS {
+    coll
+      .insert(doc!({ "name": format!("user{}", i), "age": i % 100 }))
+      .unwrap();
+  }
+  coll
+}
+
+fn read_scaling_threads(c: &mut Criterion) {
+  let coll = collection();
+  let mut group = c.benchmark_group("read_scaling");
+  for readers in READERS {
+    group.throughput(Throughput::Elements(readers * FINDS_PER_READER));
+    group.bench_with_input(
+      BenchmarkId::new("threads", readers),
+      &readers,
+      |b, &readers| {
+        b.iter(|| {
+          std::thread::scope(|s| {
+            for _ in 0..readers {
+              s.spawn(|| {
+                for _ in 0..FINDS_PER_READER {
+                  coll.find(query!({ "age": { "$gte": 50 } })).unwrap();
+                }
+              });
+            }
+          })
+        })
+      },
+    );
+  }
+  group.finish();
+}
+
+fn async_collection(runtime: &tokio::runtime::Runtime) -> r#async::Collection {
+  let coll = r#async::Collection::new();
+  runtime.block_on(async {
+    for i in 0..DOCUMENTS {
+      coll
+        .insert(doc!({ "name": format!("user{}", i), "age": i % 100 }))
+        .await
+        .unwrap();
+    }
+  });
+  coll
+}
+
+fn read_scaling_tasks(c: &mut Criterion) {
+  let runtime = tokio::runtime::Builder::new_multi_thread()
+    .worker_threads(READERS[READERS.len() - 1] as usize)
+    .build()
+    .unwrap();
+  let coll = async_collection(&runtime);
+  let mut group = c.benchmark_group("read_scaling");
+  for readers in READERS {
+    group.throughput(Throughput::Elements(readers * FINDS_PER_READER));
+    group.bench_with_input(
+      BenchmarkId::new("tasks", readers),
+      &readers,
+      |b, &readers| {
+        b.iter(|| {
+          runtime.block_on(async {
+            let tasks: Vec<_> = (0..readers)
+              .map(|_| {
+                let coll = coll.clone();
+                tokio::spawn(async move {
+                  for _ in 0..FINDS_PER_READER {
+                    coll.find(query!({ "age": { "$gte": 50 } })).await.unwrap();
+                  }
+                })
+              })
+              .collect();
+            for task in tasks {
+              task.await.unwrap();
+            }
+          })
+        })
+      },
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, read_scaling_threads, read_scaling_tasks);
+criterion_main!(benches);
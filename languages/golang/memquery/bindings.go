@@ -102,9 +102,20 @@ func WriteString(name string) (int32, int, error) {
 
 }
 
+// ResultError is error reported by memquery, Code is the same as code of MongoDB error
+type ResultError struct {
+	Code    int     `json:"code"`
+	Message string  `json:"message"`
+	Path    *string `json:"path"`
+}
+
+func (e *ResultError) Error() string {
+	return e.Message
+}
+
 type Result struct {
-	Value interface{} `json:"value,omitempty"`
-	Error string      `json:"error,omitempty"`
+	Value interface{}  `json:"value,omitempty"`
+	Error *ResultError `json:"error,omitempty"`
 }
 
 func ResultPtrToValue(memoryAddr []byte, resultPtr int32) (*Result, int, error) {
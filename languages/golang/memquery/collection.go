@@ -26,14 +26,242 @@ func CreateCollection(name string) (*Collection, error) {
 	if err != nil {
 		return nil, err
 	}
-	_, err = createCollection(namePtr, nameLen)
+	resultPtr, err := createCollection(namePtr, nameLen)
 	if err != nil {
 		return nil, err
 	}
+	if err = readEmptyResult(resultPtr.(int32)); err != nil {
+		return nil, err
+	}
+
+	return &Collection{name}, nil
+}
+
+// ReplaceCollection creates named collection, replacing existing collection and its documents
+func ReplaceCollection(name string) (*Collection, error) {
+	namePtr, nameLen, err := WriteString(name)
+	if err != nil {
+		return nil, err
+	}
+
+	replaceCollection, err := instance.Exports.GetFunction("replace_collection")
+	if err != nil {
+		return nil, err
+	}
+	resultPtr, err := replaceCollection(namePtr, nameLen)
+	if err != nil {
+		return nil, err
+	}
+	if err = readEmptyResult(resultPtr.(int32)); err != nil {
+		return nil, err
+	}
 
 	return &Collection{name}, nil
 }
 
+// ListCollectionNames returns sorted names of all collections
+func ListCollectionNames() ([]string, error) {
+	listNames, err := instance.Exports.GetFunction("list_collection_names")
+	if err != nil {
+		return nil, err
+	}
+	resultPtr, err := listNames()
+	if err != nil {
+		return nil, err
+	}
+	value, err := readResult(resultPtr.(int32))
+	if err != nil {
+		return nil, err
+	}
+
+	names := []string{}
+	values, _ := value.([]interface{})
+	for _, v := range values {
+		names = append(names, v.(string))
+	}
+	return names, nil
+}
+
+// CollectionExists checks if collection with given name exists
+func CollectionExists(name string) (bool, error) {
+	namePtr, nameLen, err := WriteString(name)
+	if err != nil {
+		return false, err
+	}
+	exists, err := instance.Exports.GetFunction("collection_exists")
+	if err != nil {
+		return false, err
+	}
+	res, err := exists(namePtr, nameLen)
+	if err != nil {
+		return false, err
+	}
+	return res != int32(0), nil
+}
+
+// RenameCollection renames collection from to collection to.  When dropTarget is true
+// existing collection named to is replaced, otherwise renaming to existing collection fails.
+func RenameCollection(from string, to string, dropTarget bool) error {
+	fromPtr, fromLen, err := WriteString(from)
+	if err != nil {
+		return err
+	}
+	toPtr, toLen, err := WriteString(to)
+	if err != nil {
+		return err
+	}
+	rename, err := instance.Exports.GetFunction("rename_collection")
+	if err != nil {
+		return err
+	}
+	drop := int32(0)
+	if dropTarget {
+		drop = 1
+	}
+	resultPtr, err := rename(fromPtr, fromLen, toPtr, toLen, drop)
+	if err != nil {
+		return err
+	}
+	return readEmptyResult(resultPtr.(int32))
+}
+
+// CountOptions limits documents counted by CountDocuments
+type CountOptions struct {
+	Skip  int  `json:"skip,omitempty"`
+	Limit *int `json:"limit,omitempty"`
+}
+
+// CountDocuments returns number of documents that match query without returning them
+func (c *Collection) CountDocuments(query interface{}, options *CountOptions) (int, error) {
+	namePtr, nameLen, err := WriteString(c.name)
+	if err != nil {
+		return 0, err
+	}
+	queryBytes, err := json.Marshal(query)
+	if err != nil {
+		return 0, err
+	}
+	queryPtr, queryLen, err := WriteString(string(queryBytes))
+	if err != nil {
+		return 0, err
+	}
+	if options == nil {
+		options = &CountOptions{}
+	}
+	optionsBytes, err := json.Marshal(options)
+	if err != nil {
+		return 0, err
+	}
+	optionsPtr, optionsLen, err := WriteString(string(optionsBytes))
+	if err != nil {
+		return 0, err
+	}
+
+	countDocuments, err := instance.Exports.GetFunction("count_documents")
+	if err != nil {
+		return 0, err
+	}
+	resultPtr, err := countDocuments(namePtr, nameLen, queryPtr, queryLen, optionsPtr, optionsLen)
+	if err != nil {
+		return 0, err
+	}
+	value, err := readResult(resultPtr.(int32))
+	if err != nil {
+		return 0, err
+	}
+	count, _ := value.(float64)
+	return int(count), nil
+}
+
+// EstimatedDocumentCount returns number of documents in collection without evaluating a query
+func (c *Collection) EstimatedDocumentCount() (int, error) {
+	namePtr, nameLen, err := WriteString(c.name)
+	if err != nil {
+		return 0, err
+	}
+	estimatedCount, err := instance.Exports.GetFunction("estimated_document_count")
+	if err != nil {
+		return 0, err
+	}
+	resultPtr, err := estimatedCount(namePtr, nameLen)
+	if err != nil {
+		return 0, err
+	}
+	value, err := readResult(resultPtr.(int32))
+	if err != nil {
+		return 0, err
+	}
+	count, _ := value.(float64)
+	return int(count), nil
+}
+
+// Stats returns number of documents, their approximate memory size, index sizes
+// and operation counters of the collection
+func (c *Collection) Stats() (map[string]interface{}, error) {
+	namePtr, nameLen, err := WriteString(c.name)
+	if err != nil {
+		return nil, err
+	}
+	collectionStats, err := instance.Exports.GetFunction("collection_stats")
+	if err != nil {
+		return nil, err
+	}
+	resultPtr, err := collectionStats(namePtr, nameLen)
+	if err != nil {
+		return nil, err
+	}
+	value, err := readResult(resultPtr.(int32))
+	if err != nil {
+		return nil, err
+	}
+	stats, _ := value.(map[string]interface{})
+	return stats, nil
+}
+
+// Stats returns statistics of all collections together
+func Stats() (map[string]interface{}, error) {
+	dbStats, err := instance.Exports.GetFunction("stats")
+	if err != nil {
+		return nil, err
+	}
+	resultPtr, err := dbStats()
+	if err != nil {
+		return nil, err
+	}
+	value, err := readResult(resultPtr.(int32))
+	if err != nil {
+		return nil, err
+	}
+	stats, _ := value.(map[string]interface{})
+	return stats, nil
+}
+
+func readEmptyResult(resultPtr int32) error {
+	_, err := readResult(resultPtr)
+	return err
+}
+
+func readResult(resultPtr int32) (interface{}, error) {
+	strAddr, err := LinearMemoryAddr()
+	if err != nil {
+		return nil, err
+	}
+	result, resultLen, err := ResultPtrToValue(strAddr, resultPtr)
+	if err != nil {
+		return nil, err
+	}
+	dealloc, err := instance.Exports.GetFunction("dealloc")
+	if err != nil {
+		return nil, err
+	}
+	dealloc(resultPtr, resultLen)
+
+	if result.Error != nil {
+		return nil, result.Error
+	}
+	return result.Value, nil
+}
+
 // GetCollection will find a previously created collection by name
 func GetCollection(name string) (*Collection, error) {
 	namePtr, nameLen, err := WriteString(name)
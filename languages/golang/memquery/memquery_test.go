@@ -30,6 +30,85 @@ func TestDeleteCollection(t *testing.T) {
 	assert.Nil(t, err, "cleanup failed")
 
 }
+func TestCreateExistingCollection(t *testing.T) {
+	_, err := CreateCollection("Test")
+	assert.Nil(t, err, "create collection failed")
+
+	_, err = CreateCollection("Test")
+	assert.NotNil(t, err, "create existing collection should fail")
+	assert.Equal(t, 48, err.(*ResultError).Code)
+
+	_, err = ReplaceCollection("Test")
+	assert.Nil(t, err, "replace collection failed")
+
+	err = DeleteCollection("Test")
+	assert.Nil(t, err, "cleanup failed")
+}
+
+func TestRenameCollection(t *testing.T) {
+	_, err := CreateCollection("Test")
+	assert.Nil(t, err, "create collection failed")
+
+	err = RenameCollection("Test", "Renamed", false)
+	assert.Nil(t, err, "rename collection failed")
+
+	exists, err := CollectionExists("Test")
+	assert.Nil(t, err)
+	assert.False(t, exists)
+
+	names, err := ListCollectionNames()
+	assert.Nil(t, err)
+	assert.Equal(t, []string{"Renamed"}, names)
+
+	err = DeleteCollection("Renamed")
+	assert.Nil(t, err, "cleanup failed")
+}
+
+func TestCountDocuments(t *testing.T) {
+	c, err := CreateCollection("Test")
+	assert.Nil(t, err, "create collection failed")
+
+	for _, age := range []float64{20, 30, 40} {
+		err = c.Insert(M{"age": age})
+		assert.Nil(t, err, "insert doc failed")
+	}
+
+	count, err := c.CountDocuments(M{"age": M{"$gt": 25}}, nil)
+	assert.Nil(t, err, "count documents failed")
+	assert.Equal(t, 2, count)
+
+	limit := 1
+	count, err = c.CountDocuments(M{}, &CountOptions{Skip: 1, Limit: &limit})
+	assert.Nil(t, err, "count documents failed")
+	assert.Equal(t, 1, count)
+
+	count, err = c.EstimatedDocumentCount()
+	assert.Nil(t, err, "estimated document count failed")
+	assert.Equal(t, 3, count)
+
+	err = DeleteCollection("Test")
+	assert.Nil(t, err, "cleanup failed")
+}
+
+func TestCollectionStats(t *testing.T) {
+	c, err := CreateCollection("Test")
+	assert.Nil(t, err, "create collection failed")
+
+	err = c.Insert(M{"name": "Ram", "age": float64(20)})
+	assert.Nil(t, err, "insert doc failed")
+
+	stats, err := c.Stats()
+	assert.Nil(t, err, "collection stats failed")
+	assert.Equal(t, float64(1), stats["count"])
+
+	dbStats, err := Stats()
+	assert.Nil(t, err, "stats failed")
+	assert.Equal(t, float64(1), dbStats["objects"])
+
+	err = DeleteCollection("Test")
+	assert.Nil(t, err, "cleanup failed")
+}
+
 func TestGetNonExistentCollection(t *testing.T) {
 	_, err := GetCollection("TestNonExistent")
 	assert.Equal(t, ErrCollectionNotFound, err, "get collection failed")
@@ -1,3 +1,3 @@
-from errors.errors import CreateCollectionFailed, InsertDocumentFailed, FindCollectionError;
+from errors.errors import CreateCollectionFailed, InsertDocumentFailed, FindCollectionError, RenameCollectionFailed;
 
-__all__ = ['CreateCollectionFailed', 'InsertDocumentFailed', 'FindCollectionError']
\ No newline at end of file
+__all__ = ['CreateCollectionFailed', 'InsertDocumentFailed', 'FindCollectionError', 'RenameCollectionFailed']
\ No newline at end of file
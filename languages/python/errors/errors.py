@@ -5,4 +5,7 @@ class InsertDocumentFailed(Exception):
   pass
 
 class FindCollectionError(Exception):
-  pass
\ No newline at end of file
+  pass
+
+class RenameCollectionFailed(Exception):
+  pass
@@ -1,8 +1,14 @@
 from memquery.memquery import Collection, create_collection,\
-    collection
+    replace_collection, list_collection_names, collection_exists,\
+    rename_collection, collection, stats
 
 __all__ = [
     'Collection',
     'create_collection',
-    'collection'
-]    
\ No newline at end of file
+    'replace_collection',
+    'list_collection_names',
+    'collection_exists',
+    'rename_collection',
+    'collection',
+    'stats'
+]
@@ -12,7 +12,8 @@ in memory document store.
 import wasmtime.loader
 import wsmemquery as instance
 import json
-from errors import CreateCollectionFailed, InsertDocumentFailed, FindCollectionError
+from errors import CreateCollectionFailed, InsertDocumentFailed, FindCollectionError,\
+    RenameCollectionFailed
 from membind import write_str, linear_mem_addr, ptr_to_str, result_ptr_to_value
 
 # memquery API
@@ -164,6 +165,80 @@ class Collection:
 
         return res_json
 
+    def count_documents(self, query, skip=0, limit=None):
+        """Count documents that match query without returning them.
+
+        Args:
+          query:
+            JSON object that specifies query criteria.
+          skip:
+            Number of matching documents that are not counted.
+          limit:
+            Maximum number of documents counted after skipped ones.
+
+        Returns:
+          Number of matching documents.  Throws exception if there was
+          a problem evaluating query.
+        """
+        name_ptr, name_len = write_str(instance, self._name)
+        query_ptr, query_len = write_str(instance, json.dumps(query))
+        options = {'skip': skip}
+        if limit is not None:
+            options['limit'] = limit
+        options_ptr, options_len = write_str(instance, json.dumps(options))
+
+        try:
+            res_ptr = instance.count_documents(
+                name_ptr, name_len, query_ptr, query_len, options_ptr, options_len)
+            count, err = result_ptr_to_value(
+                linear_mem_addr(instance), res_ptr)
+            if err is not None:
+                raise FindCollectionError(err)
+        except Exception as e:
+            raise FindCollectionError(e)
+
+        return count
+
+    def estimated_document_count(self):
+        """Number of documents in this collection, without evaluating a query.
+
+        Returns:
+          Number of documents stored in collection.
+        """
+        name_ptr, name_len = write_str(instance, self._name)
+
+        try:
+            res_ptr = instance.estimated_document_count(name_ptr, name_len)
+            count, err = result_ptr_to_value(
+                linear_mem_addr(instance), res_ptr)
+            if err is not None:
+                raise FindCollectionError(err)
+        except Exception as e:
+            raise FindCollectionError(e)
+
+        return count
+
+    def stats(self):
+        """Statistics of this collection.
+
+        Returns:
+          JSON object with number of documents (count), their approximate
+          memory size in bytes (size, avgObjSize), index sizes (totalIndexSize,
+          indexSizes) and operation counters (operations).
+        """
+        name_ptr, name_len = write_str(instance, self._name)
+
+        try:
+            res_ptr = instance.collection_stats(name_ptr, name_len)
+            res_json, err = result_ptr_to_value(
+                linear_mem_addr(instance), res_ptr)
+            if err is not None:
+                raise FindCollectionError(err)
+        except Exception as e:
+            raise FindCollectionError(e)
+
+        return res_json
+
 
 def create_collection(name):
     """Create new collection.
@@ -178,11 +253,101 @@ def create_collection(name):
     name_ptr, name_len = write_str(instance, name)
 
     try:
-        instance.create_collection(name_ptr, name_len)
+        res_ptr = instance.create_collection(name_ptr, name_len)
+        _, err = result_ptr_to_value(linear_mem_addr(instance), res_ptr)
+        if err is not None:
+            raise CreateCollectionFailed(err)
     except Exception as e:
         raise CreateCollectionFailed(e)
 
 
+def replace_collection(name):
+    """Create new collection, replacing existing collection with the same name.
+
+    Args:
+      name:
+        Name of the collection.
+
+    Returns:
+      None on success or throws error if collection could not be created.
+    """
+    name_ptr, name_len = write_str(instance, name)
+
+    try:
+        res_ptr = instance.replace_collection(name_ptr, name_len)
+        _, err = result_ptr_to_value(linear_mem_addr(instance), res_ptr)
+        if err is not None:
+            raise CreateCollectionFailed(err)
+    except Exception as e:
+        raise CreateCollectionFailed(e)
+
+
+def list_collection_names():
+    """Names of all collections.
+
+    Returns:
+      Sorted list of collection names.
+    """
+    res_ptr = instance.list_collection_names()
+    names, _ = result_ptr_to_value(linear_mem_addr(instance), res_ptr)
+    return names
+
+
+def stats():
+    """Statistics of all collections together.
+
+    Returns:
+      JSON object with number of collections, documents (objects), their
+      approximate memory size in bytes (dataSize, avgObjSize), number and size
+      of indexes (indexes, indexSize) and operation counters (operations).
+    """
+    res_ptr = instance.stats()
+    res_json, _ = result_ptr_to_value(linear_mem_addr(instance), res_ptr)
+    return res_json
+
+
+def collection_exists(name):
+    """Check if collection exists.
+
+    Args:
+      name:
+        Name of the collection.
+
+    Returns:
+      True if collection with this name exists.
+    """
+    name_ptr, name_len = write_str(instance, name)
+    return instance.collection_exists(name_ptr, name_len) != 0
+
+
+def rename_collection(from_name, to_name, drop_target=False):
+    """Rename collection.
+
+    Args:
+      from_name:
+        Current name of the collection.
+      to_name:
+        New name of the collection.
+      drop_target:
+        Replace existing collection named to_name, otherwise renaming
+        to existing collection raises error.
+
+    Returns:
+      None on success or throws error if collection could not be renamed.
+    """
+    from_ptr, from_len = write_str(instance, from_name)
+    to_ptr, to_len = write_str(instance, to_name)
+
+    try:
+        res_ptr = instance.rename_collection(
+            from_ptr, from_len, to_ptr, to_len, 1 if drop_target else 0)
+        _, err = result_ptr_to_value(linear_mem_addr(instance), res_ptr)
+        if err is not None:
+            raise RenameCollectionFailed(err)
+    except Exception as e:
+        raise RenameCollectionFailed(e)
+
+
 def collection(name):
     """Get Collection object.
 
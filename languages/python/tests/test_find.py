@@ -1,11 +1,11 @@
 from unittest import TestCase
-from memquery import Collection, create_collection,\
+from memquery import Collection, replace_collection,\
     collection
 
 
 class TestFindAPI(TestCase):
     def test_create_collection(self):
-        create_collection('TestCollection')
+        replace_collection('TestCollection')
         test_coll = None
         try:
             test_coll = collection('TestCollection')
@@ -15,7 +15,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(test_coll is not None)
 
     def test_create_collection_not_found(self):
-        create_collection('TestCollection')
+        replace_collection('TestCollection')
         test_coll = None
         try:
             _ = collection('TestCollection1')
@@ -25,7 +25,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(test_coll is None)
 
     def test_simple_query(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -36,8 +36,19 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs), 1);
         self.assertTrue(docs[0]["name"] == "Bob");
 
+    def test_count_documents(self):
+        replace_collection("TestCollection")
+        coll = collection("TestCollection")
+        coll.insert({ "name": "Rob", "age": 25 })
+        coll.insert({ "name": "Bob", "age": 20 })
+        coll.insert({ "name": "Tom", "age": 30 })
+
+        self.assertEqual(coll.count_documents({"age": {"$gte": 25}}), 2)
+        self.assertEqual(coll.count_documents({}, skip=1, limit=1), 1)
+        self.assertEqual(coll.estimated_document_count(), 3)
+
     def test_simple_query_with_multiple_conditions(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -50,7 +61,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[0]["name"] == "Bob")
 
     def test_nomatch_query_with_multiple_conditions(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -61,7 +72,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_query_match_with_and(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -73,7 +84,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[0]["name"] == "Bob")
     
     def test_query_nomatch_with_and(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -84,7 +95,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_query_match_with_or(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -95,7 +106,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 2)
     
     def test_query_nomatch_with_or(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -106,7 +117,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_eq_op(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -121,7 +132,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[1]["item"]["name"] == "mn")
     
     def test_eq_nomatch_op(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -134,7 +145,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_eq_op_single_entry_embedded_doc(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -148,7 +159,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[0]["item"]["name"] == "ab")
     
     def test_eq_op_to_match_array_to_array(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -163,7 +174,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[1]["item"]["name"] == "mn")
     
     def test_eq_op_to_nomatch_array_to_array(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -176,7 +187,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_eq_op_to_match_array_to_value(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -193,7 +204,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[3]["item"]["name"] == "xy")
     
     def test_gt_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -208,7 +219,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[1]["item"]["name"] == "xy")
     
     def test_gt_no_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -221,7 +232,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_gt_match_embedded_doc(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": 123 }, "qty": 20, "tags": [ "B" ] })
@@ -236,7 +247,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[1]["item"]["name"] == "xy")
     
     def test_gte_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -253,7 +264,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[3]["item"]["name"] == "mn")
     
     def test_gte_no_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -266,7 +277,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
 
     def test_gte_match_embedded_doc(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": 123 }, "qty": 20, "tags": [ "B" ] })
@@ -281,7 +292,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[1]["item"]["name"] == "xy")
     
     def test_lt_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -295,7 +306,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[0]["item"]["name"] == "ab")
     
     def test_lt_no_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -308,7 +319,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_lt_match_embedded_doc(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": 123 }, "qty": 20, "tags": [ "B" ] })
@@ -324,7 +335,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[2]["item"]["name"] == "mn")
     
     def test_lte_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -340,7 +351,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(docs[2]["item"]["name"] == "mn")
     
     def test_lte_no_match(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": "123" }, "qty": 20, "tags": [ "B" ] })
@@ -353,7 +364,7 @@ class TestFindAPI(TestCase):
         self.assertTrue(len(docs) == 0)
     
     def test_lte_match_embedded_doc(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] })
         coll.insert({ "item": { "name": "cd", "code": 123 }, "qty": 20, "tags": [ "B" ] })
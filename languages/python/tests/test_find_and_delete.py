@@ -1,11 +1,11 @@
 from unittest import TestCase
-from memquery import Collection, create_collection,\
+from memquery import Collection, replace_collection,\
     collection
 
 
 class TestFindAndDeleteAPI(TestCase):
     def test_simple_delete(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -20,7 +20,7 @@ class TestFindAndDeleteAPI(TestCase):
         self.assertTrue(len(docs_remaining) == 2)
 
     def test_delete_all_docs(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
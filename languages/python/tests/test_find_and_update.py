@@ -1,11 +1,11 @@
 from unittest import TestCase
-from memquery import Collection, create_collection,\
+from memquery import Collection, replace_collection,\
     collection
 
 
 class TestFindAndUpdateAPI(TestCase):
     def test_simple_update(self):
-        create_collection("TestCollection");
+        replace_collection("TestCollection");
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -23,7 +23,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0]["voice"] == "meow")
 
     def test_set_op_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -42,7 +42,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0]["email"] == "test@test.com")
     
     def test_set_op_invalid_value_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -59,7 +59,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue("should get error" == "no error")
 
     def test_set_op_invalid_value_embedded_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -76,7 +76,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue("should get error" == "no error")
 
     def test_unset_op_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -107,7 +107,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0].get("email", None) == None)
 
     def test_set_op_on_embedded_doc_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert(
             { "name": "Rob", "age": 25, "profile": { "email": "rob@test.com" } })
@@ -128,7 +128,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0]["profile"]["email"] == "tom@test.com")
 
     def test_unset_op_on_embedded_doc_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25, "profile": { "email": "rob@test.com" } })
         coll.insert({ "name": "Bob", "age": 20, "profile": { "email": "bob@test.com" }  })
@@ -157,7 +157,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0]["profile"].get("email", None) == None)
 
     def test_inc_positive_op_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -173,7 +173,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0]["age"] == 25.0)
 
     def test_inc_negative_op_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -189,7 +189,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0]["age"] == 15.0)
 
     def test_mul_positive_op_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
@@ -205,7 +205,7 @@ class TestFindAndUpdateAPI(TestCase):
         self.assertTrue(docs[0]["age"] == 100.0)
 
     def test_mul_negative_op_update(self):
-        create_collection("TestCollection")
+        replace_collection("TestCollection")
         coll = collection("TestCollection")
         coll.insert({ "name": "Rob", "age": 25 })
         coll.insert({ "name": "Bob", "age": 20 })
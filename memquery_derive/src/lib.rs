@@ -0,0 +1,326 @@
+//! Derive macro for MemQuery documents.
+//!
+//! See `memquery::Document` for description of generated code and supported attributes.
+//!
+
+use proc_macro::TokenStream;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+  meta::ParseNestedMeta, parenthesized, parse_macro_input, punctuated::Punctuated, Attribute, Data,
+  DeriveInput, Error, Fields, Ident, LitStr, Result, Token, Type,
+};
+
+const ID_FIELD: &str = "_id";
+
+#[proc_macro_derive(Document, attributes(document))]
+pub fn derive_document(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand(input)
+    .unwrap_or_else(|e| e.to_compile_error())
+    .into()
+}
+
+#[derive(Default)]
+struct IndexAttr {
+  keys: Vec<String>,
+  name: Option<String>,
+  unique: bool,
+  sparse: bool,
+}
+
+struct DocumentField {
+  ident: Ident,
+  ty: Type,
+  serialized_name: String,
+  is_id: bool,
+  skipped: bool,
+  index: Option<IndexAttr>,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+  let fields = match &input.data {
+    Data::Struct(s) => match &s.fields {
+      Fields::Named(named) => &named.named,
+      _ => {
+        return Err(Error::new_spanned(
+          &input.ident,
+          "Document can only be derived for structs with named fields",
+        ))
+      }
+    },
+    _ => {
+      return Err(Error::new_spanned(
+        &input.ident,
+        "Document can only be derived for structs",
+      ))
+    }
+  };
+
+  let mut collection_name = input.ident.to_string();
+  let mut indexes: Vec<IndexAttr> = Vec::new();
+  for attr in document_attrs(&input.attrs) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("collection") {
+        collection_name = meta.value()?.parse::<LitStr>()?.value();
+        Ok(())
+      } else if meta.path.is_ident("index") {
+        let index = parse_index(&meta)?;
+        if index.keys.is_empty() {
+          return Err(meta.error("index on struct requires keys(\"field\", ...)"));
+        }
+        indexes.push(index);
+        Ok(())
+      } else {
+        Err(meta.error("unsupported document attribute"))
+      }
+    })?;
+  }
+
+  let rename_all = serde_rename_all(&input.attrs)?;
+  let mut document_fields = Vec::new();
+  for field in fields {
+    let ident = field.ident.clone().expect("named field");
+    let (rename, skipped) = serde_field_attrs(&field.attrs)?;
+    let serialized_name = rename.unwrap_or_else(|| rename_field(&unraw(&ident), &rename_all));
+    let mut document_field = DocumentField {
+      ident,
+      ty: field.ty.clone(),
+      serialized_name,
+      is_id: false,
+      skipped,
+      index: None,
+    };
+
+    for attr in document_attrs(&field.attrs) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("id") {
+          document_field.is_id = true;
+          Ok(())
+        } else if meta.path.is_ident("index") {
+          let mut index = parse_index(&meta)?;
+          if !index.keys.is_empty() {
+            return Err(meta.error("keys are only allowed in index declared on struct"));
+          }
+          index.keys.push(document_field.serialized_name.clone());
+          document_field.index = Some(index);
+          Ok(())
+        } else {
+          Err(meta.error("unsupported document attribute"))
+        }
+      })?;
+    }
+
+    document_fields.push(document_field);
+  }
+
+  let explicit_ids = document_fields.iter().filter(|f| f.is_id).count();
+  if explicit_ids > 1 {
+    return Err(Error::new_spanned(
+      &input.ident,
+      "only one field can be marked with #[document(id)]",
+    ));
+  }
+  if explicit_ids == 0 {
+    let implicit_id = document_fields
+      .iter()
+      .position(|f| f.serialized_name == ID_FIELD)
+      .or_else(|| document_fields.iter().position(|f| f.ident == "id"));
+    if let Some(position) = implicit_id {
+      document_fields[position].is_id = true;
+    }
+  }
+
+  let id_field = document_fields
+    .iter()
+    .find(|f| f.is_id && !f.skipped)
+    .map(|f| f.serialized_name.clone())
+    .unwrap_or_else(|| ID_FIELD.to_string());
+
+  for field in document_fields.iter_mut().filter(|f| f.is_id) {
+    if let Some(index) = field.index.as_mut() {
+      index.keys = vec![ID_FIELD.to_string()];
+    }
+  }
+
+  let field_indexes = document_fields.iter().filter_map(|f| f.index.as_ref());
+  let index_models: Vec<TokenStream2> = field_indexes
+    .chain(indexes.iter())
+    .map(index_model)
+    .collect();
+
+  let constants = document_fields.iter().filter(|f| !f.skipped).map(|f| {
+    let const_name = format_ident!(
+      "{}",
+      unraw(&f.ident).trim_start_matches('_').to_uppercase(),
+      span = f.ident.span()
+    );
+    let ty = &f.ty;
+    let path = if f.is_id {
+      ID_FIELD.to_string()
+    } else {
+      f.serialized_name.clone()
+    };
+    let doc = format!("Path to `{}` field.", path);
+    quote! {
+      #[doc = #doc]
+      pub const #const_name: ::memquery::builder::Field<Self, #ty> =
+        ::memquery::builder::Field::new(#path);
+    }
+  });
+
+  let name = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics #name #ty_generics #where_clause {
+      #(#constants)*
+    }
+
+    impl #impl_generics ::memquery::Document for #name #ty_generics #where_clause {
+      const COLLECTION_NAME: &'static str = #collection_name;
+      const ID_FIELD: &'static str = #id_field;
+
+      fn indexes() -> ::std::vec::Vec<::memquery::index::IndexModel> {
+        ::std::vec![#(#index_models),*]
+      }
+    }
+  })
+}
+
+fn document_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+  attrs.iter().filter(|a| a.path().is_ident("document"))
+}
+
+fn parse_index(meta: &ParseNestedMeta) -> Result<IndexAttr> {
+  let mut index = IndexAttr::default();
+  if meta.input.is_empty() || meta.input.peek(Token![,]) {
+    return Ok(index);
+  }
+
+  meta.parse_nested_meta(|option| {
+    if option.path.is_ident("unique") {
+      index.unique = true;
+    } else if option.path.is_ident("sparse") {
+      index.sparse = true;
+    } else if option.path.is_ident("name") {
+      index.name = Some(option.value()?.parse::<LitStr>()?.value());
+    } else if option.path.is_ident("keys") {
+      let content;
+      parenthesized!(content in option.input);
+      let keys = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+      index.keys = keys.iter().map(|k| k.value()).collect();
+    } else {
+      return Err(option.error("unsupported index option"));
+    }
+    Ok(())
+  })?;
+  Ok(index)
+}
+
+fn index_model(index: &IndexAttr) -> TokenStream2 {
+  let keys = &index.keys;
+  let unique = index.unique;
+  let sparse = index.sparse;
+  let name = match &index.name {
+    Some(n) => quote!(::std::option::Option::Some(::std::string::String::from(#n))),
+    None => quote!(::std::option::Option::None),
+  };
+  quote! {
+    ::memquery::index::IndexModel {
+      keys: ::std::vec![#(::std::string::String::from(#keys)),*],
+      options: ::memquery::index::IndexOptions {
+        name: #name,
+        unique: #unique,
+        sparse: #sparse,
+        partial_filter_expression: ::std::option::Option::None,
+        expire_after: ::std::option::Option::None,
+      },
+    }
+  }
+}
+
+/// Reads `rename_all` from `#[serde(...)]` container attributes.
+fn serde_rename_all(attrs: &[Attribute]) -> Result<Option<String>> {
+  let mut rename_all = None;
+  for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("rename_all") && meta.input.peek(Token![=]) {
+        rename_all = Some(meta.value()?.parse::<LitStr>()?.value());
+        Ok(())
+      } else {
+        skip_meta(&meta)
+      }
+    })?;
+  }
+  Ok(rename_all)
+}
+
+/// Reads `rename` and skip options from `#[serde(...)]` field attributes.
+fn serde_field_attrs(attrs: &[Attribute]) -> Result<(Option<String>, bool)> {
+  let mut rename = None;
+  let mut skipped = false;
+  for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+        rename = Some(meta.value()?.parse::<LitStr>()?.value());
+        Ok(())
+      } else if meta.path.is_ident("skip")
+        || meta.path.is_ident("skip_serializing")
+        || meta.path.is_ident("flatten")
+      {
+        skipped = true;
+        skip_meta(&meta)
+      } else {
+        skip_meta(&meta)
+      }
+    })?;
+  }
+  Ok((rename, skipped))
+}
+
+/// Consumes serde option that is not relevant for documents.
+fn skip_meta(meta: &ParseNestedMeta) -> Result<()> {
+  while !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+    meta.input.parse::<TokenTree>()?;
+  }
+  Ok(())
+}
+
+fn unraw(ident: &Ident) -> String {
+  ident.to_string().trim_start_matches("r#").to_string()
+}
+
+/// Applies serde `rename_all` rule to snake case field name.
+fn rename_field(name: &str, rule: &Option<String>) -> String {
+  let rule = match rule {
+    Some(r) => r.as_str(),
+    None => return name.to_string(),
+  };
+
+  let capitalize = |word: &str| {
+    let mut chars = word.chars();
+    match chars.next() {
+      Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+      None => String::new(),
+    }
+  };
+
+  match rule {
+    "lowercase" => name.to_lowercase(),
+    "UPPERCASE" => name.to_uppercase(),
+    "PascalCase" => name.split('_').map(capitalize).collect(),
+    "camelCase" => {
+      let pascal: String = name.split('_').map(capitalize).collect();
+      let mut chars = pascal.chars();
+      match chars.next() {
+        Some(first) => first.to_lowercase().collect::<String>() + chars.as_str(),
+        None => pascal,
+      }
+    }
+    "SCREAMING_SNAKE_CASE" => name.to_uppercase(),
+    "kebab-case" => name.replace('_', "-"),
+    "SCREAMING-KEBAB-CASE" => name.to_uppercase().replace('_', "-"),
+    _ => name.to_string(),
+  }
+}
@@ -0,0 +1,611 @@
+//! Collection stores documents as JSON objects.
+//!
+#[cfg(feature = "bson")]
+use crate::bson_format::{from_bson, to_bson, BsonDocuments};
+use crate::{
+  blocking,
+  change_stream::{ChangeStream, WatchOptions},
+  clock::Clock,
+  engine::{CollectionData, DocumentCollection, Documents, Engine},
+  errors::Error,
+  id_generator::IdGenerator,
+  import_export::{
+    ExportFormat, ExportOptions, ImportOptions, ImportSummary, JsonArray, JsonLines,
+  },
+  index::{IndexDefinition, IndexOptions},
+  options::{CollectionOptions, CountOptions, FindOptions},
+  planner::ExplainOptions,
+  query::CompiledQuery,
+  snapshot::CollectionSnapshot,
+  stats::CollectionStats,
+  tailable::TailableStream,
+  view::CollectionView,
+};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::sync::{Arc, RwLock};
+
+/// Stores JSON documents.
+#[derive(Clone)]
+pub struct Collection {
+  pub data: DocumentCollection,
+}
+
+impl Collection {
+  /// Make a new collection.
+  pub fn new() -> Collection {
+    Collection {
+      data: Arc::new(RwLock::new(CollectionData::new())),
+    }
+  }
+
+  /// Make a new collection that assigns `_id` to inserted documents with specified generator.
+  ///
+  /// ```
+  /// use memquery::{id_generator::AutoIncrement, r#async::Collection};
+  /// use std::sync::Arc;
+  ///
+  /// let coll = Collection::with_id_generator(Arc::new(AutoIncrement::new()));
+  /// ```
+  pub fn with_id_generator(id_generator: Arc<dyn IdGenerator>) -> Collection {
+    Collection {
+      data: Arc::new(RwLock::new(CollectionData::with_id_generator(id_generator))),
+    }
+  }
+
+  /// Same collection that reads current time for TTL indexes from `clock`.
+  pub fn with_clock(self, clock: Arc<dyn Clock>) -> Collection {
+    self.data.write().unwrap().set_clock(clock);
+    self
+  }
+
+  pub(crate) fn engine(&self) -> Engine {
+    Engine::with_collection(self.data.clone())
+  }
+
+  /// Insert new document (async).
+  ///
+  /// Document without `_id` field gets one from collection's `IdGenerator`.
+  /// Returns `MQDuplicateKey` error if document violates unique index.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn insert(&self, document: Value) -> Result<(), Error> {
+    self.engine().insert(document)
+  }
+
+  /// Return documents that match specified criteria (async).
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let docs = coll.find(query!({"name": "Tom", "age": 25})).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn find(&self, query: Value) -> Result<Documents, Error> {
+    self.engine().find(&CompiledQuery::new(&query)?)
+  }
+
+  /// Return first document that matches specified criteria (async).
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let doc = coll.find_one(query!({"name": "Tom"})).await?;
+  ///   assert!(doc.is_some());
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn find_one(&self, query: Value) -> Result<Option<Value>, Error> {
+    self.engine().find_one(&CompiledQuery::new(&query)?)
+  }
+
+  /// Return documents that match query in insertion order or newest first (async).
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, options::FindOptions, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Events").await?;
+  ///   let events = memdb.collection("Events").await?;
+  ///   events.insert(doc!({ "n": 1 })).await?;
+  ///   events.insert(doc!({ "n": 2 })).await?;
+  ///   let options = FindOptions { newest_first: true, limit: Some(1) };
+  ///   let last = events.find_with_options(query!({}), options).await?;
+  ///   assert_eq!(last[0]["n"], 2);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn find_with_options(
+    &self,
+    query: Value,
+    options: FindOptions,
+  ) -> Result<Documents, Error> {
+    self
+      .engine()
+      .find_with_options(&CompiledQuery::new(&query)?, &options)
+  }
+
+  /// Count documents that match query without copying them (async).
+  ///
+  /// `options.skip` matching documents are not counted and at most `options.limit` are.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, options::CountOptions, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   for age in 20..30 {
+  ///     coll.insert(doc!({ "age": age })).await?;
+  ///   }
+  ///   let adults = query!({ "age": { "$gte": 21 } });
+  ///   let options = CountOptions { skip: 5, limit: Some(3) };
+  ///   assert_eq!(coll.count_documents(adults, options).await?, 3);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn count_documents(&self, query: Value, options: CountOptions) -> Result<u64, Error> {
+    self
+      .engine()
+      .count_documents(&CompiledQuery::new(&query)?, &options)
+  }
+
+  /// Number of documents in collection without evaluating any query (async).
+  ///
+  /// Takes constant time.  Documents expired by TTL index are counted until they are removed.
+  pub async fn estimated_document_count(&self) -> u64 {
+    self.engine().estimated_document_count()
+  }
+
+  /// Return documents that match query compiled with `CompiledQuery::new` (async).
+  ///
+  /// Compiled query is validated once and can be reused across calls.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query, query::CompiledQuery};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let adults = CompiledQuery::new(&query!({ "age": { "$gte": 21 } }))?;
+  ///   let docs = coll.find_compiled(&adults).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn find_compiled(&self, query: &CompiledQuery) -> Result<Documents, Error> {
+    self.engine().find(query)
+  }
+
+  /// Updates documents that match search criteria.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query, update};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let docs_updated = coll
+  ///     .find_and_update(
+  ///     query!({"name": "Tom"}),
+  ///     update!({"nickname": "Bobcat", "voice": "meow"}),
+  ///   )
+  ///   .await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn find_and_update(&self, query: Value, update: Value) -> Result<u64, Error> {
+    self
+      .engine()
+      .find_and_update(&CompiledQuery::new(&query)?, &update)
+  }
+
+  /// Delete documents that match search criteria.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let docs = coll.find_and_delete(query!({"name": "Tom"})).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn find_and_delete(&self, query: Value) -> Result<Documents, Error> {
+    self.engine().find_and_delete(&CompiledQuery::new(&query)?)
+  }
+
+  /// Creates index on specified keys and returns its name (async).
+  ///
+  /// Unique index rejects inserts and updates that would create duplicate key.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, index::IndexOptions, r#async::MemDb};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll
+  ///     .create_index(&["email"], IndexOptions { unique: true, ..Default::default() })
+  ///     .await?;
+  ///   coll.insert(doc!({ "name": "Tom", "email": "tom@test.com" })).await?;
+  ///   assert!(coll.insert(doc!({ "name": "Bob", "email": "tom@test.com" })).await.is_err());
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn create_index(&self, keys: &[&str], options: IndexOptions) -> Result<String, Error> {
+    self.engine().create_index(keys, options)
+  }
+
+  /// Drops index by name (async).  The `_id_` index cannot be dropped.
+  pub async fn drop_index(&self, name: &str) -> Result<(), Error> {
+    self.engine().drop_index(name)
+  }
+
+  /// Removes documents expired by TTL indexes and returns their number (async).
+  ///
+  /// Expired documents are not returned by queries even before they are removed, and every
+  /// write removes expired documents first, see `index` module.
+  ///
+  /// ```
+  /// use memquery::{clock::ManualClock, doc, errors::Error, extended_json::date, index::IndexOptions, r#async::MemDb, query};
+  /// use std::sync::Arc;
+  /// use std::time::Duration;
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let clock = Arc::new(ManualClock::new(0));
+  ///   let memdb = MemDb::new().with_clock(clock.clone());
+  ///   memdb.create_collection("Sessions").await?;
+  ///   let sessions = memdb.collection("Sessions").await?;
+  ///   let options = IndexOptions { expire_after: Some(Duration::from_secs(60)), ..Default::default() };
+  ///   sessions.create_index(&["lastSeen"], options).await?;
+  ///   sessions.insert(doc!({ "user": "Tom", "lastSeen": date(0) })).await?;
+  ///
+  ///   clock.advance(Duration::from_secs(60));
+  ///   assert!(sessions.find(query!({})).await?.is_empty());
+  ///   assert_eq!(sessions.purge_expired().await?, 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn purge_expired(&self) -> Result<u64, Error> {
+    self.engine().purge_expired()
+  }
+
+  /// Returns definitions of all indexes on collection (async).
+  pub async fn list_indexes(&self) -> Vec<IndexDefinition> {
+    self.engine().list_indexes()
+  }
+
+  /// Returns number of documents, their approximate memory size, sizes of indexes and
+  /// counters of operations, see `stats` module (async).
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   coll.find(query!({ "age": 25 })).await?;
+  ///
+  ///   let stats = coll.stats().await;
+  ///   assert_eq!(stats.count, 1);
+  ///   assert_eq!(stats.operations.queries, 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn stats(&self) -> CollectionStats {
+    self.engine().stats()
+  }
+
+  /// Returns immutable point-in-time view of collection (async).
+  ///
+  /// View is taken in constant time and can be queried and exported while writers
+  /// change the collection, it does not see their changes.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let view = coll.view().await;
+  ///   coll.insert(doc!({ "name": "Bob", "age": 30 })).await?;
+  ///   assert_eq!(view.find(query!({}))?.len(), 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn view(&self) -> CollectionView {
+    self.engine().view()
+  }
+
+  /// Opens tailable cursor over capped collection (async).
+  ///
+  /// Cursor yields documents that match filter in insertion order, then waits for new
+  /// inserted documents, see `tailable` module.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, options::{Capped, CollectionOptions}, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   let capped = Capped { max_docs: Some(100), max_bytes: None };
+  ///   memdb.create_collection_with_options("Events", CollectionOptions { capped: Some(capped), ..Default::default() }).await?;
+  ///   let events = memdb.collection("Events").await?;
+  ///   events.insert(doc!({ "n": 1 })).await?;
+  ///   let mut cursor = events.tail(query!({})).await?;
+  ///   events.insert(doc!({ "n": 2 })).await?;
+  ///   assert_eq!(cursor.next_document().await?["n"], 1);
+  ///   assert_eq!(cursor.next_document().await?["n"], 2);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn tail(&self, filter: Value) -> Result<TailableStream, Error> {
+    let (documents, subscription) = self.engine().tail(CompiledQuery::new(&filter)?)?;
+    Ok(TailableStream::new(documents, subscription))
+  }
+
+  /// Options collection was created with (async).
+  pub async fn options(&self) -> CollectionOptions {
+    self.data.read().unwrap().options().clone()
+  }
+
+  /// Watches inserts, updates and deletes of documents that match filter (async).
+  ///
+  /// Filter is matched against inserted or updated document, or deleted document for
+  /// deletes.  Stream gets changes made after it was created and ends when collection is
+  /// deleted, see `change_stream` module.
+  ///
+  /// ```
+  /// use memquery::{change_stream::OperationType, doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   let mut changes = coll.watch(query!({ "age": { "$gt": 20 } })).await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let event = changes.recv().await?;
+  ///   assert_eq!(event.operation_type, OperationType::Insert);
+  ///   assert_eq!(event.full_document.unwrap()["name"], "Tom");
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn watch(&self, filter: Value) -> Result<ChangeStream, Error> {
+    self
+      .watch_with_options(filter, WatchOptions::default())
+      .await
+  }
+
+  /// Watches changes like `watch` (async), `options.resume_after` continues after earlier
+  /// event.
+  pub async fn watch_with_options(
+    &self,
+    filter: Value,
+    options: WatchOptions,
+  ) -> Result<ChangeStream, Error> {
+    let subscription = self.engine().watch(CompiledQuery::new(&filter)?, options)?;
+    Ok(ChangeStream::new(subscription))
+  }
+
+  /// Returns copy of index definitions (except `_id_`) and documents (async), see `view`
+  /// for point-in-time view that can be queried.
+  pub async fn snapshot(&self) -> CollectionSnapshot {
+    self.engine().snapshot()
+  }
+
+  /// Imports documents from JSON Lines input, one document per line as written by
+  /// `mongoexport` (async).
+  ///
+  /// ```
+  /// use memquery::{errors::Error, import_export::{ErrorPolicy, ImportOptions}, r#async::MemDb};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   let input = "{\"name\": \"Tom\"}\nnot json\n{\"name\": \"Bob\"}\n";
+  ///   let options = ImportOptions { on_error: ErrorPolicy::Skip, ..Default::default() };
+  ///   let summary = coll.import_jsonl(input.as_bytes(), options).await?;
+  ///   assert_eq!(summary.inserted, 2);
+  ///   assert_eq!(summary.skipped[0].line, 2);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn import_jsonl<R: Read>(
+    &self,
+    reader: R,
+    options: ImportOptions,
+  ) -> Result<ImportSummary, Error> {
+    self.engine().import(JsonLines::new(reader), options)
+  }
+
+  /// Imports documents from JSON array, as written by `mongoexport --jsonArray` (async).
+  pub async fn import_json_array<R: Read>(
+    &self,
+    reader: R,
+    options: ImportOptions,
+  ) -> Result<ImportSummary, Error> {
+    self.engine().import(JsonArray::new(reader), options)
+  }
+
+  /// Writes all documents as JSON Lines, one document per line (async).
+  ///
+  /// Returns number of exported documents.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, import_export::ExportOptions, r#async::MemDb};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom" })).await?;
+  ///   let mut output = Vec::new();
+  ///   coll.export_jsonl(&mut output, ExportOptions::default()).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn export_jsonl<W: Write>(
+    &self,
+    writer: W,
+    options: ExportOptions,
+  ) -> Result<u64, Error> {
+    self
+      .engine()
+      .export(writer, options, ExportFormat::JsonLines)
+  }
+
+  /// Writes all documents as JSON array (async).
+  ///
+  /// Returns number of exported documents.
+  pub async fn export_json_array<W: Write>(
+    &self,
+    writer: W,
+    options: ExportOptions,
+  ) -> Result<u64, Error> {
+    self
+      .engine()
+      .export(writer, options, ExportFormat::JsonArray)
+  }
+
+  /// Insert BSON document (`bson` feature, async).
+  ///
+  /// ObjectId, DateTime, Decimal128 and Binary values are stored as Extended JSON, see
+  /// `bson_format` module.
+  ///
+  /// ```
+  /// # #[cfg(feature = "bson")]
+  /// use memquery::{errors::Error, r#async::MemDb, query};
+  ///
+  /// # #[cfg(feature = "bson")]
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert_bson(bson::doc! { "name": "Tom", "joined": bson::DateTime::now() }).await?;
+  ///   let docs = coll.find_bson(query!({ "name": "Tom" })).await?;
+  ///   assert!(docs[0].get_datetime("joined").is_ok());
+  ///   Ok(())
+  /// }
+  /// ```
+  #[cfg(feature = "bson")]
+  pub async fn insert_bson(&self, document: bson::Document) -> Result<(), Error> {
+    self.insert(from_bson(document)?).await
+  }
+
+  /// Return documents that match specified criteria as BSON documents (`bson` feature,
+  /// async).
+  #[cfg(feature = "bson")]
+  pub async fn find_bson(&self, query: Value) -> Result<Vec<bson::Document>, Error> {
+    self.find(query).await?.iter().map(to_bson).collect()
+  }
+
+  /// Imports concatenated BSON documents, as in `.bson` file written by `mongodump`
+  /// (`bson` feature, async).
+  #[cfg(feature = "bson")]
+  pub async fn import_bson<R: Read>(
+    &self,
+    reader: R,
+    options: ImportOptions,
+  ) -> Result<ImportSummary, Error> {
+    self.engine().import(BsonDocuments::new(reader), options)
+  }
+
+  /// Writes all documents as concatenated BSON documents, the format of `.bson` files
+  /// written by `mongodump` (`bson` feature, async).
+  ///
+  /// Returns number of exported documents.
+  #[cfg(feature = "bson")]
+  pub async fn export_bson<W: Write>(
+    &self,
+    writer: W,
+    options: ExportOptions,
+  ) -> Result<u64, Error> {
+    self.engine().export(writer, options, ExportFormat::Bson)
+  }
+
+  /// Describes how query is executed (async).
+  ///
+  /// Returns plan chosen for the query (collection scan or index scan with bounds) and,
+  /// with `ExplainVerbosity::ExecutionStats`, number of documents and index keys examined,
+  /// number of documents returned, and execution time.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, index::IndexOptions, r#async::MemDb, planner::ExplainOptions, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.create_index(&["age"], IndexOptions::default()).await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let explain = coll
+  ///     .explain(query!({ "age": { "$gt": 20 } }), ExplainOptions::default())
+  ///     .await?;
+  ///   assert_eq!(explain["queryPlanner"]["winningPlan"]["inputStage"]["indexName"], "age_1");
+  ///   assert_eq!(explain["executionStats"]["nReturned"], 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn explain(&self, query: Value, options: ExplainOptions) -> Result<Value, Error> {
+    self.engine().explain(&CompiledQuery::new(&query)?, options)
+  }
+}
+
+impl Default for Collection {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Handle of the same collection for blocking API.
+impl From<Collection> for blocking::Collection {
+  fn from(collection: Collection) -> blocking::Collection {
+    blocking::Collection {
+      data: collection.data,
+    }
+  }
+}
+
+/// Handle of the same collection for async API.
+impl From<blocking::Collection> for Collection {
+  fn from(collection: blocking::Collection) -> Collection {
+    Collection {
+      data: collection.data,
+    }
+  }
+}
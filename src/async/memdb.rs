@@ -0,0 +1,559 @@
+//! MemDb allows creation, deletion and retrieval of collections of documents.
+//!
+//! Async API, see `blocking::MemDb` for blocking one.
+//!
+//! With `persistence` feature MemDb can be made durable with write-ahead log, see `wal` module.
+//! With `bson` feature it can be dumped to and restored from `mongodump` directory.
+//!
+
+use super::{Collection, TypedCollection};
+#[cfg(feature = "bson")]
+use crate::bson_format::{read_dump, write_dump};
+#[cfg(feature = "persistence")]
+use crate::wal::PersistenceOptions;
+use crate::{
+  clock::Clock,
+  database::Database,
+  document::Document,
+  errors::Error,
+  id_generator::{IdGenerator, UuidV4},
+  options::CollectionOptions,
+  snapshot::{read_async, write_atomically_async, Snapshot},
+  stats::DatabaseStats,
+  transaction::Transaction,
+  view::DatabaseView,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+
+/// Stores database collections.
+pub struct MemDb {
+  db: Database,
+}
+
+impl MemDb {
+  /// Makes a new database.
+  pub fn new() -> MemDb {
+    MemDb::with_id_generator(|| UuidV4)
+  }
+
+  /// Makes a new database whose collections assign `_id` with generators made by `factory`.
+  ///
+  /// Every collection gets its own generator, so for example `AutoIncrement` ids start at 1
+  /// in each collection.
+  ///
+  /// ```
+  /// use memquery::{id_generator::AutoIncrement, r#async::MemDb};
+  ///
+  /// let memdb = MemDb::with_id_generator(AutoIncrement::new);
+  /// ```
+  pub fn with_id_generator<F, G>(factory: F) -> MemDb
+  where
+    F: Fn() -> G + Send + Sync + 'static,
+    G: IdGenerator + 'static,
+  {
+    MemDb {
+      db: Database::with_id_generator(factory),
+    }
+  }
+
+  /// Same database that reads current time for TTL indexes from `clock`.
+  ///
+  /// ```
+  /// use memquery::{clock::ManualClock, r#async::MemDb};
+  /// use std::sync::Arc;
+  ///
+  /// let clock = Arc::new(ManualClock::new(0));
+  /// let memdb = MemDb::new().with_clock(clock.clone());
+  /// ```
+  pub fn with_clock(self, clock: Arc<dyn Clock>) -> MemDb {
+    MemDb {
+      db: self.db.with_clock(clock),
+    }
+  }
+
+  /// Removes documents expired by TTL indexes from all collections and returns their
+  /// number (async).  Expired documents are never returned by queries, and every write
+  /// removes expired documents of its collection, so calling this only frees memory sooner.
+  pub async fn purge_expired(&self) -> Result<u64, Error> {
+    self.db.purge_expired()
+  }
+
+  /// Spawns tokio task that removes expired documents every `period` (`tokio` feature).
+  ///
+  /// Task ends when database is dropped or returned handle is aborted.  Errors of durable
+  /// database are ignored, failed removal is retried in the next period.
+  ///
+  /// ```
+  /// use memquery::r#async::MemDb;
+  /// use std::time::Duration;
+  ///
+  /// async fn play() {
+  ///   let memdb = MemDb::new();
+  ///   let monitor = memdb.spawn_ttl_monitor(Duration::from_secs(60));
+  ///   monitor.abort();
+  /// }
+  /// ```
+  #[cfg(feature = "tokio")]
+  pub fn spawn_ttl_monitor(&self, period: Duration) -> tokio::task::JoinHandle<()> {
+    let collections = self.db.collections_weak();
+    tokio::spawn(async move {
+      loop {
+        tokio::time::sleep(period).await;
+        match collections.upgrade() {
+          Some(collections) => {
+            let _ = Database::purge_collections(&collections);
+          }
+          None => break,
+        }
+      }
+    })
+  }
+
+  /// Creates new collection (async).
+  ///
+  /// Returns `MQCollectionExists` if there already is collection with the same name, use
+  /// `replace_collection` to replace it.  In durable database collection is not created if
+  /// it cannot be written to the log.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   assert!(memdb.create_collection("TestCollection").await.is_err());
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn create_collection(&self, name: &str) -> Result<(), Error> {
+    self.db.create_collection(name)
+  }
+
+  /// Creates new collection with options (async), returns `MQCollectionExists` if there
+  /// already is collection with the same name.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, options::{Capped, CollectionOptions}};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   let capped = Capped { max_docs: Some(1000), max_bytes: None };
+  ///   memdb
+  ///     .create_collection_with_options("Events", CollectionOptions { capped: Some(capped), ..Default::default() }).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn create_collection_with_options(
+    &self,
+    name: &str,
+    options: CollectionOptions,
+  ) -> Result<(), Error> {
+    self.db.create_collection_with_options(name, options)
+  }
+
+  /// Retrieves collection by name (async).
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn collection(&self, collection_name: &str) -> Result<Collection, Error> {
+    self
+      .db
+      .collection(collection_name)
+      .map(|data| Collection { data })
+  }
+
+  /// Retrieves collection by name as typed collection (async).
+  ///
+  /// ```
+  /// use memquery::{errors::Error, r#async::MemDb};
+  /// use serde::{Deserialize, Serialize};
+  ///
+  /// #[derive(Serialize, Deserialize)]
+  /// struct User {
+  ///   name: String,
+  /// }
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Users").await?;
+  ///   let users = memdb.collection_typed::<User>("Users").await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn collection_typed<T>(
+    &self,
+    collection_name: &str,
+  ) -> Result<TypedCollection<T>, Error>
+  where
+    T: Serialize + DeserializeOwned,
+  {
+    Ok(TypedCollection::new(
+      self.collection(collection_name).await?,
+    ))
+  }
+
+  /// Creates collection for `Document` type together with its declared indexes (async).
+  ///
+  /// ```
+  /// use memquery::{errors::Error, r#async::MemDb, Document};
+  /// use serde::{Deserialize, Serialize};
+  ///
+  /// #[derive(Serialize, Deserialize, Document)]
+  /// struct User {
+  ///   #[document(index(unique))]
+  ///   email: String,
+  /// }
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   let users = memdb.create_collection_for::<User>().await?;
+  ///   let same_users = memdb.collection_for::<User>().await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn create_collection_for<T: Document>(&self) -> Result<TypedCollection<T>, Error> {
+    let data = self.db.create_collection_for::<T>()?;
+    Ok(TypedCollection::for_document(Collection { data }))
+  }
+
+  /// Retrieves collection for `Document` type (async).
+  pub async fn collection_for<T: Document>(&self) -> Result<TypedCollection<T>, Error> {
+    Ok(TypedCollection::for_document(
+      self.collection(T::COLLECTION_NAME).await?,
+    ))
+  }
+
+  /// Runs `f` in transaction over collections named in `names` (async).
+  ///
+  /// Named collections are locked in order of their names before `f` runs, so transactions
+  /// cannot deadlock and other handles of these collections neither see changes before
+  /// commit nor change documents that transaction reads.  Other collections stay
+  /// available.  If `f` returns error all its changes are rolled back, also if `f` panics.
+  /// In durable database changes are written to the log together on commit.
+  ///
+  /// Closure is not async, it runs while collections are locked and must use only
+  /// collections of `tx`.  Tasks that use locked collections meanwhile block their worker
+  /// threads, see `r#async` module.  Calling `Collection` handle of locked collection inside the
+  /// closure deadlocks, because the thread that runs the closure already holds the lock
+  /// that the call waits for.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query, update};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Stock").await?;
+  ///   memdb.create_collection("Orders").await?;
+  ///   let stock = memdb.collection("Stock").await?;
+  ///   stock.insert(doc!({ "item": "pen", "qty": 5 })).await?;
+  ///
+  ///   memdb.transaction(&["Stock", "Orders"], |tx| {
+  ///     let mut stock = tx.collection("Stock")?;
+  ///     let pen = stock.find_one(query!({ "item": "pen", "qty": { "$gte": 2 } }))?;
+  ///     if pen.is_none() {
+  ///       return Err(Error::MQError(String::from("Out of stock.")));
+  ///     }
+  ///     stock.find_and_update(query!({ "item": "pen" }), update!({ "$inc": { "qty": -2 } }))?;
+  ///     tx.collection("Orders")?.insert(doc!({ "item": "pen", "qty": 2 }))
+  ///   }).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn transaction<F, R>(&self, names: &[&str], f: F) -> Result<R, Error>
+  where
+    F: FnOnce(&mut Transaction) -> Result<R, Error>,
+  {
+    self.db.transaction(names, f)
+  }
+
+  /// Returns immutable point-in-time view of all collections (async).
+  ///
+  /// All collections are copied at the same moment in constant time.  View can be
+  /// queried while writers change the database and does not see their changes.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   let view = memdb.view().await;
+  ///   coll.find_and_delete(query!({})).await?;
+  ///   let docs = view.collection("TestCollection")?.find(query!({}))?;
+  ///   assert_eq!(docs.len(), 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn view(&self) -> DatabaseView {
+    self.db.view()
+  }
+
+  /// Saves all collections with their index definitions to versioned JSON snapshot (async).
+  ///
+  /// Snapshot is written to temporary file that is renamed over `path` once complete,
+  /// so existing snapshot is never left partially written.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   memdb.save_to("db.json").await?;
+  ///
+  ///   let restored = MemDb::new();
+  ///   restored.load_from("db.json").await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn save_to<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+    write_atomically_async(path.as_ref(), &self.db.snapshot().to_vec()?).await
+  }
+
+  /// Loads collections from snapshot created with `save_to` (async).
+  ///
+  /// Loaded collections replace collections with the same name.  If snapshot cannot
+  /// be read or is invalid database is not changed.  Durable database is compacted
+  /// afterwards so that loaded collections are stored in its snapshot.
+  pub async fn load_from<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+    let data = read_async(path.as_ref()).await?;
+    self.db.load(Snapshot::from_slice(&data)?)
+  }
+
+  /// Writes all collections to `dir` in `mongodump` layout (`bson` feature, async).
+  ///
+  /// Every collection is written to `<name>.bson` file and its indexes to
+  /// `<name>.metadata.json` file, see `bson_format` module.
+  ///
+  /// ```
+  /// # #[cfg(feature = "bson")]
+  /// use memquery::{doc, errors::Error, r#async::MemDb};
+  ///
+  /// # #[cfg(feature = "bson")]
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   memdb.dump_bson("dump/test").await?;
+  ///
+  ///   let restored = MemDb::new();
+  ///   restored.restore_bson("dump/test").await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  #[cfg(feature = "bson")]
+  pub async fn dump_bson<P: AsRef<Path>>(&self, dir: P) -> Result<(), Error> {
+    write_dump(dir.as_ref(), &self.db.snapshot())
+  }
+
+  /// Loads collections from `mongodump` directory (`bson` feature, async).
+  ///
+  /// Loaded collections replace collections with the same name.  If any file cannot be
+  /// read database is not changed.
+  #[cfg(feature = "bson")]
+  pub async fn restore_bson<P: AsRef<Path>>(&self, dir: P) -> Result<(), Error> {
+    self.db.load(read_dump(dir.as_ref())?)
+  }
+
+  /// Opens durable database stored in `options.dir` (async).
+  ///
+  /// Same as `MemDb::new().with_persistence(options)`.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, wal::{FsyncPolicy, PersistenceOptions}};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let mut options = PersistenceOptions::new("data");
+  ///   options.fsync = FsyncPolicy::EveryN(100);
+  ///   let memdb = MemDb::open(options).await?;
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+  ///   memdb.compact().await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  #[cfg(feature = "persistence")]
+  pub async fn open(options: PersistenceOptions) -> Result<MemDb, Error> {
+    MemDb::new().with_persistence(options).await
+  }
+
+  /// Makes database durable (async).
+  ///
+  /// Snapshot and log in `options.dir` are loaded first, then every change of collections,
+  /// their documents and indexes is appended to the log before it is applied.
+  /// Database must not have any collections yet.
+  #[cfg(feature = "persistence")]
+  pub async fn with_persistence(self, options: PersistenceOptions) -> Result<MemDb, Error> {
+    Ok(MemDb {
+      db: self.db.with_persistence(options)?,
+    })
+  }
+
+  /// Writes snapshot of durable database and removes log entries that it contains (async).
+  ///
+  /// Writes may continue while database is compacted.
+  #[cfg(feature = "persistence")]
+  pub async fn compact(&self) -> Result<(), Error> {
+    self.db.compact()
+  }
+
+  /// Delete collection by name (async).
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, query};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   let coll = memdb.collection("TestCollection").await?;
+  ///   let _ = memdb.delete_collection("TestCollection").await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn delete_collection(&self, name: &str) -> Result<Collection, Error> {
+    self
+      .db
+      .delete_collection(name)
+      .map(|data| Collection { data })
+  }
+
+  /// Creates new collection with options (async), replacing existing collection with the
+  /// same name and all its documents.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb, options::CollectionOptions};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection").await?;
+  ///   memdb.replace_collection("TestCollection", CollectionOptions::default()).await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn replace_collection(
+    &self,
+    name: &str,
+    options: CollectionOptions,
+  ) -> Result<(), Error> {
+    self.db.replace_collection(name, options)
+  }
+
+  /// Names of all collections, sorted (async).
+  ///
+  /// ```
+  /// use memquery::{errors::Error, r#async::MemDb};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Users").await?;
+  ///   memdb.create_collection("Orders").await?;
+  ///   assert_eq!(memdb.list_collection_names().await, vec!["Orders", "Users"]);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn list_collection_names(&self) -> Vec<String> {
+    self.db.collection_names()
+  }
+
+  /// Returns statistics of all collections together, see `stats` module (async).
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Users").await?;
+  ///   memdb.create_collection("Orders").await?;
+  ///   memdb.collection("Users").await?.insert(doc!({ "name": "Tom" })).await?;
+  ///
+  ///   let stats = memdb.stats().await;
+  ///   assert_eq!(stats.collections, 2);
+  ///   assert_eq!(stats.objects, 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn stats(&self) -> DatabaseStats {
+    self.db.stats()
+  }
+
+  /// Returns true if there is collection with the name (async).
+  pub async fn collection_exists(&self, name: &str) -> bool {
+    self.db.collection_exists(name)
+  }
+
+  /// Renames collection (async).  Handles of collection stay valid and see the new name.
+  ///
+  /// Returns `MQCollectionExists` if collection named `to` exists, unless `drop_target` is
+  /// set, in which case that collection is deleted.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, r#async::MemDb};
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Staging").await?;
+  ///   memdb.create_collection("Users").await?;
+  ///   memdb.rename_collection("Staging", "Users", true).await?;
+  ///   assert!(!memdb.collection_exists("Staging").await);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn rename_collection(
+    &self,
+    from: &str,
+    to: &str,
+    drop_target: bool,
+  ) -> Result<(), Error> {
+    self.db.rename_collection(from, to, drop_target)
+  }
+}
+
+impl Default for MemDb {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{errors::Error, r#async::MemDb};
+
+  #[tokio::test]
+  async fn test_create_collection() -> Result<(), Error> {
+    let memdb = MemDb::new();
+    memdb.create_collection("TestCollection").await?;
+    let _ = memdb.collection("TestCollection").await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn test_delete_collection() -> Result<(), Error> {
+    let memdb = MemDb::new();
+    memdb.create_collection("TestCollection").await?;
+    let _ = memdb.collection("TestCollection").await?;
+    memdb.delete_collection("TestCollection").await?;
+    if memdb.delete_collection("TestCollection").await.is_ok() {
+      assert_eq!("should not find collection", "found collection");
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,35 @@
+//! Async API (`async` feature, enabled by default through `tokio` feature).
+//!
+//! Operations run on documents in memory and never hold a lock across `.await`, so they do
+//! not depend on any runtime.  They can be run by tokio, async-std, smol or `block_on` of
+//! any executor.  They do not yield either: collections are guarded by `std::sync` locks
+//! shared with the blocking API, so a call that needs a lock held by another thread blocks
+//! the executor worker thread until the lock is released.  `find_and_update` and
+//! `find_and_delete` hold the write lock while they scan and change matching documents,
+//! and `MemDb::transaction` holds write locks of its collections while its closure runs.
+//! Keep transactions short, or run long ones with `spawn_blocking` of the runtime, when
+//! other tasks use the same collections.  With `tokio` feature snapshot files are
+//! read and written with `tokio::fs`, without it they are accessed on the calling task.
+//! `Collection` handle can be converted to blocking one and back with `From`.
+//!
+//! ```
+//! use memquery::{doc, errors::Error, query, r#async::MemDb};
+//!
+//! async fn play() -> Result<(), Error> {
+//!   let memdb = MemDb::new();
+//!   memdb.create_collection("TestCollection").await?;
+//!   let coll = memdb.collection("TestCollection").await?;
+//!   coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+//!   let docs = coll.find(query!({ "name": "Tom" })).await?;
+//!   Ok(())
+//! }
+//! ```
+//!
+
+mod collection;
+mod memdb;
+mod typed_collection;
+
+pub use collection::Collection;
+pub use memdb::MemDb;
+pub use typed_collection::TypedCollection;
@@ -0,0 +1,108 @@
+//! TypedCollection stores Rust values that serialize to JSON objects, see `typed_collection`
+//! module.
+//!
+
+use super::Collection;
+use crate::{document::Document, errors::Error, query::CompiledQuery, typed_collection::Codec};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Collection of documents deserialized as `T`.
+pub struct TypedCollection<T> {
+  collection: Collection,
+  codec: Codec<T>,
+}
+
+impl<T> Clone for TypedCollection<T> {
+  fn clone(&self) -> Self {
+    TypedCollection {
+      collection: self.collection.clone(),
+      codec: self.codec,
+    }
+  }
+}
+
+impl<T> TypedCollection<T>
+where
+  T: Serialize + DeserializeOwned,
+{
+  /// Wraps untyped collection.
+  pub fn new(collection: Collection) -> TypedCollection<T> {
+    TypedCollection {
+      collection,
+      codec: Codec::serde(),
+    }
+  }
+
+  /// Wraps untyped collection storing `Document` structs.
+  pub fn for_document(collection: Collection) -> TypedCollection<T>
+  where
+    T: Document,
+  {
+    TypedCollection {
+      collection,
+      codec: Codec::document(),
+    }
+  }
+
+  /// Returns underlying untyped collection.
+  pub fn collection(&self) -> &Collection {
+    &self.collection
+  }
+
+  /// Insert new document (async).
+  ///
+  /// ```
+  /// use memquery::{errors::Error, r#async::MemDb, query};
+  /// use serde::{Deserialize, Serialize};
+  ///
+  /// #[derive(Serialize, Deserialize)]
+  /// struct User {
+  ///   name: String,
+  ///   age: u32,
+  /// }
+  ///
+  /// async fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Users").await?;
+  ///   let users = memdb.collection_typed::<User>("Users").await?;
+  ///   users.insert(User { name: "Tom".to_string(), age: 25 }).await?;
+  ///   let tom = users.find_one(query!({ "name": "Tom" })).await?;
+  ///   assert_eq!(tom.map(|u| u.age), Some(25));
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn insert(&self, document: T) -> Result<(), Error> {
+    self.collection.insert(self.codec.encode(&document)?).await
+  }
+
+  /// Return documents that match specified criteria (async).
+  pub async fn find(&self, query: Value) -> Result<Vec<T>, Error> {
+    self.codec.decode_all(self.collection.find(query).await?)
+  }
+
+  /// Return first document that matches specified criteria (async).
+  pub async fn find_one(&self, query: Value) -> Result<Option<T>, Error> {
+    self
+      .codec
+      .decode_one(self.collection.find_one(query).await?)
+  }
+
+  /// Updates documents that match search criteria (async).
+  pub async fn find_and_update(&self, query: Value, update: Value) -> Result<u64, Error> {
+    self.collection.find_and_update(query, update).await
+  }
+
+  /// Delete documents that match search criteria and return them (async).
+  ///
+  /// Nothing is deleted if any of the matching documents fails to deserialize.
+  pub async fn find_and_delete(&self, query: Value) -> Result<Vec<T>, Error> {
+    let codec = self.codec;
+    self
+      .collection
+      .engine()
+      .find_and_delete_with(&CompiledQuery::new(&query)?, |document| {
+        codec.decode(document)
+      })
+  }
+}
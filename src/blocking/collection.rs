@@ -0,0 +1,585 @@
+//! Collection stores documents as JSON objects.
+//!
+#[cfg(feature = "bson")]
+use crate::bson_format::{from_bson, to_bson, BsonDocuments};
+use crate::{
+  change_stream::{ChangeReceiver, WatchOptions},
+  clock::Clock,
+  engine::{CollectionData, DocumentCollection, Documents, Engine},
+  errors::Error,
+  id_generator::IdGenerator,
+  import_export::{
+    ExportFormat, ExportOptions, ImportOptions, ImportSummary, JsonArray, JsonLines,
+  },
+  index::{IndexDefinition, IndexOptions},
+  options::{CollectionOptions, CountOptions, FindOptions},
+  planner::ExplainOptions,
+  query::CompiledQuery,
+  snapshot::CollectionSnapshot,
+  stats::CollectionStats,
+  tailable::TailableCursor,
+  view::CollectionView,
+};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::sync::{Arc, RwLock};
+
+/// Stores JSON documents.
+#[derive(Clone)]
+pub struct Collection {
+  pub data: DocumentCollection,
+}
+
+impl Collection {
+  /// Make a new collection.
+  pub fn new() -> Collection {
+    Collection {
+      data: Arc::new(RwLock::new(CollectionData::new())),
+    }
+  }
+
+  /// Make a new collection that assigns `_id` to inserted documents with specified generator.
+  ///
+  /// ```
+  /// use memquery::{blocking::Collection, id_generator::AutoIncrement};
+  /// use std::sync::Arc;
+  ///
+  /// let coll = Collection::with_id_generator(Arc::new(AutoIncrement::new()));
+  /// ```
+  pub fn with_id_generator(id_generator: Arc<dyn IdGenerator>) -> Collection {
+    Collection {
+      data: Arc::new(RwLock::new(CollectionData::with_id_generator(id_generator))),
+    }
+  }
+
+  /// Same collection that reads current time for TTL indexes from `clock`.
+  pub fn with_clock(self, clock: Arc<dyn Clock>) -> Collection {
+    self.data.write().unwrap().set_clock(clock);
+    self
+  }
+
+  pub(crate) fn engine(&self) -> Engine {
+    Engine::with_collection(self.data.clone())
+  }
+
+  /// Insert new document.
+  ///
+  /// Document without `_id` field gets one from collection's `IdGenerator`.
+  /// Returns `MQDuplicateKey` error if document violates unique index.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn insert(&self, document: Value) -> Result<(), Error> {
+    self.engine().insert(document)
+  }
+
+  /// Return documents that match specified criteria.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let docs = coll.find(query!({"name": "Tom", "age": 25}))?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn find(&self, query: Value) -> Result<Documents, Error> {
+    self.engine().find(&CompiledQuery::new(&query)?)
+  }
+
+  /// Return first document that matches specified criteria.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let doc = coll.find_one(query!({"name": "Tom"}))?;
+  ///   assert!(doc.is_some());
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn find_one(&self, query: Value) -> Result<Option<Value>, Error> {
+    self.engine().find_one(&CompiledQuery::new(&query)?)
+  }
+
+  /// Return documents that match query in insertion order or newest first.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, options::FindOptions, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Events")?;
+  ///   let events = memdb.collection("Events")?;
+  ///   events.insert(doc!({ "n": 1 }))?;
+  ///   events.insert(doc!({ "n": 2 }))?;
+  ///   let options = FindOptions { newest_first: true, limit: Some(1) };
+  ///   let last = events.find_with_options(query!({}), options)?;
+  ///   assert_eq!(last[0]["n"], 2);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn find_with_options(&self, query: Value, options: FindOptions) -> Result<Documents, Error> {
+    self
+      .engine()
+      .find_with_options(&CompiledQuery::new(&query)?, &options)
+  }
+
+  /// Count documents that match query without copying them.
+  ///
+  /// `options.skip` matching documents are not counted and at most `options.limit` are.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, options::CountOptions, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   for age in 20..30 {
+  ///     coll.insert(doc!({ "age": age }))?;
+  ///   }
+  ///   let adults = query!({ "age": { "$gte": 21 } });
+  ///   assert_eq!(coll.count_documents(adults.clone(), CountOptions::default())?, 9);
+  ///   let options = CountOptions { skip: 5, limit: Some(3) };
+  ///   assert_eq!(coll.count_documents(adults, options)?, 3);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn count_documents(&self, query: Value, options: CountOptions) -> Result<u64, Error> {
+    self
+      .engine()
+      .count_documents(&CompiledQuery::new(&query)?, &options)
+  }
+
+  /// Number of documents in collection without evaluating any query.
+  ///
+  /// Takes constant time.  Documents expired by TTL index are counted until they are removed.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom" }))?;
+  ///   assert_eq!(coll.estimated_document_count(), 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn estimated_document_count(&self) -> u64 {
+    self.engine().estimated_document_count()
+  }
+
+  /// Return documents that match query compiled with `CompiledQuery::new`.
+  ///
+  /// Compiled query is validated once and can be reused across calls.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query, query::CompiledQuery};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let adults = CompiledQuery::new(&query!({ "age": { "$gte": 21 } }))?;
+  ///   let docs = coll.find_compiled(&adults)?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn find_compiled(&self, query: &CompiledQuery) -> Result<Documents, Error> {
+    self.engine().find(query)
+  }
+
+  /// Updates documents that match search criteria.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query, update};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let docs_updated = coll
+  ///     .find_and_update(
+  ///     query!({"name": "Tom"}),
+  ///     update!({"nickname": "Bobcat", "voice": "meow"}),
+  ///   )?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn find_and_update(&self, query: Value, update: Value) -> Result<u64, Error> {
+    self
+      .engine()
+      .find_and_update(&CompiledQuery::new(&query)?, &update)
+  }
+
+  /// Delete documents that match search criteria.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let docs = coll.find_and_delete(query!({"name": "Tom"}))?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn find_and_delete(&self, query: Value) -> Result<Documents, Error> {
+    self.engine().find_and_delete(&CompiledQuery::new(&query)?)
+  }
+
+  /// Creates index on specified keys and returns its name.
+  ///
+  /// Unique index rejects inserts and updates that would create duplicate key.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, index::IndexOptions, blocking::MemDb};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.create_index(&["email"], IndexOptions { unique: true, ..Default::default() })?;
+  ///   coll.insert(doc!({ "name": "Tom", "email": "tom@test.com" }))?;
+  ///   assert!(coll.insert(doc!({ "name": "Bob", "email": "tom@test.com" })).is_err());
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn create_index(&self, keys: &[&str], options: IndexOptions) -> Result<String, Error> {
+    self.engine().create_index(keys, options)
+  }
+
+  /// Drops index by name.  The `_id_` index cannot be dropped.
+  pub fn drop_index(&self, name: &str) -> Result<(), Error> {
+    self.engine().drop_index(name)
+  }
+
+  /// Removes documents expired by TTL indexes and returns their number.
+  ///
+  /// Expired documents are not returned by queries even before they are removed, and every
+  /// write removes expired documents first, see `index` module.
+  ///
+  /// ```
+  /// use memquery::{clock::ManualClock, doc, errors::Error, extended_json::date, index::IndexOptions, blocking::MemDb, query};
+  /// use std::sync::Arc;
+  /// use std::time::Duration;
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let clock = Arc::new(ManualClock::new(0));
+  ///   let memdb = MemDb::new().with_clock(clock.clone());
+  ///   memdb.create_collection("Sessions")?;
+  ///   let sessions = memdb.collection("Sessions")?;
+  ///   let options = IndexOptions { expire_after: Some(Duration::from_secs(60)), ..Default::default() };
+  ///   sessions.create_index(&["lastSeen"], options)?;
+  ///   sessions.insert(doc!({ "user": "Tom", "lastSeen": date(0) }))?;
+  ///
+  ///   clock.advance(Duration::from_secs(60));
+  ///   assert!(sessions.find(query!({}))?.is_empty());
+  ///   assert_eq!(sessions.purge_expired()?, 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn purge_expired(&self) -> Result<u64, Error> {
+    self.engine().purge_expired()
+  }
+
+  /// Returns definitions of all indexes on collection.
+  pub fn list_indexes(&self) -> Vec<IndexDefinition> {
+    self.engine().list_indexes()
+  }
+
+  /// Returns number of documents, their approximate memory size, sizes of indexes and
+  /// counters of operations, see `stats` module.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   coll.find(query!({ "age": 25 }))?;
+  ///
+  ///   let stats = coll.stats();
+  ///   assert_eq!(stats.count, 1);
+  ///   assert_eq!(stats.operations.queries, 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn stats(&self) -> CollectionStats {
+    self.engine().stats()
+  }
+
+  /// Returns immutable point-in-time view of collection.
+  ///
+  /// View is taken in constant time and can be queried and exported while writers
+  /// change the collection, it does not see their changes.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let view = coll.view();
+  ///   coll.insert(doc!({ "name": "Bob", "age": 30 }))?;
+  ///   assert_eq!(view.find(query!({}))?.len(), 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn view(&self) -> CollectionView {
+    self.engine().view()
+  }
+
+  /// Opens tailable cursor over capped collection.
+  ///
+  /// Cursor yields documents that match filter in insertion order, then waits for new
+  /// inserted documents, see `tailable` module.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, options::{Capped, CollectionOptions}, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   let capped = Capped { max_docs: Some(100), max_bytes: None };
+  ///   memdb.create_collection_with_options("Events", CollectionOptions { capped: Some(capped), ..Default::default() })?;
+  ///   let events = memdb.collection("Events")?;
+  ///   events.insert(doc!({ "n": 1 }))?;
+  ///   let mut cursor = events.tail(query!({}))?;
+  ///   events.insert(doc!({ "n": 2 }))?;
+  ///   assert_eq!(cursor.next_document()?["n"], 1);
+  ///   assert_eq!(cursor.next_document()?["n"], 2);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn tail(&self, filter: Value) -> Result<TailableCursor, Error> {
+    let (documents, subscription) = self.engine().tail(CompiledQuery::new(&filter)?)?;
+    Ok(TailableCursor::new(documents, subscription))
+  }
+
+  /// Options collection was created with.
+  pub fn options(&self) -> CollectionOptions {
+    self.data.read().unwrap().options().clone()
+  }
+
+  /// Watches inserts, updates and deletes of documents that match filter.
+  ///
+  /// Filter is matched against inserted or updated document, or deleted document for
+  /// deletes.  Receiver gets changes made after it was created and ends when collection is
+  /// deleted, see `change_stream` module.
+  ///
+  /// ```
+  /// use memquery::{change_stream::OperationType, doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   let mut changes = coll.watch(query!({ "age": { "$gt": 20 } }))?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let event = changes.recv()?;
+  ///   assert_eq!(event.operation_type, OperationType::Insert);
+  ///   assert_eq!(event.full_document.unwrap()["name"], "Tom");
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn watch(&self, filter: Value) -> Result<ChangeReceiver, Error> {
+    self.watch_with_options(filter, WatchOptions::default())
+  }
+
+  /// Watches changes like `watch`, `options.resume_after` continues after earlier event.
+  pub fn watch_with_options(
+    &self,
+    filter: Value,
+    options: WatchOptions,
+  ) -> Result<ChangeReceiver, Error> {
+    let subscription = self.engine().watch(CompiledQuery::new(&filter)?, options)?;
+    Ok(ChangeReceiver::new(subscription))
+  }
+
+  /// Returns copy of index definitions (except `_id_`) and documents, see `view` for
+  /// point-in-time view that can be queried.
+  pub fn snapshot(&self) -> CollectionSnapshot {
+    self.engine().snapshot()
+  }
+
+  /// Imports documents from JSON Lines input, one document per line as written by
+  /// `mongoexport`.
+  ///
+  /// ```
+  /// use memquery::{errors::Error, import_export::{ErrorPolicy, ImportOptions}, blocking::MemDb};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   let input = "{\"name\": \"Tom\"}\nnot json\n{\"name\": \"Bob\"}\n";
+  ///   let options = ImportOptions { on_error: ErrorPolicy::Skip, ..Default::default() };
+  ///   let summary = coll.import_jsonl(input.as_bytes(), options)?;
+  ///   assert_eq!(summary.inserted, 2);
+  ///   assert_eq!(summary.skipped[0].line, 2);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn import_jsonl<R: Read>(
+    &self,
+    reader: R,
+    options: ImportOptions,
+  ) -> Result<ImportSummary, Error> {
+    self.engine().import(JsonLines::new(reader), options)
+  }
+
+  /// Imports documents from JSON array, as written by `mongoexport --jsonArray`.
+  pub fn import_json_array<R: Read>(
+    &self,
+    reader: R,
+    options: ImportOptions,
+  ) -> Result<ImportSummary, Error> {
+    self.engine().import(JsonArray::new(reader), options)
+  }
+
+  /// Writes all documents as JSON Lines, one document per line.
+  ///
+  /// Returns number of exported documents.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, import_export::ExportOptions, blocking::MemDb};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert(doc!({ "name": "Tom" }))?;
+  ///   let mut output = Vec::new();
+  ///   coll.export_jsonl(&mut output, ExportOptions::default())?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn export_jsonl<W: Write>(&self, writer: W, options: ExportOptions) -> Result<u64, Error> {
+    self
+      .engine()
+      .export(writer, options, ExportFormat::JsonLines)
+  }
+
+  /// Writes all documents as JSON array.
+  ///
+  /// Returns number of exported documents.
+  pub fn export_json_array<W: Write>(
+    &self,
+    writer: W,
+    options: ExportOptions,
+  ) -> Result<u64, Error> {
+    self
+      .engine()
+      .export(writer, options, ExportFormat::JsonArray)
+  }
+
+  /// Insert BSON document (`bson` feature).
+  ///
+  /// ObjectId, DateTime, Decimal128 and Binary values are stored as Extended JSON, see
+  /// `bson_format` module.
+  ///
+  /// ```
+  /// # #[cfg(feature = "bson")]
+  /// use memquery::{errors::Error, blocking::MemDb, query};
+  ///
+  /// # #[cfg(feature = "bson")]
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.insert_bson(bson::doc! { "name": "Tom", "joined": bson::DateTime::now() })?;
+  ///   let docs = coll.find_bson(query!({ "name": "Tom" }))?;
+  ///   assert!(docs[0].get_datetime("joined").is_ok());
+  ///   Ok(())
+  /// }
+  /// ```
+  #[cfg(feature = "bson")]
+  pub fn insert_bson(&self, document: bson::Document) -> Result<(), Error> {
+    self.insert(from_bson(document)?)
+  }
+
+  /// Return documents that match specified criteria as BSON documents (`bson` feature).
+  #[cfg(feature = "bson")]
+  pub fn find_bson(&self, query: Value) -> Result<Vec<bson::Document>, Error> {
+    self.find(query)?.iter().map(to_bson).collect()
+  }
+
+  /// Imports concatenated BSON documents, as in `.bson` file written by `mongodump`
+  /// (`bson` feature).
+  #[cfg(feature = "bson")]
+  pub fn import_bson<R: Read>(
+    &self,
+    reader: R,
+    options: ImportOptions,
+  ) -> Result<ImportSummary, Error> {
+    self.engine().import(BsonDocuments::new(reader), options)
+  }
+
+  /// Writes all documents as concatenated BSON documents, the format of `.bson` files
+  /// written by `mongodump` (`bson` feature).
+  ///
+  /// Returns number of exported documents.
+  #[cfg(feature = "bson")]
+  pub fn export_bson<W: Write>(&self, writer: W, options: ExportOptions) -> Result<u64, Error> {
+    self.engine().export(writer, options, ExportFormat::Bson)
+  }
+
+  /// Describes how query is executed.
+  ///
+  /// Returns plan chosen for the query (collection scan or index scan with bounds) and,
+  /// with `ExplainVerbosity::ExecutionStats`, number of documents and index keys examined,
+  /// number of documents returned, and execution time.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, index::IndexOptions, blocking::MemDb, planner::ExplainOptions, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("TestCollection")?;
+  ///   let coll = memdb.collection("TestCollection")?;
+  ///   coll.create_index(&["age"], IndexOptions::default())?;
+  ///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  ///   let explain = coll.explain(query!({ "age": { "$gt": 20 } }), ExplainOptions::default())?;
+  ///   assert_eq!(explain["queryPlanner"]["winningPlan"]["inputStage"]["indexName"], "age_1");
+  ///   assert_eq!(explain["executionStats"]["nReturned"], 1);
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn explain(&self, query: Value, options: ExplainOptions) -> Result<Value, Error> {
+    self.engine().explain(&CompiledQuery::new(&query)?, options)
+  }
+}
+
+impl Default for Collection {
+  fn default() -> Self {
+    Self::new()
+  }
+}
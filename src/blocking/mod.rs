@@ -0,0 +1,27 @@
+//! Blocking API.
+//!
+//! Every call returns when operation is complete, so handles can be used from plain threads.
+//! Types here share data with their `r#async` counterparts, `Collection` handle can be
+//! converted to async one and back with `From`.
+//!
+//! ```
+//! use memquery::{blocking::MemDb, doc, errors::Error, query};
+//!
+//! fn play() -> Result<(), Error> {
+//!   let memdb = MemDb::new();
+//!   memdb.create_collection("TestCollection")?;
+//!   let coll = memdb.collection("TestCollection")?;
+//!   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+//!   let docs = coll.find(query!({ "name": "Tom" }))?;
+//!   Ok(())
+//! }
+//! ```
+//!
+
+mod collection;
+mod memdb;
+mod typed_collection;
+
+pub use collection::Collection;
+pub use memdb::MemDb;
+pub use typed_collection::TypedCollection;
@@ -0,0 +1,346 @@
+//! BSON documents and `mongodump` files (`bson` feature).
+//!
+//! Collections store documents as JSON.  BSON values that JSON cannot represent are stored
+//! as Extended JSON wrappers (see `extended_json` module), so ObjectId, DateTime, Decimal128
+//! and Binary values come back unchanged when document is returned as BSON.  Integers are
+//! returned as Int32 when they fit and as Int64 otherwise.
+//!
+//! `MemDb::dump_bson` writes directory in `mongodump` layout, one `<collection>.bson` file
+//! with documents and one `<collection>.metadata.json` file with indexes, capped and
+//! validation options per collection.  `MemDb::restore_bson` reads it back.  Files are read
+//! and written with blocking I/O also when async API is used.
+//!
+
+use super::{
+  errors::Error,
+  extended_json::{self, ExtendedJsonMode},
+  import_export::Record,
+  index::{IndexDefinition, IndexOptions, ID_INDEX_NAME},
+  options::{Capped, CollectionOptions},
+  snapshot::{CollectionSnapshot, Snapshot},
+};
+use bson::{Bson, Document};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Extension of files with documents in `mongodump` directory.
+pub const BSON_EXTENSION: &str = "bson";
+
+/// Suffix of files with collection metadata in `mongodump` directory.
+pub const METADATA_SUFFIX: &str = ".metadata.json";
+
+/// Largest BSON document accepted on input, the same limit as MongoDB has.
+const MAX_DOCUMENT_SIZE: usize = 16 * 1024 * 1024;
+
+/// Index version written to metadata, as `mongodump` of current MongoDB does.
+const INDEX_VERSION: i32 = 2;
+
+/// Converts BSON document to JSON document as stored in collections.
+pub fn from_bson(document: Document) -> Result<Value, Error> {
+  extended_json::parse(Bson::Document(document).into_relaxed_extjson())
+}
+
+/// Converts JSON document to BSON document.
+pub fn to_bson(document: &Value) -> Result<Document, Error> {
+  match extended_json::serialize(document, ExtendedJsonMode::Canonical) {
+    Value::Object(fields) => Ok(Document::try_from(fields)?),
+    _ => Err(Error::MQInvalidValue(String::from(
+      "Document must be a JSON object.",
+    ))),
+  }
+}
+
+/// Reads concatenated BSON documents, as in `.bson` files written by `mongodump`.
+///
+/// Record line is position of document in input, starting at 1.
+pub(crate) struct BsonDocuments<R> {
+  reader: BufReader<R>,
+  count: usize,
+  done: bool,
+}
+
+impl<R: Read> BsonDocuments<R> {
+  pub fn new(reader: R) -> BsonDocuments<R> {
+    BsonDocuments {
+      reader: BufReader::new(reader),
+      count: 0,
+      done: false,
+    }
+  }
+
+  fn fail(&mut self, message: &str) -> Option<Result<Record, Error>> {
+    self.done = true;
+    Some(Err(Error::MQImportError {
+      line: self.count,
+      error: Box::new(Error::MQError(message.to_string())),
+    }))
+  }
+}
+
+impl<R: Read> Iterator for BsonDocuments<R> {
+  type Item = Result<Record, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+    match self.reader.fill_buf() {
+      Ok([]) => return None,
+      Ok(_) => (),
+      Err(e) => return Some(Err(e.into())),
+    }
+    self.count += 1;
+
+    let mut length = [0u8; 4];
+    if self.reader.read_exact(&mut length).is_err() {
+      return self.fail("BSON document is truncated.");
+    }
+    let size = i32::from_le_bytes(length) as usize;
+    if !(5..=MAX_DOCUMENT_SIZE).contains(&size) {
+      return self.fail("Invalid BSON document size.");
+    }
+    let mut data = vec![0u8; size];
+    data[..4].copy_from_slice(&length);
+    if self.reader.read_exact(&mut data[4..]).is_err() {
+      return self.fail("BSON document is truncated.");
+    }
+
+    Some(Ok(Record {
+      line: self.count,
+      document: Document::from_reader(&data[..])
+        .map_err(Error::from)
+        .and_then(from_bson),
+    }))
+  }
+}
+
+/// Writes documents of snapshot to `dir` in `mongodump` layout.
+pub(crate) fn write_dump(dir: &Path, snapshot: &Snapshot) -> Result<(), Error> {
+  std::fs::create_dir_all(dir)?;
+  for (name, collection) in &snapshot.collections {
+    let mut writer = BufWriter::new(File::create(
+      dir.join(format!("{}.{}", name, BSON_EXTENSION)),
+    )?);
+    for document in &collection.documents {
+      to_bson(document)?.to_writer(&mut writer)?;
+    }
+    writer.flush()?;
+
+    let metadata = serde_json::to_vec(&metadata(name, &collection.indexes, &collection.options))?;
+    std::fs::write(dir.join(format!("{}{}", name, METADATA_SUFFIX)), metadata)?;
+  }
+  Ok(())
+}
+
+/// Reads collections of `mongodump` directory.  Metadata files are optional.
+pub(crate) fn read_dump(dir: &Path) -> Result<Snapshot, Error> {
+  let mut snapshot = Snapshot::new();
+  for entry in std::fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.extension().and_then(|e| e.to_str()) != Some(BSON_EXTENSION) {
+      continue;
+    }
+    let name = match path.file_stem().and_then(|s| s.to_str()) {
+      Some(name) if !name.starts_with("system.") => name.to_string(),
+      _ => continue,
+    };
+
+    let mut collection = CollectionSnapshot::default();
+    for record in BsonDocuments::new(File::open(&path)?) {
+      let Record { line, document } = record?;
+      collection
+        .documents
+        .push(document.map_err(|e| Error::MQImportError {
+          line,
+          error: Box::new(e),
+        })?);
+    }
+    let metadata_path = dir.join(format!("{}{}", name, METADATA_SUFFIX));
+    if metadata_path.exists() {
+      let metadata = serde_json::from_slice(&std::fs::read(metadata_path)?)?;
+      let metadata = extended_json::parse(metadata)?;
+      collection.options = options_from_metadata(&metadata)?;
+      collection.indexes = indexes_from_metadata(metadata)?;
+    }
+    snapshot.collections.insert(name, collection);
+  }
+  Ok(snapshot)
+}
+
+fn metadata(name: &str, indexes: &[IndexDefinition], options: &CollectionOptions) -> Value {
+  let mut specs = vec![json!({ "v": INDEX_VERSION, "key": { "_id": 1 }, "name": ID_INDEX_NAME })];
+  for index in indexes {
+    let mut spec = Map::new();
+    spec.insert("v".to_string(), json!(INDEX_VERSION));
+    spec.insert(
+      "key".to_string(),
+      Value::Object(index.keys.iter().map(|k| (k.clone(), json!(1))).collect()),
+    );
+    spec.insert("name".to_string(), json!(index.name));
+    if index.unique {
+      spec.insert("unique".to_string(), json!(true));
+    }
+    if index.sparse {
+      spec.insert("sparse".to_string(), json!(true));
+    }
+    if let Some(filter) = &index.partial_filter_expression {
+      spec.insert("partialFilterExpression".to_string(), filter.clone());
+    }
+    if let Some(expire_after) = index.expire_after {
+      spec.insert(
+        "expireAfterSeconds".to_string(),
+        json!(expire_after.as_secs()),
+      );
+    }
+    specs.push(Value::Object(spec));
+  }
+  let mut collection_options = Map::new();
+  if let Some(capped) = &options.capped {
+    collection_options.insert("capped".to_string(), json!(true));
+    if let Some(max_bytes) = capped.max_bytes {
+      collection_options.insert("size".to_string(), json!(max_bytes));
+    }
+    if let Some(max_docs) = capped.max_docs {
+      collection_options.insert("max".to_string(), json!(max_docs));
+    }
+  }
+  if let Some(validator) = &options.validator {
+    collection_options.insert("validator".to_string(), validator.clone());
+    collection_options.insert(
+      "validationLevel".to_string(),
+      json!(options.validation_level),
+    );
+    collection_options.insert(
+      "validationAction".to_string(),
+      json!(options.validation_action),
+    );
+  }
+  json!({ "options": collection_options, "indexes": specs, "collectionName": name })
+}
+
+/// Reads capped and validation options of collection, other options are ignored.
+fn options_from_metadata(metadata: &Value) -> Result<CollectionOptions, Error> {
+  let options = &metadata["options"];
+  let capped = match options["capped"].as_bool() {
+    Some(true) => Some(Capped {
+      max_docs: options["max"].as_u64().filter(|max| *max > 0),
+      max_bytes: options["size"].as_u64().filter(|size| *size > 0),
+    }),
+    _ => None,
+  };
+  Ok(CollectionOptions {
+    capped,
+    validator: options.get("validator").cloned(),
+    validation_level: setting(options, "validationLevel")?,
+    validation_action: setting(options, "validationAction")?,
+  })
+}
+
+fn setting<T: DeserializeOwned + Default>(options: &Value, name: &str) -> Result<T, Error> {
+  match options.get(name) {
+    Some(value) => Ok(serde_json::from_value(value.clone())?),
+    None => Ok(T::default()),
+  }
+}
+
+fn indexes_from_metadata(metadata: Value) -> Result<Vec<IndexDefinition>, Error> {
+  let invalid = |message: &str| Error::MQInvalidValue(format!("Invalid metadata: {}", message));
+  let specs = match metadata.get("indexes") {
+    Some(Value::Array(specs)) => specs,
+    Some(_) => return Err(invalid("indexes must be an array.")),
+    None => return Ok(Vec::new()),
+  };
+
+  let mut indexes = Vec::new();
+  for spec in specs {
+    let name = spec.get("name").and_then(|n| n.as_str());
+    if name == Some(ID_INDEX_NAME) {
+      continue;
+    }
+    let key = match spec.get("key").and_then(|k| k.as_object()) {
+      Some(key) => key,
+      None => return Err(invalid("index must have key.")),
+    };
+    if let Some((field, kind)) = key.iter().find(|(_, kind)| !kind.is_number()) {
+      return Err(invalid(&format!(
+        "index type {} of {} is not supported.",
+        kind, field
+      )));
+    }
+    let keys: Vec<&str> = key.keys().map(|k| k.as_str()).collect();
+    let flag = |name: &str| spec.get(name).and_then(|f| f.as_bool()).unwrap_or(false);
+    let options = IndexOptions {
+      name: name.map(|n| n.to_string()),
+      unique: flag("unique"),
+      sparse: flag("sparse"),
+      partial_filter_expression: spec.get("partialFilterExpression").cloned(),
+      expire_after: spec
+        .get("expireAfterSeconds")
+        .and_then(|secs| secs.as_u64())
+        .map(Duration::from_secs),
+    };
+    indexes.push(IndexDefinition::new(&keys, options)?);
+  }
+  Ok(indexes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::options::ValidationLevel;
+  use bson::{doc, oid::ObjectId, spec::BinarySubtype, Binary, DateTime, Decimal128};
+
+  #[test]
+  fn test_round_trip_keeps_bson_types() -> Result<(), Error> {
+    let document = doc! {
+      "_id": ObjectId::parse_str("5f1d7f3e9c4b2a0011223344").unwrap(),
+      "created": DateTime::from_millis(1_593_561_600_123),
+      "price": "9.99".parse::<Decimal128>().unwrap(),
+      "data": Binary { subtype: BinarySubtype::Generic, bytes: vec![0, 1, 254, 255] },
+      "count": 5,
+      "views": 9_007_199_254_740_993_i64,
+      "ratio": 0.5,
+      "tags": ["a", { "n": 1.0 }],
+    };
+    let value = from_bson(document.clone())?;
+    assert_eq!(value["count"], 5);
+    assert_eq!(value["created"]["$date"], "2020-07-01T00:00:00.123Z");
+    assert_eq!(value["data"]["$binary"]["base64"], "AAH+/w==");
+    assert_eq!(to_bson(&value)?, document);
+    Ok(())
+  }
+
+  #[test]
+  fn test_metadata_round_trip() -> Result<(), Error> {
+    let index = IndexDefinition::new(
+      &["name", "email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )?;
+    let indexes = vec![index];
+    let options = CollectionOptions {
+      capped: Some(Capped {
+        max_docs: Some(100),
+        max_bytes: Some(4096),
+      }),
+      validator: Some(json!({ "$jsonSchema": { "required": ["name"] } })),
+      validation_level: ValidationLevel::Moderate,
+      ..Default::default()
+    };
+    let metadata = metadata("Users", &indexes, &options);
+    assert_eq!(metadata["indexes"][0]["name"], ID_INDEX_NAME);
+    assert_eq!(metadata["options"]["size"], 4096);
+    assert_eq!(metadata["options"]["validationAction"], "error");
+    assert_eq!(options_from_metadata(&metadata)?, options);
+    assert_eq!(indexes_from_metadata(metadata)?, indexes);
+    assert!(indexes_from_metadata(json!({ "indexes": [{ "key": { "a": "text" } }] })).is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,498 @@
+//! Fluent builders for query and update documents.
+//!
+//! Builders produce the same `serde_json::Value` that `query!` and `update!` macros
+//! produce, but operators are methods so misspelled operator does not compile.
+//!
+//! ```
+//! use memquery::{builder::{Filter, Update, UpdateOps}, query, update};
+//!
+//! let filter = Filter::field("age").gte(21).and(Filter::field("tags").all(vec!["a"]));
+//! assert_eq!(
+//!   filter.to_value(),
+//!   query!({ "$and": [{ "age": { "$gte": 21 } }, { "tags": { "$all": ["a"] } }] })
+//! );
+//!
+//! let update = Update::set("x", 1).inc("n", 2);
+//! assert_eq!(update.to_value(), update!({ "$set": { "x": 1 }, "$inc": { "n": 2 } }));
+//! ```
+//!
+
+use super::{
+  errors::Error,
+  query::{ComparisonOp, CompiledQuery, REGEX_OPTIONS},
+  utils::{AND, INC, MUL, OR, SET, UNSET},
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Query document builder.
+///
+/// Empty filter matches all documents.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+  query: Map<String, Value>,
+}
+
+impl Filter {
+  /// Filter that matches all documents.
+  pub fn new() -> Filter {
+    Filter::default()
+  }
+
+  /// Starts condition on field.  Nested fields use dot separated path, for example `info.age`.
+  pub fn field(path: &str) -> FieldFilter {
+    FieldFilter {
+      path: path.to_string(),
+    }
+  }
+
+  /// Documents must match both filters.
+  pub fn and(self, other: Filter) -> Filter {
+    if self.query.is_empty() {
+      return other;
+    }
+    if other.query.is_empty() {
+      return self;
+    }
+    self.combine(AND, other)
+  }
+
+  /// Documents must match at least one of the filters.
+  pub fn or(self, other: Filter) -> Filter {
+    self.combine(OR, other)
+  }
+
+  fn combine(mut self, op: &str, other: Filter) -> Filter {
+    if self.query.len() == 1 {
+      if let Some(Value::Array(filters)) = self.query.get_mut(op) {
+        filters.push(other.into());
+        return self;
+      }
+    }
+
+    let mut query = Map::new();
+    query.insert(
+      op.to_string(),
+      Value::Array(vec![self.into(), other.into()]),
+    );
+    Filter { query }
+  }
+
+  /// Returns query document.
+  pub fn to_value(&self) -> Value {
+    Value::Object(self.query.clone())
+  }
+
+  /// Validates and compiles query.
+  pub fn compile(&self) -> Result<CompiledQuery, Error> {
+    CompiledQuery::new(&self.to_value())
+  }
+}
+
+impl From<Filter> for Value {
+  fn from(filter: Filter) -> Value {
+    Value::Object(filter.query)
+  }
+}
+
+/// Condition on single field created with `Filter::field`.
+#[derive(Clone, Debug)]
+pub struct FieldFilter {
+  path: String,
+}
+
+impl FieldFilter {
+  /// Field equals value.
+  pub fn eq<V: Into<Value>>(self, value: V) -> Filter {
+    self.filter(value.into())
+  }
+
+  /// Field does not equal value.
+  pub fn ne<V: Into<Value>>(self, value: V) -> Filter {
+    self.op(ComparisonOp::Ne, value.into())
+  }
+
+  /// Field is greater than value.
+  pub fn gt<V: Into<Value>>(self, value: V) -> Filter {
+    self.op(ComparisonOp::Gt, value.into())
+  }
+
+  /// Field is greater than or equal to value.
+  pub fn gte<V: Into<Value>>(self, value: V) -> Filter {
+    self.op(ComparisonOp::Gte, value.into())
+  }
+
+  /// Field is less than value.
+  pub fn lt<V: Into<Value>>(self, value: V) -> Filter {
+    self.op(ComparisonOp::Lt, value.into())
+  }
+
+  /// Field is less than or equal to value.
+  pub fn lte<V: Into<Value>>(self, value: V) -> Filter {
+    self.op(ComparisonOp::Lte, value.into())
+  }
+
+  /// Field (or any element of array field) equals one of the values.
+  pub fn in_<I, V>(self, values: I) -> Filter
+  where
+    I: IntoIterator<Item = V>,
+    V: Into<Value>,
+  {
+    self.op(ComparisonOp::In, to_array(values))
+  }
+
+  /// Field (and every element of array field) equals none of the values.
+  pub fn nin<I, V>(self, values: I) -> Filter
+  where
+    I: IntoIterator<Item = V>,
+    V: Into<Value>,
+  {
+    self.op(ComparisonOp::Nin, to_array(values))
+  }
+
+  /// Array field contains all of the values.
+  pub fn all<I, V>(self, values: I) -> Filter
+  where
+    I: IntoIterator<Item = V>,
+    V: Into<Value>,
+  {
+    self.op(ComparisonOp::All, to_array(values))
+  }
+
+  /// Field matches regular expression.
+  pub fn regex(self, pattern: &str) -> Filter {
+    self.op(ComparisonOp::Regex, Value::from(pattern))
+  }
+
+  /// Field matches regular expression with options (`i`, `m`, `s`, `x`).
+  pub fn regex_with_options(self, pattern: &str, options: &str) -> Filter {
+    let mut operators = Map::new();
+    operators.insert(
+      ComparisonOp::Regex.as_operator().to_string(),
+      Value::from(pattern),
+    );
+    operators.insert(REGEX_OPTIONS.to_string(), Value::from(options));
+    self.filter(Value::Object(operators))
+  }
+
+  fn op(self, op: ComparisonOp, value: Value) -> Filter {
+    let mut operators = Map::new();
+    operators.insert(op.as_operator().to_string(), value);
+    self.filter(Value::Object(operators))
+  }
+
+  fn filter(self, value: Value) -> Filter {
+    let mut query = Map::new();
+    query.insert(self.path, value);
+    Filter { query }
+  }
+}
+
+/// Path to field of document type `D` holding value of type `V`.
+///
+/// Constants of this type are generated by `#[derive(Document)]` so conditions on struct
+/// fields are checked at compile time: both field name and compared value type.
+pub struct Field<D, V> {
+  path: &'static str,
+  _marker: PhantomData<fn() -> (D, V)>,
+}
+
+impl<D, V> Field<D, V> {
+  pub const fn new(path: &'static str) -> Field<D, V> {
+    Field {
+      path,
+      _marker: PhantomData,
+    }
+  }
+
+  /// Dot separated path to the field.
+  pub fn path(&self) -> &'static str {
+    self.path
+  }
+}
+
+/// Conditions on typed fields fail only if value does not serialize to JSON, for example
+/// map with non-string keys.
+impl<D, V> Field<D, V>
+where
+  V: Serialize,
+{
+  /// Field equals value.
+  pub fn eq(self, value: V) -> Result<Filter, Error> {
+    Ok(Filter::field(self.path).eq(typed_value(value)?))
+  }
+
+  /// Field does not equal value.
+  pub fn ne(self, value: V) -> Result<Filter, Error> {
+    Ok(Filter::field(self.path).ne(typed_value(value)?))
+  }
+
+  /// Field is greater than value.
+  pub fn gt(self, value: V) -> Result<Filter, Error> {
+    Ok(Filter::field(self.path).gt(typed_value(value)?))
+  }
+
+  /// Field is greater than or equal to value.
+  pub fn gte(self, value: V) -> Result<Filter, Error> {
+    Ok(Filter::field(self.path).gte(typed_value(value)?))
+  }
+
+  /// Field is less than value.
+  pub fn lt(self, value: V) -> Result<Filter, Error> {
+    Ok(Filter::field(self.path).lt(typed_value(value)?))
+  }
+
+  /// Field is less than or equal to value.
+  pub fn lte(self, value: V) -> Result<Filter, Error> {
+    Ok(Filter::field(self.path).lte(typed_value(value)?))
+  }
+
+  /// Field equals one of the values.
+  pub fn in_<I>(self, values: I) -> Result<Filter, Error>
+  where
+    I: IntoIterator<Item = V>,
+  {
+    Ok(Filter::field(self.path).in_(typed_values(values)?))
+  }
+
+  /// Field equals none of the values.
+  pub fn nin<I>(self, values: I) -> Result<Filter, Error>
+  where
+    I: IntoIterator<Item = V>,
+  {
+    Ok(Filter::field(self.path).nin(typed_values(values)?))
+  }
+
+  /// Array field contains all elements of the values.
+  pub fn all(self, values: V) -> Result<Filter, Error> {
+    Ok(match typed_value(values)? {
+      Value::Array(items) => Filter::field(self.path).all(items),
+      value => Filter::field(self.path).all(vec![value]),
+    })
+  }
+
+  /// Field matches regular expression.
+  pub fn regex(self, pattern: &str) -> Filter {
+    Filter::field(self.path).regex(pattern)
+  }
+}
+
+impl<D, V> Clone for Field<D, V> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<D, V> Copy for Field<D, V> {}
+
+impl<D, V> fmt::Debug for Field<D, V> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Field").field(&self.path).finish()
+  }
+}
+
+impl<D, V> AsRef<str> for Field<D, V> {
+  fn as_ref(&self) -> &str {
+    self.path
+  }
+}
+
+fn typed_value<V: Serialize>(value: V) -> Result<Value, Error> {
+  Ok(serde_json::to_value(value)?)
+}
+
+fn typed_values<I, V>(values: I) -> Result<Vec<Value>, Error>
+where
+  I: IntoIterator<Item = V>,
+  V: Serialize,
+{
+  values.into_iter().map(typed_value).collect()
+}
+
+fn to_array<I, V>(values: I) -> Value
+where
+  I: IntoIterator<Item = V>,
+  V: Into<Value>,
+{
+  Value::Array(values.into_iter().map(|v| v.into()).collect())
+}
+
+/// Numeric types accepted by `$inc` and `$mul`.
+pub trait Numeric: Into<Value> {}
+
+macro_rules! numeric {
+  ($($t:ty),*) => {
+    $(impl Numeric for $t {})*
+  };
+}
+
+numeric!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+/// Update document builder for `find_and_update`.
+///
+/// Update is started with `Update::new()` or with one of the operators, `Update::set("x", 1)`,
+/// and further operators are chained with methods of `UpdateOps`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Update {
+  update: Map<String, Value>,
+}
+
+impl Update {
+  /// Empty update.
+  pub fn new() -> Update {
+    Update::default()
+  }
+
+  /// Update that sets field to value.
+  pub fn set<P: AsRef<str>, V: Into<Value>>(path: P, value: V) -> Update {
+    UpdateOps::set(Update::new(), path, value)
+  }
+
+  /// Update that removes field.
+  pub fn unset<P: AsRef<str>>(path: P) -> Update {
+    UpdateOps::unset(Update::new(), path)
+  }
+
+  /// Update that increments field by amount.
+  pub fn inc<P: AsRef<str>, N: Numeric>(path: P, amount: N) -> Update {
+    UpdateOps::inc(Update::new(), path, amount)
+  }
+
+  /// Update that multiplies field by factor.
+  pub fn mul<P: AsRef<str>, N: Numeric>(path: P, factor: N) -> Update {
+    UpdateOps::mul(Update::new(), path, factor)
+  }
+
+  fn op<P: AsRef<str>>(mut self, op: &str, path: P, value: Value) -> Update {
+    if let Value::Object(fields) = self
+      .update
+      .entry(op)
+      .or_insert_with(|| Value::Object(Map::new()))
+    {
+      fields.insert(path.as_ref().to_string(), value);
+    }
+    self
+  }
+
+  /// Returns update document.
+  pub fn to_value(&self) -> Value {
+    Value::Object(self.update.clone())
+  }
+}
+
+/// Operators chained onto `Update`.
+///
+/// They are trait methods because associated functions `Update::set`, `Update::inc`, ...
+/// with the same names start the update.
+pub trait UpdateOps: Sized {
+  /// Sets field to value.
+  fn set<P: AsRef<str>, V: Into<Value>>(self, path: P, value: V) -> Update;
+
+  /// Removes field.
+  fn unset<P: AsRef<str>>(self, path: P) -> Update;
+
+  /// Increments field by amount.
+  fn inc<P: AsRef<str>, N: Numeric>(self, path: P, amount: N) -> Update;
+
+  /// Multiplies field by factor.
+  fn mul<P: AsRef<str>, N: Numeric>(self, path: P, factor: N) -> Update;
+}
+
+impl UpdateOps for Update {
+  fn set<P: AsRef<str>, V: Into<Value>>(self, path: P, value: V) -> Update {
+    self.op(SET, path, value.into())
+  }
+
+  fn unset<P: AsRef<str>>(self, path: P) -> Update {
+    self.op(UNSET, path, Value::from(""))
+  }
+
+  fn inc<P: AsRef<str>, N: Numeric>(self, path: P, amount: N) -> Update {
+    self.op(INC, path, amount.into())
+  }
+
+  fn mul<P: AsRef<str>, N: Numeric>(self, path: P, factor: N) -> Update {
+    self.op(MUL, path, factor.into())
+  }
+}
+
+impl From<Update> for Value {
+  fn from(update: Update) -> Value {
+    Value::Object(update.update)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_field_operators() {
+    assert_eq!(
+      Filter::field("name").eq("Tom").to_value(),
+      json!({ "name": "Tom" })
+    );
+    assert_eq!(
+      Filter::field("age").lt(30).to_value(),
+      json!({ "age": { "$lt": 30 } })
+    );
+    assert_eq!(
+      Filter::field("qty").in_(vec![1, 2]).to_value(),
+      json!({ "qty": { "$in": [1, 2] } })
+    );
+    assert_eq!(
+      Filter::field("name")
+        .regex_with_options("^t", "i")
+        .to_value(),
+      json!({ "name": { "$regex": "^t", "$options": "i" } })
+    );
+  }
+
+  #[test]
+  fn test_logical_operators() {
+    assert_eq!(
+      Filter::new().and(Filter::field("a").eq(1)),
+      Filter::field("a").eq(1)
+    );
+
+    let filter = Filter::field("a")
+      .eq(1)
+      .or(Filter::field("b").eq(2))
+      .or(Filter::field("c").eq(3));
+    assert_eq!(
+      filter.to_value(),
+      json!({ "$or": [{ "a": 1 }, { "b": 2 }, { "c": 3 }] })
+    );
+    assert!(filter.compile().is_ok());
+  }
+
+  #[test]
+  fn test_update() {
+    let update = Update::set("name", "Tom")
+      .set("info.age", 30)
+      .unset("email")
+      .mul("score", 1.5);
+    assert_eq!(
+      Value::from(update),
+      json!({
+        "$set": { "name": "Tom", "info.age": 30 },
+        "$unset": { "email": "" },
+        "$mul": { "score": 1.5 }
+      })
+    );
+    assert_eq!(Update::new().inc("n", 2), Update::inc("n", 2));
+  }
+
+  #[test]
+  fn test_field_value_that_does_not_serialize() {
+    use std::collections::HashMap;
+
+    let field: Field<(), HashMap<Vec<u8>, u8>> = Field::new("map");
+    let mut map = HashMap::new();
+    map.insert(vec![1], 1);
+    assert!(field.eq(map).is_err());
+  }
+}
@@ -0,0 +1,445 @@
+//! Change streams that report inserts, updates and deletes of collection documents.
+//!
+//! `Collection::watch` returns `ChangeReceiver` in blocking API and `ChangeStream` (a
+//! `futures_core::Stream`) in async API.  Events look like MongoDB change events:
+//!
+//! ```json
+//! {
+//!   "_id": 7,
+//!   "operationType": "update",
+//!   "documentKey": { "_id": "..." },
+//!   "fullDocument": { "_id": "...", "name": "Rob", "age": 26 },
+//!   "updateDescription": { "updatedFields": { "age": 26 }, "removedFields": [] }
+//! }
+//! ```
+//!
+//! Every collection keeps last `CHANGE_HISTORY` events once it is watched for the first time.
+//! Event `_id` is resume token, stream opened with `WatchOptions::resume_after` continues
+//! after that event while it is still kept.  Watcher that falls behind by more than
+//! `CHANGE_HISTORY` events gets `MQChangeStreamLagged` error with number of events it
+//! missed and continues with the oldest kept event.  Changes made in transaction are
+//! reported when it commits.  Resume tokens are not persisted, they start again from 0
+//! when durable database is opened.
+//!
+
+use super::{errors::Error, index::ID_FIELD, query::CompiledQuery};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+#[cfg(feature = "async")]
+use std::{
+  pin::Pin,
+  task::{Context, Poll, Waker},
+};
+
+/// Number of events kept by collection for resuming and for watchers that fall behind.
+pub const CHANGE_HISTORY: usize = 1024;
+
+/// Position of event in change stream of collection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ResumeToken(pub u64);
+
+/// Kind of change.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationType {
+  Insert,
+  Update,
+  Delete,
+}
+
+/// Top-level fields changed by update.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDescription {
+  /// Fields that were added or changed, with their new values.
+  pub updated_fields: Map<String, Value>,
+  /// Fields that were removed.
+  pub removed_fields: Vec<String>,
+}
+
+/// Change of one document.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+  #[serde(rename = "_id")]
+  pub resume_token: ResumeToken,
+  pub operation_type: OperationType,
+  /// `_id` of changed document as `{ "_id": ... }`.
+  pub document_key: Value,
+  /// Inserted document or document after update.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub full_document: Option<Value>,
+  /// Deleted document.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub full_document_before_change: Option<Value>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub update_description: Option<UpdateDescription>,
+}
+
+fn document_key(document: &Value) -> Value {
+  json!({ ID_FIELD: document.get(ID_FIELD).cloned().unwrap_or(Value::Null) })
+}
+
+impl ChangeEvent {
+  pub(crate) fn insert(document: &Value) -> ChangeEvent {
+    ChangeEvent {
+      resume_token: ResumeToken(0),
+      operation_type: OperationType::Insert,
+      document_key: document_key(document),
+      full_document: Some(document.clone()),
+      full_document_before_change: None,
+      update_description: None,
+    }
+  }
+
+  pub(crate) fn update(before: &Value, after: &Value) -> ChangeEvent {
+    let empty = Map::new();
+    let before_fields = before.as_object().unwrap_or(&empty);
+    let after_fields = after.as_object().unwrap_or(&empty);
+    let description = UpdateDescription {
+      updated_fields: after_fields
+        .iter()
+        .filter(|(field, value)| before_fields.get(*field) != Some(*value))
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect(),
+      removed_fields: before_fields
+        .keys()
+        .filter(|field| !after_fields.contains_key(*field))
+        .cloned()
+        .collect(),
+    };
+    ChangeEvent {
+      resume_token: ResumeToken(0),
+      operation_type: OperationType::Update,
+      document_key: document_key(after),
+      full_document: Some(after.clone()),
+      full_document_before_change: None,
+      update_description: Some(description),
+    }
+  }
+
+  pub(crate) fn delete(document: &Value) -> ChangeEvent {
+    ChangeEvent {
+      resume_token: ResumeToken(0),
+      operation_type: OperationType::Delete,
+      document_key: document_key(document),
+      full_document: None,
+      full_document_before_change: Some(document.clone()),
+      update_description: None,
+    }
+  }
+
+  /// Document that watch filter is matched against.
+  fn document(&self) -> Option<&Value> {
+    self
+      .full_document
+      .as_ref()
+      .or(self.full_document_before_change.as_ref())
+  }
+}
+
+/// Options for `Collection::watch_with_options`.
+#[derive(Clone, Debug, Default)]
+pub struct WatchOptions {
+  /// Start after event with this token instead of with the next change.
+  pub resume_after: Option<ResumeToken>,
+}
+
+#[derive(Default)]
+struct HubState {
+  enabled: bool,
+  closed: bool,
+  next_token: u64,
+  events: VecDeque<ChangeEvent>,
+  #[cfg(feature = "async")]
+  wakers: Vec<Waker>,
+}
+
+impl HubState {
+  fn oldest_token(&self) -> u64 {
+    self.next_token - self.events.len() as u64
+  }
+}
+
+/// Events of one collection shared by its watchers.
+#[derive(Default)]
+pub(crate) struct ChangeHub {
+  state: Mutex<HubState>,
+  ready: Condvar,
+}
+
+impl ChangeHub {
+  fn state(&self) -> MutexGuard<'_, HubState> {
+    self.state.lock().unwrap()
+  }
+
+  /// True once collection has been watched, events are not built before that.
+  pub fn is_enabled(&self) -> bool {
+    self.state().enabled
+  }
+
+  /// Assigns resume tokens to events and wakes watchers.
+  pub fn publish(&self, events: Vec<ChangeEvent>) {
+    let mut state = self.state();
+    for mut event in events {
+      event.resume_token = ResumeToken(state.next_token);
+      state.next_token += 1;
+      if state.events.len() == CHANGE_HISTORY {
+        state.events.pop_front();
+      }
+      state.events.push_back(event);
+    }
+    self.wake(state);
+  }
+
+  /// Ends streams of deleted or replaced collection once they read remaining events.
+  pub fn close(&self) {
+    let mut state = self.state();
+    state.closed = true;
+    self.wake(state);
+  }
+
+  #[allow(unused_mut)]
+  fn wake(&self, mut state: MutexGuard<'_, HubState>) {
+    #[cfg(feature = "async")]
+    for waker in state.wakers.drain(..) {
+      waker.wake();
+    }
+    drop(state);
+    self.ready.notify_all();
+  }
+
+  pub fn subscribe(
+    self: &Arc<Self>,
+    filter: CompiledQuery,
+    options: WatchOptions,
+  ) -> Result<Subscription, Error> {
+    let mut state = self.state();
+    state.enabled = true;
+    let next = match options.resume_after {
+      Some(ResumeToken(token)) => {
+        if token >= state.next_token {
+          return Err(Error::MQInvalidValue(format!(
+            "Resume token {} is not in change stream.",
+            token
+          )));
+        }
+        if token + 1 < state.oldest_token() {
+          return Err(Error::MQChangeStreamLagged(
+            state.oldest_token() - token - 1,
+          ));
+        }
+        token + 1
+      }
+      None => state.next_token,
+    };
+    Ok(Subscription {
+      hub: self.clone(),
+      next,
+      filter,
+    })
+  }
+}
+
+/// Position of one watcher in change stream.
+pub(crate) struct Subscription {
+  hub: Arc<ChangeHub>,
+  next: u64,
+  filter: CompiledQuery,
+}
+
+impl Subscription {
+  /// Next matching event that is already published, `None` if watcher has read all of them.
+  fn next_event(&mut self, state: &HubState) -> Result<Option<ChangeEvent>, Error> {
+    let oldest = state.oldest_token();
+    if self.next < oldest {
+      let skipped = oldest - self.next;
+      self.next = oldest;
+      return Err(Error::MQChangeStreamLagged(skipped));
+    }
+    while self.next < state.next_token {
+      let event = &state.events[(self.next - oldest) as usize];
+      self.next += 1;
+      if let Some(document) = event.document() {
+        if self.filter.matches(document)? {
+          return Ok(Some(event.clone()));
+        }
+      }
+    }
+    if state.closed {
+      return Err(Error::MQChangeStreamClosed);
+    }
+    Ok(None)
+  }
+
+  /// Waits for next matching event, at most `timeout` if it is set.
+  fn wait(&mut self, timeout: Option<Duration>) -> Result<Option<ChangeEvent>, Error> {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let hub = self.hub.clone();
+    let mut state = hub.state();
+    loop {
+      if let Some(event) = self.next_event(&state)? {
+        return Ok(Some(event));
+      }
+      state = match deadline {
+        None => hub.ready.wait(state).unwrap(),
+        Some(deadline) => {
+          let now = Instant::now();
+          if now >= deadline {
+            return Ok(None);
+          }
+          hub.ready.wait_timeout(state, deadline - now).unwrap().0
+        }
+      };
+    }
+  }
+
+  #[cfg(feature = "async")]
+  fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Result<ChangeEvent, Error>> {
+    let hub = self.hub.clone();
+    let mut state = hub.state();
+    match self.next_event(&state) {
+      Ok(Some(event)) => Poll::Ready(Ok(event)),
+      Err(e) => Poll::Ready(Err(e)),
+      Ok(None) => {
+        if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+          state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+      }
+    }
+  }
+}
+
+/// Blocking change stream returned by `blocking::Collection::watch`.
+///
+/// Iterating over receiver waits for events and ends when collection is deleted.
+pub struct ChangeReceiver {
+  subscription: Subscription,
+}
+
+impl ChangeReceiver {
+  pub(crate) fn new(subscription: Subscription) -> ChangeReceiver {
+    ChangeReceiver { subscription }
+  }
+
+  /// Waits for next event.  Returns `MQChangeStreamLagged` if events were missed and
+  /// `MQChangeStreamClosed` once collection is deleted and all events are read.
+  pub fn recv(&mut self) -> Result<ChangeEvent, Error> {
+    self
+      .subscription
+      .wait(None)
+      .map(|event| event.expect("waiting without timeout returns event"))
+  }
+
+  /// Waits at most `timeout` for next event, returns `None` if there was none.
+  pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<ChangeEvent>, Error> {
+    self.subscription.wait(Some(timeout))
+  }
+
+  /// Returns next event if it is already available.
+  pub fn try_recv(&mut self) -> Result<Option<ChangeEvent>, Error> {
+    let hub = self.subscription.hub.clone();
+    let state = hub.state();
+    self.subscription.next_event(&state)
+  }
+}
+
+impl Iterator for ChangeReceiver {
+  type Item = Result<ChangeEvent, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self.recv() {
+      Err(Error::MQChangeStreamClosed) => None,
+      result => Some(result),
+    }
+  }
+}
+
+/// Async change stream returned by `r#async::Collection::watch`.
+///
+/// Stream ends when collection is deleted.  It does not depend on any runtime.
+#[cfg(feature = "async")]
+pub struct ChangeStream {
+  subscription: Subscription,
+}
+
+#[cfg(feature = "async")]
+impl ChangeStream {
+  pub(crate) fn new(subscription: Subscription) -> ChangeStream {
+    ChangeStream { subscription }
+  }
+
+  /// Waits for next event.  Returns `MQChangeStreamLagged` if events were missed and
+  /// `MQChangeStreamClosed` once collection is deleted and all events are read.
+  pub async fn recv(&mut self) -> Result<ChangeEvent, Error> {
+    std::future::poll_fn(|cx| self.subscription.poll_event(cx)).await
+  }
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for ChangeStream {
+  type Item = Result<ChangeEvent, Error>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    match self.get_mut().subscription.poll_event(cx) {
+      Poll::Ready(Err(Error::MQChangeStreamClosed)) => Poll::Ready(None),
+      Poll::Ready(result) => Poll::Ready(Some(result)),
+      Poll::Pending => Poll::Pending,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_update_description() {
+    let event = ChangeEvent::update(
+      &json!({ "_id": 1, "name": "Rob", "age": 25, "city": "NY" }),
+      &json!({ "_id": 1, "name": "Rob", "age": 26, "tags": ["a"] }),
+    );
+    let description = event.update_description.unwrap();
+    assert_eq!(
+      Value::Object(description.updated_fields),
+      json!({ "age": 26, "tags": ["a"] })
+    );
+    assert_eq!(description.removed_fields, vec!["city"]);
+    assert_eq!(event.document_key, json!({ "_id": 1 }));
+  }
+
+  #[test]
+  fn test_history_and_lag() -> Result<(), Error> {
+    let hub = Arc::new(ChangeHub::default());
+    let all = || CompiledQuery::new(&json!({})).unwrap();
+    let mut subscription = hub.subscribe(all(), WatchOptions::default())?;
+    let events = (0..CHANGE_HISTORY + 2)
+      .map(|i| ChangeEvent::insert(&json!({ "_id": i })))
+      .collect();
+    hub.publish(events);
+
+    let state = hub.state();
+    assert!(matches!(
+      subscription.next_event(&state),
+      Err(Error::MQChangeStreamLagged(2))
+    ));
+    assert_eq!(
+      subscription.next_event(&state)?.unwrap().resume_token,
+      ResumeToken(2)
+    );
+    drop(state);
+
+    let options = WatchOptions {
+      resume_after: Some(ResumeToken(0)),
+    };
+    assert!(matches!(
+      hub.subscribe(all(), options),
+      Err(Error::MQChangeStreamLagged(1))
+    ));
+    Ok(())
+  }
+}
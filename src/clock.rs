@@ -0,0 +1,69 @@
+//! Clocks that tell collections current time for TTL indexes.
+//!
+//! Collection uses `SystemClock` unless other clock is configured with `MemDb::with_clock`
+//! or `Collection::with_clock`.  `ManualClock` lets tests move time forward and expire
+//! documents without waiting.
+//!
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of current time.
+pub trait Clock: Send + Sync {
+  /// Milliseconds since Unix epoch, same as `$date` values.
+  fn now_millis(&self) -> i64;
+}
+
+/// Time of operating system.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now_millis(&self) -> i64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_millis() as i64)
+      .unwrap_or(0)
+  }
+}
+
+/// Clock that only changes when it is set or advanced.
+///
+/// ```
+/// use memquery::clock::{Clock, ManualClock};
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new(1_000);
+/// clock.advance(Duration::from_secs(2));
+/// assert_eq!(clock.now_millis(), 3_000);
+/// ```
+#[derive(Debug, Default)]
+pub struct ManualClock {
+  millis: AtomicI64,
+}
+
+impl ManualClock {
+  pub fn new(millis: i64) -> ManualClock {
+    ManualClock {
+      millis: AtomicI64::new(millis),
+    }
+  }
+
+  /// Sets current time in milliseconds since Unix epoch.
+  pub fn set(&self, millis: i64) {
+    self.millis.store(millis, Ordering::SeqCst);
+  }
+
+  /// Moves current time forward.
+  pub fn advance(&self, duration: Duration) {
+    self
+      .millis
+      .fetch_add(duration.as_millis() as i64, Ordering::SeqCst);
+  }
+}
+
+impl Clock for ManualClock {
+  fn now_millis(&self) -> i64 {
+    self.millis.load(Ordering::SeqCst)
+  }
+}
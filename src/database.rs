@@ -0,0 +1,520 @@
+//! Database core shared by `blocking` and `r#async` front-ends.
+//!
+//! Keeps collections by name and gives every new collection its own `_id` generator and
+//! clock shared by the database.  With
+//! `persistence` feature it records creation and deletion of collections in write-ahead log
+//! and attaches the log to collection data, see `wal` module.
+//!
+
+use super::{
+  clock::{Clock, SystemClock},
+  document::Document,
+  engine::{CollectionData, DocumentCollection, Engine},
+  errors::Error,
+  id_generator::IdGenerator,
+  options::CollectionOptions,
+  snapshot::Snapshot,
+  stats::{CollectionStats, DatabaseStats},
+  transaction::Transaction,
+  view::DatabaseView,
+};
+#[cfg(feature = "persistence")]
+use super::{
+  query::CompiledQuery,
+  snapshot::write_atomically,
+  wal::{
+    read_log, read_snapshot, CollectionLog, PersistenceOptions, WalEntry, WalOperation,
+    WriteAheadLog,
+  },
+};
+use std::collections::{BTreeMap, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "tokio")]
+use std::sync::Weak;
+use std::sync::{Arc, Mutex, RwLock};
+
+type IdGeneratorFactory = Arc<dyn Fn() -> Arc<dyn IdGenerator> + Send + Sync>;
+
+/// Write-ahead log of durable database and where its files are.
+#[cfg(feature = "persistence")]
+struct Persistence {
+  options: PersistenceOptions,
+  wal: Arc<WriteAheadLog>,
+}
+
+/// Collections of database.  Lock of collection list is taken before lock of collection
+/// data, never the other way round.
+pub(crate) struct Database {
+  collections: Arc<Mutex<HashMap<String, DocumentCollection>>>,
+  id_generator: IdGeneratorFactory,
+  clock: Arc<dyn Clock>,
+  #[cfg(feature = "persistence")]
+  persistence: Option<Persistence>,
+}
+
+impl Database {
+  pub fn with_id_generator<F, G>(factory: F) -> Database
+  where
+    F: Fn() -> G + Send + Sync + 'static,
+    G: IdGenerator + 'static,
+  {
+    Database {
+      collections: Arc::new(Mutex::new(HashMap::new())),
+      id_generator: Arc::new(move || Arc::new(factory()) as Arc<dyn IdGenerator>),
+      clock: Arc::new(SystemClock),
+      #[cfg(feature = "persistence")]
+      persistence: None,
+    }
+  }
+
+  /// Uses `clock` for TTL indexes of existing and new collections.
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Database {
+    for collection in self.collections.lock().unwrap().values() {
+      collection.write().unwrap().set_clock(clock.clone());
+    }
+    self.clock = clock;
+    self
+  }
+
+  /// Empty collection that is not recorded in write-ahead log.
+  fn new_collection(&self, options: CollectionOptions) -> DocumentCollection {
+    let data = CollectionData::with_id_generator((self.id_generator)())
+      .with_clock(self.clock.clone())
+      .with_options(options);
+    Arc::new(RwLock::new(data))
+  }
+
+  /// Empty collection that records its changes in write-ahead log of the database.
+  fn new_logged_collection(&self, name: &str, options: CollectionOptions) -> DocumentCollection {
+    let data = CollectionData::with_id_generator((self.id_generator)())
+      .with_clock(self.clock.clone())
+      .with_options(options);
+    #[cfg(feature = "persistence")]
+    let data = data.with_log(self.collection_log(name));
+    #[cfg(not(feature = "persistence"))]
+    let _ = name;
+    Arc::new(RwLock::new(data))
+  }
+
+  #[cfg(feature = "persistence")]
+  fn collection_log(&self, name: &str) -> Option<CollectionLog> {
+    self.persistence.as_ref().map(|p| CollectionLog {
+      wal: p.wal.clone(),
+      collection: name.to_string(),
+    })
+  }
+
+  /// Logs creation or deletion of collection.  Collection that is replaced or deleted
+  /// stops logging, so that writes through its remaining handles are not replayed.
+  #[cfg(feature = "persistence")]
+  fn log_collection_operation(
+    &self,
+    name: &str,
+    operation: WalOperation,
+    replaced: Option<&DocumentCollection>,
+  ) -> Result<(), Error> {
+    if let Some(persistence) = &self.persistence {
+      persistence.wal.append(name, operation)?;
+      if let Some(replaced) = replaced {
+        replaced.write().unwrap().set_log(None);
+      }
+    }
+    Ok(())
+  }
+
+  #[cfg(feature = "persistence")]
+  fn next_lsn(snapshot: &Snapshot, entries: &[WalEntry]) -> u64 {
+    snapshot
+      .collections
+      .values()
+      .filter_map(|c| c.lsn)
+      .chain(snapshot.lsn)
+      .chain(entries.iter().map(|e| e.lsn + 1))
+      .max()
+      .unwrap_or(0)
+  }
+
+  /// Creates collection, returns `MQCollectionExists` if there already is collection with
+  /// the same name.  In durable database collection is not created if it cannot be written
+  /// to the log.
+  pub fn create_collection(&self, name: &str) -> Result<(), Error> {
+    self.create_collection_with_options(name, CollectionOptions::default())
+  }
+
+  /// Creates collection with options, returns `MQCollectionExists` if there already is
+  /// collection with the same name.
+  pub fn create_collection_with_options(
+    &self,
+    name: &str,
+    options: CollectionOptions,
+  ) -> Result<(), Error> {
+    self.add_collection(name, options, false)
+  }
+
+  /// Creates collection with options, replacing existing one with the same name.
+  pub fn replace_collection(&self, name: &str, options: CollectionOptions) -> Result<(), Error> {
+    self.add_collection(name, options, true)
+  }
+
+  fn add_collection(
+    &self,
+    name: &str,
+    options: CollectionOptions,
+    replace: bool,
+  ) -> Result<(), Error> {
+    options.validate()?;
+    let new_collection = self.new_logged_collection(name, options.clone());
+    let mut collections = self.collections.lock().unwrap();
+    if !replace && collections.contains_key(name) {
+      return Err(Error::MQCollectionExists(name.to_string()));
+    }
+    #[cfg(feature = "persistence")]
+    self.log_collection_operation(
+      name,
+      WalOperation::CreateCollection { options },
+      collections.get(name),
+    )?;
+    #[cfg(not(feature = "persistence"))]
+    let _ = options;
+    if let Some(replaced) = collections.insert(name.to_string(), new_collection) {
+      replaced.read().unwrap().close_changes();
+    }
+    Ok(())
+  }
+
+  pub fn collection(&self, name: &str) -> Result<DocumentCollection, Error> {
+    match self.collections.lock().unwrap().get(name) {
+      Some(c) => Ok(c.clone()),
+      None => Err(Error::MQCollectionNotFound(name.to_string())),
+    }
+  }
+
+  /// Names of all collections, sorted.
+  pub fn collection_names(&self) -> Vec<String> {
+    let mut names: Vec<String> = self.collections.lock().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+  }
+
+  pub fn collection_exists(&self, name: &str) -> bool {
+    self.collections.lock().unwrap().contains_key(name)
+  }
+
+  /// Renames collection, existing handles of collection stay valid.  Existing collection
+  /// named `to` is replaced only if `drop_target` is set.
+  pub fn rename_collection(&self, from: &str, to: &str, drop_target: bool) -> Result<(), Error> {
+    let mut collections = self.collections.lock().unwrap();
+    if !collections.contains_key(from) {
+      return Err(Error::MQCollectionNotFound(from.to_string()));
+    }
+    if from == to {
+      return Err(Error::MQInvalidValue(format!(
+        "Collection {} cannot be renamed to itself.",
+        from
+      )));
+    }
+    if !drop_target && collections.contains_key(to) {
+      return Err(Error::MQCollectionExists(to.to_string()));
+    }
+    #[cfg(feature = "persistence")]
+    self.log_collection_operation(
+      from,
+      WalOperation::RenameCollection {
+        to: to.to_string(),
+        drop_target,
+      },
+      collections.get(to),
+    )?;
+
+    let collection = collections
+      .remove(from)
+      .ok_or_else(|| Error::MQCollectionNotFound(from.to_string()))?;
+    #[cfg(feature = "persistence")]
+    collection.write().unwrap().set_log(self.collection_log(to));
+    if let Some(replaced) = collections.insert(to.to_string(), collection) {
+      replaced.read().unwrap().close_changes();
+    }
+    Ok(())
+  }
+
+  pub fn delete_collection(&self, name: &str) -> Result<DocumentCollection, Error> {
+    let mut collections = self.collections.lock().unwrap();
+    #[cfg(feature = "persistence")]
+    if let Some(collection) = collections.get(name) {
+      self.log_collection_operation(name, WalOperation::DeleteCollection, Some(collection))?;
+    }
+    let removed = collections
+      .remove(name)
+      .ok_or_else(|| Error::MQCollectionNotFound(name.to_string()))?;
+    removed.read().unwrap().close_changes();
+    Ok(removed)
+  }
+
+  /// Creates collection for `Document` type together with its declared indexes.
+  pub fn create_collection_for<T: Document>(&self) -> Result<DocumentCollection, Error> {
+    self.create_collection(T::COLLECTION_NAME)?;
+    let collection = self.collection(T::COLLECTION_NAME)?;
+    let engine = Engine::with_collection(collection.clone());
+    for index in T::indexes() {
+      let keys: Vec<&str> = index.keys.iter().map(|k| k.as_str()).collect();
+      engine.create_index(&keys, index.options)?;
+    }
+    Ok(collection)
+  }
+
+  /// Removes expired documents from all collections and returns their number.
+  pub fn purge_expired(&self) -> Result<u64, Error> {
+    Database::purge_collections(&self.collections)
+  }
+
+  /// Statistics of all collections, collection lock is taken only after collection list
+  /// is released.
+  pub fn stats(&self) -> DatabaseStats {
+    let collections: Vec<DocumentCollection> =
+      self.collections.lock().unwrap().values().cloned().collect();
+    let stats: Vec<CollectionStats> = collections
+      .into_iter()
+      .map(|collection| Engine::with_collection(collection).stats())
+      .collect();
+    DatabaseStats::new(&stats)
+  }
+
+  /// Collection list that background task can use without keeping database alive.
+  #[cfg(feature = "tokio")]
+  pub fn collections_weak(&self) -> Weak<Mutex<HashMap<String, DocumentCollection>>> {
+    Arc::downgrade(&self.collections)
+  }
+
+  /// Collection lock is taken only after collection list is released.
+  pub fn purge_collections(
+    collections: &Mutex<HashMap<String, DocumentCollection>>,
+  ) -> Result<u64, Error> {
+    let collections: Vec<DocumentCollection> =
+      collections.lock().unwrap().values().cloned().collect();
+    let mut purged = 0;
+    for collection in collections {
+      purged += Engine::with_collection(collection).purge_expired()?;
+    }
+    Ok(purged)
+  }
+
+  /// Runs `f` in transaction over collections named in `names`.  Collection list is locked
+  /// only while collections are looked up, missing collection is reported before `f` runs.
+  ///
+  /// If `f` panics changes are rolled back and locks are released before panic continues,
+  /// so they are not poisoned.
+  pub fn transaction<F, R>(&self, names: &[&str], f: F) -> Result<R, Error>
+  where
+    F: FnOnce(&mut Transaction) -> Result<R, Error>,
+  {
+    let collections: BTreeMap<String, DocumentCollection> = {
+      let collections = self.collections.lock().unwrap();
+      names
+        .iter()
+        .map(|name| match collections.get(*name) {
+          Some(collection) => Ok((name.to_string(), collection.clone())),
+          None => Err(Error::MQCollectionNotFound(name.to_string())),
+        })
+        .collect::<Result<_, Error>>()?
+    };
+    let mut transaction = Transaction::begin(&collections);
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&mut transaction))) {
+      Ok(Ok(result)) => {
+        transaction.commit()?;
+        Ok(result)
+      }
+      Ok(Err(e)) => {
+        transaction.rollback();
+        Err(e)
+      }
+      Err(payload) => {
+        transaction.rollback();
+        panic::resume_unwind(payload)
+      }
+    }
+  }
+
+  /// Point-in-time view of all collections.
+  pub fn view(&self) -> DatabaseView {
+    DatabaseView::new(&self.collections.lock().unwrap())
+  }
+
+  /// Copies all collections with their index definitions.
+  pub fn snapshot(&self) -> Snapshot {
+    self.view().to_snapshot()
+  }
+
+  /// Loads collections from snapshot, replacing collections with the same name.  If
+  /// snapshot is invalid database is not changed.  Durable database is compacted
+  /// afterwards so that loaded collections are stored in its snapshot.
+  pub fn load(&self, snapshot: Snapshot) -> Result<(), Error> {
+    let loaded = self.restore_collections(snapshot)?;
+    #[cfg(feature = "persistence")]
+    {
+      let mut collections = self.collections.lock().unwrap();
+      for (name, collection) in loaded {
+        collection
+          .write()
+          .unwrap()
+          .set_log(self.collection_log(&name));
+        if let Some(replaced) = collections.insert(name, collection) {
+          let mut replaced = replaced.write().unwrap();
+          replaced.set_log(None);
+          replaced.close_changes();
+        }
+      }
+      drop(collections);
+      if self.persistence.is_some() {
+        self.compact()?;
+      }
+    }
+    #[cfg(not(feature = "persistence"))]
+    {
+      let mut collections = self.collections.lock().unwrap();
+      for (name, collection) in loaded {
+        if let Some(replaced) = collections.insert(name, collection) {
+          replaced.read().unwrap().close_changes();
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Builds collections from snapshot without adding them to database.
+  fn restore_collections(
+    &self,
+    snapshot: Snapshot,
+  ) -> Result<Vec<(String, DocumentCollection)>, Error> {
+    let mut loaded = Vec::new();
+    for (name, collection_snapshot) in snapshot.collections {
+      let options = collection_snapshot.options;
+      options.validate()?;
+      // documents were accepted when they were written, validator is enabled only after
+      // they are loaded
+      let collection = self.new_collection(CollectionOptions {
+        validator: None,
+        ..options.clone()
+      });
+      let engine = Engine::with_collection(collection.clone());
+      for index in collection_snapshot.indexes {
+        let keys: Vec<&str> = index.keys.iter().map(|k| k.as_str()).collect();
+        engine.create_index(&keys, index.options())?;
+      }
+      for document in collection_snapshot.documents {
+        engine.insert(document)?;
+      }
+      collection.write().unwrap().set_options(options);
+      loaded.push((name, collection));
+    }
+    Ok(loaded)
+  }
+
+  /// Loads snapshot and log in `options.dir`, then records every change in the log.
+  /// Database must not have any collections yet.
+  #[cfg(feature = "persistence")]
+  pub fn with_persistence(mut self, options: PersistenceOptions) -> Result<Database, Error> {
+    if !self.collections.lock().unwrap().is_empty() {
+      return Err(Error::MQError(String::from(
+        "Persistence must be enabled before collections are created.",
+      )));
+    }
+
+    std::fs::create_dir_all(&options.dir)?;
+    let snapshot = read_snapshot(&options.snapshot_path())?;
+    let entries = read_log(&options.log_path())?;
+    let next_lsn = Database::next_lsn(&snapshot, &entries);
+
+    let restored = self.restore_collections(snapshot.clone())?;
+    self.collections.lock().unwrap().extend(restored);
+    for entry in entries.into_iter().flat_map(WalEntry::changes) {
+      if !entry.is_in_snapshot(&snapshot) {
+        self.replay(entry)?;
+      }
+    }
+
+    let wal = WriteAheadLog::open(&options.log_path(), options.fsync, next_lsn)?;
+    self.persistence = Some(Persistence {
+      options,
+      wal: Arc::new(wal),
+    });
+    for (name, collection) in self.collections.lock().unwrap().iter() {
+      collection
+        .write()
+        .unwrap()
+        .set_log(self.collection_log(name));
+    }
+    Ok(self)
+  }
+
+  /// Applies log entry to database that is not logging yet.
+  #[cfg(feature = "persistence")]
+  fn replay(&self, entry: WalEntry) -> Result<(), Error> {
+    let name = entry.collection.as_str();
+    let engine = match entry.operation {
+      WalOperation::CreateCollection { options } => {
+        return self.replace_collection(name, options);
+      }
+      WalOperation::DeleteCollection => return self.delete_collection(name).map(|_| ()),
+      WalOperation::RenameCollection { to, drop_target } => {
+        return self.rename_collection(name, &to, drop_target);
+      }
+      _ => match self.collection(name) {
+        Ok(collection) => Engine::with_collection(collection),
+        // written through handle of deleted collection
+        Err(Error::MQCollectionNotFound(_)) => return Ok(()),
+        Err(e) => return Err(e),
+      },
+    };
+
+    match entry.operation {
+      WalOperation::Insert { document } => engine.insert(document)?,
+      WalOperation::Update { query, update } => {
+        engine.find_and_update(&CompiledQuery::new(&query)?, &update)?;
+      }
+      WalOperation::Delete { query } => {
+        engine.find_and_delete(&CompiledQuery::new(&query)?)?;
+      }
+      WalOperation::CreateIndex { index } => {
+        let keys: Vec<&str> = index.keys.iter().map(|k| k.as_str()).collect();
+        engine.create_index(&keys, index.options())?;
+      }
+      WalOperation::DropIndex { name } => engine.drop_index(&name)?,
+      // transactions are split into their changes before replay
+      WalOperation::CreateCollection { .. }
+      | WalOperation::DeleteCollection
+      | WalOperation::RenameCollection { .. }
+      | WalOperation::Transaction { .. } => (),
+    }
+    Ok(())
+  }
+
+  /// Writes snapshot of durable database and removes log entries that it contains.
+  /// Writes may continue while database is compacted.
+  #[cfg(feature = "persistence")]
+  pub fn compact(&self) -> Result<(), Error> {
+    let persistence = self
+      .persistence
+      .as_ref()
+      .ok_or_else(|| Error::MQError(String::from("Persistence is not enabled.")))?;
+
+    let mut snapshot = Snapshot::new();
+    let collections: Vec<(String, DocumentCollection)> = {
+      let collections = self.collections.lock().unwrap();
+      snapshot.lsn = Some(persistence.wal.next_lsn());
+      collections
+        .iter()
+        .map(|(name, collection)| (name.clone(), collection.clone()))
+        .collect()
+    };
+    for (name, collection) in collections {
+      let collection_snapshot = Engine::with_collection(collection).snapshot();
+      // collection deleted or replaced since the list was taken
+      if collection_snapshot.lsn.is_some() {
+        snapshot.collections.insert(name, collection_snapshot);
+      }
+    }
+
+    write_atomically(&persistence.options.snapshot_path(), &snapshot.to_vec()?)?;
+    persistence.wal.compact(&snapshot)
+  }
+}
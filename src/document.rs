@@ -0,0 +1,107 @@
+//! Rust structs stored as documents.
+//!
+//! `Document` is usually derived with `#[derive(Document)]` which generates collection
+//! name, index declarations, `_id` mapping and typed field-path constants.
+//!
+
+use super::{
+  errors::Error,
+  index::{IndexModel, ID_FIELD},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Struct that is stored in its own collection.
+///
+/// Derive attributes:
+///
+/// * `#[document(collection = "users")]` on struct sets collection name (defaults to struct name).
+/// * `#[document(index(keys("last", "first"), unique, sparse, name = "full_name"))]` on struct
+///   declares compound index.
+/// * `#[document(index)]` or `#[document(index(unique, sparse, name = "..."))]` on field
+///   declares single field index.
+/// * `#[document(id)]` on field marks field stored as `_id`.  Without it field named `id`
+///   (or serialized as `_id`) is used.  If id is missing or null when document is inserted
+///   collection's `IdGenerator` assigns one.
+///
+/// For every serialized field constant of type `builder::Field` is generated, named after the
+/// field in upper case (`User::AGE`, `User::ID`), that builds type checked filters.
+///
+/// ```
+/// # #[cfg(feature = "async")]
+/// use memquery::{errors::Error, memdb::MemDb, Document};
+/// use serde::{Deserialize, Serialize};
+///
+/// # #[cfg(feature = "async")]
+/// #[derive(Serialize, Deserialize, Document)]
+/// #[document(collection = "users")]
+/// struct User {
+///   id: Option<String>,
+///   #[document(index(unique))]
+///   email: String,
+///   age: u32,
+/// }
+///
+/// # #[cfg(feature = "async")]
+/// async fn play() -> Result<(), Error> {
+///   let memdb = MemDb::new();
+///   let users = memdb.create_collection_for::<User>().await?;
+///   users
+///     .insert(User { id: None, email: "tom@test.com".to_string(), age: 25 })
+///     .await?;
+///   let adults = users.find(User::AGE.gte(21)?.into()).await?;
+///   assert!(adults[0].id.is_some());
+///   Ok(())
+/// }
+/// ```
+pub trait Document: Serialize + DeserializeOwned {
+  /// Name of the collection storing documents of this type.
+  const COLLECTION_NAME: &'static str;
+
+  /// Serialized name of the field that is stored as `_id`.
+  const ID_FIELD: &'static str = ID_FIELD;
+
+  /// Indexes created together with collection.
+  fn indexes() -> Vec<IndexModel> {
+    Vec::new()
+  }
+
+  /// Serializes struct into document with id stored as `_id` field.
+  fn to_document(&self) -> Result<Value, Error> {
+    let mut document = serde_json::to_value(self)?;
+    let fields = match document.as_object_mut() {
+      Some(f) => f,
+      None => {
+        return Err(Error::MQError(String::from(
+          "Document must be a JSON object.",
+        )))
+      }
+    };
+
+    if Self::ID_FIELD != ID_FIELD {
+      if let Some(id) = fields.remove(Self::ID_FIELD) {
+        fields.insert(ID_FIELD.to_string(), id);
+      }
+    }
+
+    // null id is left for collection's IdGenerator
+    if let Some(Value::Null) = fields.get(ID_FIELD) {
+      fields.remove(ID_FIELD);
+    }
+
+    Ok(document)
+  }
+
+  /// Deserializes struct from stored document.
+  fn from_document(mut document: Value) -> Result<Self, Error> {
+    if Self::ID_FIELD != ID_FIELD {
+      if let Some(fields) = document.as_object_mut() {
+        if let Some(id) = fields.remove(ID_FIELD) {
+          fields.insert(Self::ID_FIELD.to_string(), id);
+        }
+      }
+    }
+
+    Ok(serde_json::from_value(document)?)
+  }
+}
@@ -1,26 +1,323 @@
-use super::{errors::Error, utils::*};
+use super::{
+  change_stream::{ChangeEvent, ChangeHub, Subscription, WatchOptions},
+  clock::{Clock, SystemClock},
+  errors::Error,
+  extended_json::is_extended,
+  id_generator::{IdGenerator, UuidV4},
+  import_export::{
+    export_document, ErrorPolicy, ExportFormat, ExportOptions, ImportOptions, ImportSummary, Record,
+  },
+  index::{Index, IndexDefinition, IndexKey, IndexOptions, ID_FIELD, ID_INDEX_NAME},
+  options::{CollectionOptions, CountOptions, FindOptions, ValidationAction, ValidationLevel},
+  planner::{plan, ExecutionStats, ExplainOptions, ExplainVerbosity, Plan},
+  query::CompiledQuery,
+  snapshot::CollectionSnapshot,
+  stats::{CollectionStats, OperationCounters},
+  utils::*,
+  validation::Validator,
+  view::CollectionView,
+};
+use im::OrdMap;
 use serde_json::{json, Value};
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
-#[cfg(feature = "sync")]
-use std::sync::Mutex;
-
-#[cfg(not(feature = "sync"))]
-use tokio::sync::Mutex;
+#[cfg(feature = "persistence")]
+use super::wal::{CollectionLog, WalOperation};
 
 pub type Documents = Vec<Value>;
 
-pub type DocumentCollection = Arc<Mutex<Documents>>;
+/// Collection data shared by all handles of collection.
+///
+/// Queries, explain and export take read lock only to copy collection data, changes take
+/// write lock.  Lock is held only while documents are copied or changed in memory, never
+/// across `.await`, so blocking and async front-ends share the same `std::sync::RwLock`.
+/// Writes are not starved by queries because copy of `CollectionData` takes constant time:
+/// readers scan their copy after the lock is released, so read sections are short and
+/// writer waits only for copies that are in progress, not for scans.
+pub type DocumentCollection = Arc<RwLock<CollectionData>>;
+
+/// Documents stored in collection together with their indexes.
+///
+/// Documents are kept in insertion order keyed by sequence number
+/// so that indexes can refer to them.
+///
+/// Documents and index entries are kept in persistent maps and documents are shared with
+/// `Arc`, so clone of collection data is cheap point-in-time copy that shares everything
+/// with the original until either of them changes.
+#[derive(Clone)]
+pub struct CollectionData {
+  docs: OrdMap<u64, Arc<Value>>,
+  next_seq: u64,
+  indexes: Vec<Index>,
+  id_generator: Arc<dyn IdGenerator>,
+  clock: Arc<dyn Clock>,
+  options: CollectionOptions,
+  validator: Option<Arc<Validator>>,
+  /// JSON size of all documents, kept only if collection is capped by `max_bytes`.
+  bytes: u64,
+  changes: Arc<ChangeHub>,
+  pending_changes: Option<Vec<ChangeEvent>>,
+  counters: Arc<OperationCounters>,
+  #[cfg(feature = "persistence")]
+  log: Option<CollectionLog>,
+  #[cfg(feature = "persistence")]
+  pending: Option<Vec<WalOperation>>,
+}
+
+impl CollectionData {
+  pub fn new() -> CollectionData {
+    CollectionData::with_id_generator(Arc::new(UuidV4))
+  }
+
+  /// Empty collection that assigns `_id` with specified generator.
+  pub fn with_id_generator(id_generator: Arc<dyn IdGenerator>) -> CollectionData {
+    CollectionData {
+      docs: OrdMap::new(),
+      next_seq: 0,
+      indexes: vec![Index::id_index()],
+      id_generator,
+      clock: Arc::new(SystemClock),
+      options: CollectionOptions::default(),
+      validator: None,
+      bytes: 0,
+      changes: Arc::new(ChangeHub::default()),
+      pending_changes: None,
+      counters: Arc::new(OperationCounters::default()),
+      #[cfg(feature = "persistence")]
+      log: None,
+      #[cfg(feature = "persistence")]
+      pending: None,
+    }
+  }
+
+  /// Same collection that reads current time for TTL indexes from `clock`.
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> CollectionData {
+    self.clock = clock;
+    self
+  }
+
+  pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+    self.clock = clock;
+  }
+
+  /// Same empty collection with `options`.
+  pub fn with_options(mut self, options: CollectionOptions) -> CollectionData {
+    self.set_options(options);
+    self
+  }
+
+  /// Replaces options, documents already in collection are not checked by new validator.
+  /// Options must have been validated.
+  pub fn set_options(&mut self, options: CollectionOptions) {
+    self.validator = options
+      .validator
+      .as_ref()
+      .and_then(|validator| Validator::new(validator).ok())
+      .map(Arc::new);
+    self.options = options;
+  }
+
+  pub fn options(&self) -> &CollectionOptions {
+    &self.options
+  }
+
+  fn max_bytes(&self) -> Option<u64> {
+    self.options.capped.as_ref().and_then(|c| c.max_bytes)
+  }
+
+  /// Size of document counted against `Capped::max_bytes`, 0 if collection has no such
+  /// limit so that other collections do not serialize documents.
+  fn tracked_size(&self, document: &Value) -> u64 {
+    match self.max_bytes() {
+      Some(_) => serde_json::to_vec(document)
+        .map(|bytes| bytes.len() as u64)
+        .unwrap_or(0),
+      None => 0,
+    }
+  }
+
+  /// Oldest documents that capped collection evicts so that `count` documents of `bytes`
+  /// size fit its limits.  `resized` has sizes of documents that are being updated.
+  fn evictions(&self, mut count: u64, mut bytes: u64, resized: &BTreeMap<u64, u64>) -> Vec<u64> {
+    let capped = match &self.options.capped {
+      Some(capped) => capped,
+      None => return Vec::new(),
+    };
+    let mut evicted = Vec::new();
+    for (seq, document) in self.docs.iter() {
+      let over_docs = capped.max_docs.is_some_and(|max| count > max);
+      let over_bytes = capped.max_bytes.is_some_and(|max| bytes > max);
+      if !over_docs && !over_bytes {
+        break;
+      }
+      evicted.push(*seq);
+      count -= 1;
+      bytes -= resized
+        .get(seq)
+        .copied()
+        .unwrap_or_else(|| self.tracked_size(document));
+    }
+    evicted
+  }
+
+  /// Number of documents in collection that have not expired.
+  pub fn len(&self) -> usize {
+    self.docs.len() - self.expired().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Iterates over documents that have not expired in insertion order.
+  pub fn documents(&self) -> impl Iterator<Item = &Value> {
+    let expired = self.expired();
+    self
+      .docs
+      .iter()
+      .filter(move |(seq, _)| !expired.contains(seq))
+      .map(|(_, document)| document.as_ref())
+  }
+
+  /// Documents expired by TTL indexes that are not removed yet.  Clock is not read if
+  /// collection has no TTL index.
+  pub(crate) fn expired(&self) -> BTreeSet<u64> {
+    let mut ttl_indexes = self
+      .indexes
+      .iter()
+      .filter(|index| index.definition.expire_after.is_some())
+      .peekable();
+    if ttl_indexes.peek().is_none() {
+      return BTreeSet::new();
+    }
+    let now = self.clock.now_millis();
+    ttl_indexes.flat_map(|index| index.expired(now)).collect()
+  }
+
+  /// Same collection with write-ahead log attached.
+  #[cfg(feature = "persistence")]
+  pub fn with_log(mut self, log: Option<CollectionLog>) -> CollectionData {
+    self.log = log;
+    self
+  }
+
+  /// Attaches write-ahead log that records changes of this collection, or detaches it.
+  #[cfg(feature = "persistence")]
+  pub fn set_log(&mut self, log: Option<CollectionLog>) {
+    self.log = log;
+  }
+
+  /// Appends operation to the log before it is applied, so that failed write leaves
+  /// collection untouched.  Inside transaction operation is kept until commit.
+  #[cfg(feature = "persistence")]
+  fn append_log<F>(&mut self, operation: F) -> Result<(), Error>
+  where
+    F: FnOnce() -> WalOperation,
+  {
+    if let Some(pending) = &mut self.pending {
+      pending.push(operation());
+    } else if let Some(log) = &self.log {
+      log.append(operation())?;
+    }
+    Ok(())
+  }
+
+  /// Log that records changes of this collection, if database is durable.
+  #[cfg(feature = "persistence")]
+  pub(crate) fn log(&self) -> Option<&CollectionLog> {
+    self.log.as_ref()
+  }
+
+  /// Keeps logged operations and change events in memory until transaction commits.
+  pub(crate) fn begin_pending(&mut self) {
+    #[cfg(feature = "persistence")]
+    if self.log.is_some() {
+      self.pending = Some(Vec::new());
+    }
+    if self.changes.is_enabled() {
+      self.pending_changes = Some(Vec::new());
+    }
+  }
+
+  /// Operations kept since `begin_pending`, later operations are appended to the log again.
+  #[cfg(feature = "persistence")]
+  pub(crate) fn take_pending(&mut self) -> Vec<WalOperation> {
+    self.pending.take().unwrap_or_default()
+  }
+
+  /// Publishes change events kept since `begin_pending` to watchers.
+  pub(crate) fn publish_pending(&mut self) {
+    if let Some(events) = self.pending_changes.take() {
+      self.changes.publish(events);
+    }
+  }
+
+  /// Reports change to watchers, event is built only if collection is watched.
+  fn record_change<F>(&mut self, event: F)
+  where
+    F: FnOnce() -> ChangeEvent,
+  {
+    if let Some(pending) = &mut self.pending_changes {
+      pending.push(event());
+    } else if self.changes.is_enabled() {
+      self.changes.publish(vec![event()]);
+    }
+  }
+
+  /// Ends change streams of collection that was deleted or replaced.
+  pub(crate) fn close_changes(&self) {
+    self.changes.close();
+  }
+
+  /// Log sequence number up to which changes are included in the collection, if it is
+  /// persistent.
+  #[cfg(feature = "persistence")]
+  pub(crate) fn log_position(&self) -> Option<u64> {
+    self.log.as_ref().map(|l| l.wal.next_lsn())
+  }
+
+  #[cfg(not(feature = "persistence"))]
+  pub(crate) fn log_position(&self) -> Option<u64> {
+    None
+  }
+}
+
+impl Default for CollectionData {
+  fn default() -> Self {
+    Self::new()
+  }
+}
 
 enum MathOpType {
   Inc,
   Mul,
 }
 
+impl MathOpType {
+  fn as_operator(&self) -> &'static str {
+    match self {
+      MathOpType::Inc => INC,
+      MathOpType::Mul => MUL,
+    }
+  }
+}
+
+/// True if update uses operators, false if it is replacement document.  Extended JSON
+/// value such as `{"$date": ...}` is a value, not an operator, so it is rejected.
 pub fn has_update_operations(update: &Value) -> Result<bool, Error> {
-  let update = update.as_object().unwrap();
-  let all_operators = update.keys().map(|k| k.starts_with("$")).all(|o| o == true);
-  if !all_operators && update.keys().map(|k| k.starts_with("$")).any(|o| o == true) {
+  let update = match update.as_object() {
+    Some(fields) if !is_extended(update) => fields,
+    _ => {
+      return Err(Error::MQInvalidValue(String::from(
+        "Update must be a document.",
+      )))
+    }
+  };
+  let all_operators = update.keys().all(|k| k.starts_with('$'));
+  if !all_operators && update.keys().any(|k| k.starts_with('$')) {
     return Err(Error::MQInvalidOp(String::from(
       "Cannot mix update operators with keys.",
     )));
@@ -28,20 +325,6 @@ pub fn has_update_operations(update: &Value) -> Result<bool, Error> {
   Ok(all_operators)
 }
 
-fn is_key_valid_op(key: &str) -> Result<(), Error> {
-  // if key contains comparison op check if it is valid
-  let (is_embedded, _) = is_embedded_query(key);
-  if is_embedded && key.contains("$") {
-    return Err(Error::MQOpNotAllowedInMultipartKey);
-  }
-
-  if is_op(key) && !matches!(key, EQ | GT | GTE | LT | LTE | NE | IN | NIN | AND | OR) {
-    return Err(Error::MQInvalidOp(format!("Op {} is not supported.", key)));
-  }
-
-  Ok(())
-}
-
 fn sum<T>(x: T, y: T) -> T
 where
   T: std::ops::Add<Output = T>,
@@ -58,6 +341,12 @@ where
   result
 }
 
+/// Operations on collection data shared by `blocking` and `r#async` front-ends.
+///
+/// Every operation takes the lock, works on documents in memory and releases the lock
+/// before it returns, so async front-end calls it directly.  Queries, explain and export
+/// hold read lock only while they copy collection data, then run on the copy, so long
+/// scans do not block writers.
 pub struct Engine {
   docs: DocumentCollection,
 }
@@ -67,82 +356,694 @@ impl Engine {
     Engine { docs }
   }
 
-  #[cfg(feature = "sync")]
-  pub fn find(&self, query: &Value) -> Result<Documents, Error> {
-    let mut result: Documents = Vec::new();
+  pub fn insert(&self, document: Value) -> Result<(), Error> {
+    self.insert_document(&mut self.docs.write().unwrap(), document)
+  }
 
-    for document in self.docs.lock().unwrap().iter() {
-      if self.perform_query(&query, document)? {
-        result.push(document.clone());
+  /// Inserts documents under one lock and returns result for each of them.  With
+  /// `stop_on_error` documents after the first one that fails are not inserted.
+  pub fn insert_batch(&self, documents: Vec<Value>, stop_on_error: bool) -> Vec<Result<(), Error>> {
+    self.insert_documents(&mut self.docs.write().unwrap(), documents, stop_on_error)
+  }
+
+  /// Point-in-time copy of collection data, lock is held only while it is copied.
+  pub fn current(&self) -> CollectionData {
+    self.docs.read().unwrap().clone()
+  }
+
+  pub fn find(&self, query: &CompiledQuery) -> Result<Documents, Error> {
+    self.find_documents(&self.current(), query)
+  }
+
+  pub fn find_one(&self, query: &CompiledQuery) -> Result<Option<Value>, Error> {
+    self.find_first_document(&self.current(), query)
+  }
+
+  /// Matching documents in insertion order or newest first, at most `options.limit` of them.
+  pub fn find_with_options(
+    &self,
+    query: &CompiledQuery,
+    options: &FindOptions,
+  ) -> Result<Documents, Error> {
+    let data = self.current();
+    data.counters.queried();
+    let mut matched = self.matching(&data, query)?;
+    if options.newest_first {
+      matched.reverse();
+    }
+    if let Some(limit) = options.limit {
+      matched.truncate(limit);
+    }
+    Ok(
+      matched
+        .iter()
+        .filter_map(|seq| data.docs.get(seq).map(|d| Value::clone(d)))
+        .collect(),
+    )
+  }
+
+  /// Number of matching documents after `options.skip` of them, at most `options.limit`.
+  /// Matching documents are not copied.
+  pub fn count_documents(
+    &self,
+    query: &CompiledQuery,
+    options: &CountOptions,
+  ) -> Result<u64, Error> {
+    let data = self.current();
+    data.counters.queried();
+    let count = self
+      .matching(&data, query)?
+      .len()
+      .saturating_sub(options.skip);
+    Ok(options.limit.map_or(count, |limit| count.min(limit)) as u64)
+  }
+
+  /// Number of documents stored in collection, including expired documents that are not
+  /// removed yet, read without scanning documents.
+  pub fn estimated_document_count(&self) -> u64 {
+    self.docs.read().unwrap().docs.len() as u64
+  }
+
+  pub fn find_and_update(&self, query: &CompiledQuery, update: &Value) -> Result<u64, Error> {
+    self.update_documents(&mut self.docs.write().unwrap(), query, update)
+  }
+
+  pub fn find_and_delete(&self, query: &CompiledQuery) -> Result<Documents, Error> {
+    self.delete_documents(&mut self.docs.write().unwrap(), query)
+  }
+
+  /// Converts matching documents with `decode` and deletes them only if all of them were
+  /// converted, so documents that typed collection can not read are not lost.
+  pub fn find_and_delete_with<T, F>(
+    &self,
+    query: &CompiledQuery,
+    decode: F,
+  ) -> Result<Vec<T>, Error>
+  where
+    F: Fn(Value) -> Result<T, Error>,
+  {
+    let mut data = self.docs.write().unwrap();
+    self.remove_expired(&mut data)?;
+    let decoded = self
+      .matching(&data, query)?
+      .iter()
+      .filter_map(|seq| data.docs.get(seq))
+      .map(|document| decode(Value::clone(document)))
+      .collect::<Result<Vec<T>, Error>>()?;
+    self.delete_documents(&mut data, query)?;
+    Ok(decoded)
+  }
+
+  /// Removes expired documents, write lock is taken only if there are some.
+  pub fn purge_expired(&self) -> Result<u64, Error> {
+    if self.docs.read().unwrap().expired().is_empty() {
+      return Ok(0);
+    }
+    self.remove_expired(&mut self.docs.write().unwrap())
+  }
+
+  pub fn create_index(&self, keys: &[&str], options: IndexOptions) -> Result<String, Error> {
+    self.add_index(&mut self.docs.write().unwrap(), keys, options)
+  }
+
+  pub fn drop_index(&self, name: &str) -> Result<(), Error> {
+    Self::remove_index(&mut self.docs.write().unwrap(), name)
+  }
+
+  pub fn list_indexes(&self) -> Vec<IndexDefinition> {
+    Self::index_definitions(&self.docs.read().unwrap())
+  }
+
+  pub fn view(&self) -> CollectionView {
+    CollectionView::new(&self.docs.read().unwrap())
+  }
+
+  /// Statistics computed on point-in-time copy of collection data.
+  pub fn stats(&self) -> CollectionStats {
+    let data = self.current();
+    let documents: Vec<&Value> = data.documents().collect();
+    CollectionStats::new(&documents, &data.indexes, data.counters.stats())
+  }
+
+  pub(crate) fn watch(
+    &self,
+    filter: CompiledQuery,
+    options: WatchOptions,
+  ) -> Result<Subscription, Error> {
+    let changes = self.docs.read().unwrap().changes.clone();
+    changes.subscribe(filter, options)
+  }
+
+  /// Matching documents and subscription to later changes taken under one lock, so that
+  /// tailable cursor neither misses nor repeats documents.
+  pub(crate) fn tail(&self, filter: CompiledQuery) -> Result<(Documents, Subscription), Error> {
+    let data = self.docs.read().unwrap();
+    if data.options.capped.is_none() {
+      return Err(Error::MQInvalidOp(String::from(
+        "Tailable cursor requires capped collection.",
+      )));
+    }
+    let documents = self.find_documents(&data, &filter)?;
+    let subscription = data.changes.subscribe(filter, WatchOptions::default())?;
+    Ok((documents, subscription))
+  }
+
+  pub fn snapshot(&self) -> CollectionSnapshot {
+    let (data, lsn) = {
+      let data = self.docs.read().unwrap();
+      (data.clone(), data.log_position())
+    };
+    Self::collection_snapshot(&data, lsn)
+  }
+
+  /// Inserts imported records in batches of `options.batch_size`, each under one lock.
+  pub fn import<I>(&self, records: I, options: ImportOptions) -> Result<ImportSummary, Error>
+  where
+    I: Iterator<Item = Result<Record, Error>>,
+  {
+    let stop_on_error = options.on_error == ErrorPolicy::Abort;
+    let mut summary = ImportSummary::default();
+    let mut lines = Vec::new();
+    let mut batch = Vec::new();
+    for record in records {
+      let record = record?;
+      match options.prepare(record.document) {
+        Ok(document) => {
+          lines.push(record.line);
+          batch.push(document);
+          if batch.len() >= options.batch_size() {
+            let results = self.insert_batch(std::mem::take(&mut batch), stop_on_error);
+            options.record_results(&mut summary, std::mem::take(&mut lines), results)?;
+          }
+        }
+        Err(e) => {
+          let results = self.insert_batch(std::mem::take(&mut batch), stop_on_error);
+          options.record_results(&mut summary, std::mem::take(&mut lines), results)?;
+          options.reject(&mut summary, record.line, e)?;
+        }
       }
     }
-    Ok(result)
+    let results = self.insert_batch(batch, stop_on_error);
+    options.record_results(&mut summary, lines, results)?;
+    Ok(summary)
+  }
+
+  /// Writes documents of point-in-time copy of collection, so export is consistent and
+  /// writes proceed while it runs.
+  pub fn export<W: Write>(
+    &self,
+    mut writer: W,
+    options: ExportOptions,
+    format: ExportFormat,
+  ) -> Result<u64, Error> {
+    let data = self.current();
+    let mut count = 0;
+    format.begin(&mut writer)?;
+    for document in data.documents() {
+      format.write(
+        &mut writer,
+        &export_document(document.clone(), &options),
+        count == 0,
+      )?;
+      count += 1;
+    }
+    format.end(&mut writer, count)?;
+    Ok(count)
   }
 
-  #[cfg(not(feature = "sync"))]
-  pub async fn find(&self, query: &Value) -> Result<Documents, Error> {
-    let mut result: Documents = Vec::new();
+  /// Copy of collection with log position taken at the same time.
+  pub(crate) fn collection_snapshot(data: &CollectionData, lsn: Option<u64>) -> CollectionSnapshot {
+    CollectionSnapshot {
+      options: data.options.clone(),
+      indexes: Self::index_definitions(data)
+        .into_iter()
+        .filter(|d| d.name != ID_INDEX_NAME)
+        .collect(),
+      documents: data.documents().cloned().collect(),
+      lsn,
+    }
+  }
 
-    for document in self.docs.lock().await.iter() {
-      if self.perform_query(&query, document)? {
-        result.push(document.clone());
+  fn insert_documents(
+    &self,
+    data: &mut CollectionData,
+    documents: Vec<Value>,
+    stop_on_error: bool,
+  ) -> Vec<Result<(), Error>> {
+    let mut results = Vec::with_capacity(documents.len());
+    for document in documents {
+      let result = self.insert_document(data, document);
+      let failed = result.is_err();
+      results.push(result);
+      if failed && stop_on_error {
+        break;
       }
     }
-    Ok(result)
+    results
   }
 
-  #[cfg(not(feature = "sync"))]
-  pub async fn find_and_update(&self, query: &Value, update: &Value) -> Result<u64, Error> {
-    let mut documents_updated: u64 = 0;
-    for document in self.docs.lock().await.iter_mut() {
-      if self.perform_query(&query, document)? {
-        self.perform_update(update, document)?;
-        documents_updated += 1;
+  pub(crate) fn insert_document(
+    &self,
+    data: &mut CollectionData,
+    mut document: Value,
+  ) -> Result<(), Error> {
+    if !document.is_object() {
+      return Err(Error::MQError(String::from(
+        "Document must be a JSON object.",
+      )));
+    }
+    self.remove_expired(data)?;
+
+    if let Some(fields) = document.as_object_mut() {
+      match fields.get(ID_FIELD) {
+        Some(id) => data.id_generator.observe(id),
+        None => {
+          fields.insert(ID_FIELD.to_string(), data.id_generator.generate());
+        }
       }
     }
-    Ok(documents_updated)
+
+    self.check_valid(data, &document, None)?;
+    let size = data.tracked_size(&document);
+    if let Some(max_bytes) = data.max_bytes() {
+      if size > max_bytes {
+        return Err(Error::MQInvalidValue(format!(
+          "Document of {} bytes does not fit capped collection of {} bytes.",
+          size, max_bytes
+        )));
+      }
+    }
+    let evicted = data.evictions(
+      data.docs.len() as u64 + 1,
+      data.bytes + size,
+      &BTreeMap::new(),
+    );
+
+    let keys = self.document_keys(data, &document);
+    let ignored: BTreeSet<u64> = evicted.iter().copied().collect();
+    for (index, index_keys) in data.indexes.iter().zip(keys.iter()) {
+      index.check_unique(index_keys, &ignored)?;
+    }
+
+    self.log_removal(data, &evicted)?;
+    #[cfg(feature = "persistence")]
+    data.append_log(|| WalOperation::Insert {
+      document: document.clone(),
+    })?;
+
+    self.remove_documents(data, evicted);
+    data.bytes += size;
+    let seq = data.next_seq;
+    data.next_seq += 1;
+    for (index, index_keys) in data.indexes.iter_mut().zip(keys.iter()) {
+      index.insert(seq, index_keys);
+    }
+    data.record_change(|| ChangeEvent::insert(&document));
+    data.docs.insert(seq, Arc::new(document));
+    data.counters.inserted(1);
+
+    Ok(())
+  }
+
+  pub fn explain(&self, query: &CompiledQuery, options: ExplainOptions) -> Result<Value, Error> {
+    self.explain_query(&self.current(), query, options)
+  }
+
+  /// Returns sequence numbers of documents that match query in insertion order.
+  fn select(
+    &self,
+    data: &CollectionData,
+    query: &CompiledQuery,
+    plan: &Plan,
+    stats: &mut ExecutionStats,
+  ) -> Result<Vec<u64>, Error> {
+    let candidates: Vec<u64> = match plan {
+      Plan::CollectionScan => data.docs.keys().copied().collect(),
+      Plan::IndexScan { index, bounds, .. } => {
+        match data.indexes.iter().find(|i| &i.definition.name == index) {
+          Some(index) => index.scan(bounds, stats).into_iter().collect(),
+          None => data.docs.keys().copied().collect(),
+        }
+      }
+    };
+
+    let expired = data.expired();
+    let mut matched: Vec<u64> = Vec::new();
+    let mut examined = 0;
+    for seq in candidates.into_iter().filter(|seq| !expired.contains(seq)) {
+      if let Some(document) = data.docs.get(&seq) {
+        examined += 1;
+        if query.matches(document)? {
+          matched.push(seq);
+        }
+      }
+    }
+    data.counters.scanned(examined);
+    stats.docs_examined += examined;
+    stats.returned = matched.len() as u64;
+    Ok(matched)
+  }
+
+  fn matching(&self, data: &CollectionData, query: &CompiledQuery) -> Result<Vec<u64>, Error> {
+    let plan = plan(query, &data.indexes);
+    self.select(data, query, &plan, &mut ExecutionStats::default())
   }
 
-  #[cfg(feature = "sync")]
-  pub fn find_and_update(&self, query: &Value, update: &Value) -> Result<u64, Error> {
-    let mut documents_updated: u64 = 0;
-    for document in self.docs.lock().unwrap().iter_mut() {
-      if self.perform_query(&query, document)? {
-        self.perform_update(update, document)?;
-        documents_updated += 1;
+  fn explain_query(
+    &self,
+    data: &CollectionData,
+    query: &CompiledQuery,
+    options: ExplainOptions,
+  ) -> Result<Value, Error> {
+    let plan = plan(query, &data.indexes);
+    let mut explain = json!({
+      "queryPlanner": {
+        "parsedQuery": query.as_value(),
+        "winningPlan": plan.to_json(),
       }
+    });
+
+    if options.verbosity == ExplainVerbosity::ExecutionStats {
+      let mut stats = ExecutionStats::default();
+      let start = Instant::now();
+      self.select(data, query, &plan, &mut stats)?;
+      let elapsed = start.elapsed();
+      explain["executionStats"] = json!({
+        "nReturned": stats.returned,
+        "totalKeysExamined": stats.keys_examined,
+        "totalDocsExamined": stats.docs_examined,
+        "executionTimeMillis": elapsed.as_millis() as u64,
+        "executionTimeMicros": elapsed.as_micros() as u64,
+      });
     }
+
+    Ok(explain)
+  }
+
+  pub(crate) fn find_first_document(
+    &self,
+    data: &CollectionData,
+    query: &CompiledQuery,
+  ) -> Result<Option<Value>, Error> {
+    data.counters.queried();
+    Ok(
+      self
+        .matching(data, query)?
+        .first()
+        .and_then(|seq| data.docs.get(seq).map(|d| Value::clone(d))),
+    )
+  }
+
+  pub(crate) fn find_documents(
+    &self,
+    data: &CollectionData,
+    query: &CompiledQuery,
+  ) -> Result<Documents, Error> {
+    data.counters.queried();
+    Ok(
+      self
+        .matching(data, query)?
+        .iter()
+        .filter_map(|seq| data.docs.get(seq).map(|d| Value::clone(d)))
+        .collect(),
+    )
+  }
+
+  pub(crate) fn update_documents(
+    &self,
+    data: &mut CollectionData,
+    query: &CompiledQuery,
+    update: &Value,
+  ) -> Result<u64, Error> {
+    if !update.is_object() {
+      return Err(Error::MQError(String::from(
+        "Update must be a JSON object.",
+      )));
+    }
+    self.remove_expired(data)?;
+
+    let mut updated: Vec<(u64, Value)> = Vec::new();
+    for seq in self.matching(data, query)? {
+      let mut new_document = Value::clone(&data.docs[&seq]);
+      self.perform_update(update, &mut new_document)?;
+      self.check_valid(data, &new_document, Some(&data.docs[&seq]))?;
+      updated.push((seq, new_document));
+    }
+
+    // check all unique indexes before changing anything so that failed update
+    // leaves collection untouched
+    let changed: BTreeSet<u64> = updated.iter().map(|(seq, _)| *seq).collect();
+    let new_keys: Vec<Vec<Vec<IndexKey>>> = updated
+      .iter()
+      .map(|(_, document)| self.document_keys(data, document))
+      .collect();
+    for (i, index) in data.indexes.iter().enumerate() {
+      if !index.definition.unique {
+        continue;
+      }
+      let mut seen: BTreeSet<&IndexKey> = BTreeSet::new();
+      for keys in new_keys.iter() {
+        index.check_unique(&keys[i], &changed)?;
+        for key in keys[i].iter() {
+          if !seen.insert(key) {
+            return Err(index.duplicate_key_error(key));
+          }
+        }
+      }
+    }
+
+    let mut bytes = data.bytes;
+    let mut resized = BTreeMap::new();
+    if data.max_bytes().is_some() {
+      for (seq, document) in updated.iter() {
+        let size = data.tracked_size(document);
+        bytes = bytes - data.tracked_size(&data.docs[seq]) + size;
+        resized.insert(*seq, size);
+      }
+    }
+    let evicted = data.evictions(data.docs.len() as u64, bytes, &resized);
+    self.log_removal(data, &evicted)?;
+
+    #[cfg(feature = "persistence")]
+    if !updated.is_empty() {
+      data.append_log(|| WalOperation::Update {
+        query: query.as_value().clone(),
+        update: update.clone(),
+      })?;
+    }
+
+    let documents_updated = updated.len() as u64;
+    for ((seq, document), keys) in updated.into_iter().zip(new_keys) {
+      let document = Arc::new(document);
+      if let Some(old_document) = data.docs.insert(seq, document.clone()) {
+        data.bytes = data.bytes - data.tracked_size(&old_document) + data.tracked_size(&document);
+        data.record_change(|| ChangeEvent::update(&old_document, &document));
+        let old_keys = self.document_keys(data, &old_document);
+        for ((index, old), new) in data
+          .indexes
+          .iter_mut()
+          .zip(old_keys.iter())
+          .zip(keys.iter())
+        {
+          index.remove(seq, old);
+          index.insert(seq, new);
+        }
+      }
+    }
+    self.remove_documents(data, evicted);
+    data.counters.updated(documents_updated);
+
     Ok(documents_updated)
   }
 
-  #[cfg(not(feature = "sync"))]
-  pub async fn find_and_delete(&self, query: &Value) -> Result<Documents, Error> {
-    let mut docs_deleted: Documents = Vec::new();
-    let mut docs_guard = self.docs.lock().await;
+  pub(crate) fn delete_documents(
+    &self,
+    data: &mut CollectionData,
+    query: &CompiledQuery,
+  ) -> Result<Documents, Error> {
+    self.remove_expired(data)?;
+    let matched = self.matching(data, query)?;
+
+    #[cfg(feature = "persistence")]
+    if !matched.is_empty() {
+      data.append_log(|| WalOperation::Delete {
+        query: query.as_value().clone(),
+      })?;
+    }
+
+    let deleted = self.remove_documents(data, matched);
+    data.counters.deleted(deleted.len() as u64);
+    Ok(deleted)
+  }
 
-    docs_guard.retain(|document| {
-      if self.perform_query(&query, &document).unwrap_or(true) {
-        docs_deleted.push(document.clone());
-        return false;
+  /// Checks document against validator of collection.  `old_document` is version of
+  /// document before update, moderate validation level does not check documents that were
+  /// already invalid.
+  fn check_valid(
+    &self,
+    data: &CollectionData,
+    document: &Value,
+    old_document: Option<&Value>,
+  ) -> Result<(), Error> {
+    let validator = match &data.validator {
+      Some(validator) => validator,
+      None => return Ok(()),
+    };
+    if let (ValidationLevel::Moderate, Some(old_document)) =
+      (data.options.validation_level, old_document)
+    {
+      if !validator.validate(old_document).is_empty() {
+        return Ok(());
       }
-      true
-    });
-    Ok(docs_deleted)
+    }
+    let failures = validator.validate(document);
+    if failures.is_empty() {
+      return Ok(());
+    }
+    match data.options.validation_action {
+      ValidationAction::Error => Err(Error::MQValidationFailed { failures }),
+      ValidationAction::Warn => {
+        for failure in failures {
+          log::warn!(
+            "Document {} failed validation: {}",
+            document[ID_FIELD],
+            failure
+          );
+        }
+        Ok(())
+      }
+    }
+  }
+
+  /// Removes expired documents and returns their number.  Removal is logged as delete by
+  /// `_id` so that replay does not depend on the clock.
+  fn remove_expired(&self, data: &mut CollectionData) -> Result<u64, Error> {
+    let expired = data.expired();
+    if expired.is_empty() {
+      return Ok(0);
+    }
+
+    let expired: Vec<u64> = expired.into_iter().collect();
+    self.log_removal(data, &expired)?;
+    Ok(self.remove_documents(data, expired).len() as u64)
+  }
+
+  /// Logs removal of expired or evicted documents as delete by `_id`.
+  #[allow(unused_variables)]
+  fn log_removal(&self, data: &mut CollectionData, seqs: &[u64]) -> Result<(), Error> {
+    #[cfg(feature = "persistence")]
+    if !seqs.is_empty() {
+      let ids: Vec<Value> = seqs
+        .iter()
+        .filter_map(|seq| data.docs.get(seq))
+        .filter_map(|document| document.get(ID_FIELD).cloned())
+        .collect();
+      data.append_log(|| WalOperation::Delete {
+        query: json!({ ID_FIELD: { "$in": ids } }),
+      })?;
+    }
+    Ok(())
   }
 
-  #[cfg(feature = "sync")]
-  pub fn find_and_delete(&self, query: &Value) -> Result<Documents, Error> {
+  fn remove_documents<I>(&self, data: &mut CollectionData, seqs: I) -> Documents
+  where
+    I: IntoIterator<Item = u64>,
+  {
     let mut docs_deleted: Documents = Vec::new();
-    let mut docs_guard = self.docs.lock().unwrap();
+    for seq in seqs {
+      if let Some(document) = data.docs.remove(&seq) {
+        data.bytes -= data.tracked_size(&document);
+        let keys = self.document_keys(data, &document);
+        for (index, index_keys) in data.indexes.iter_mut().zip(keys.iter()) {
+          index.remove(seq, index_keys);
+        }
+        data.record_change(|| ChangeEvent::delete(&document));
+        docs_deleted.push(Arc::try_unwrap(document).unwrap_or_else(|d| Value::clone(&d)));
+      }
+    }
+    docs_deleted
+  }
 
-    docs_guard.retain(|document| {
-      if self.perform_query(&query, &document).unwrap_or(true) {
-        docs_deleted.push(document.clone());
-        return false;
+  fn add_index(
+    &self,
+    data: &mut CollectionData,
+    keys: &[&str],
+    options: IndexOptions,
+  ) -> Result<String, Error> {
+    let definition = IndexDefinition::new(keys, options)?;
+    if let Some(existing) = data
+      .indexes
+      .iter()
+      .find(|i| i.definition.name == definition.name)
+    {
+      if existing.definition == definition {
+        return Ok(definition.name);
       }
-      true
-    });
-    Ok(docs_deleted)
+      return Err(Error::MQInvalidValue(format!(
+        "Index {} already exists with different options.",
+        definition.name
+      )));
+    }
+
+    let mut index = Index::new(definition)?;
+    for (seq, document) in data.docs.iter() {
+      let keys = self.index_keys(&index, document);
+      index.check_unique(&keys, &BTreeSet::new())?;
+      index.insert(*seq, &keys);
+    }
+
+    #[cfg(feature = "persistence")]
+    data.append_log(|| WalOperation::CreateIndex {
+      index: index.definition.clone(),
+    })?;
+
+    let name = index.definition.name.clone();
+    data.indexes.push(index);
+    Ok(name)
+  }
+
+  fn remove_index(data: &mut CollectionData, name: &str) -> Result<(), Error> {
+    if name == ID_INDEX_NAME {
+      return Err(Error::MQInvalidValue(String::from(
+        "Cannot drop _id_ index.",
+      )));
+    }
+
+    let position = data
+      .indexes
+      .iter()
+      .position(|i| i.definition.name == name)
+      .ok_or_else(|| Error::MQIndexNotFound(name.to_string()))?;
+
+    #[cfg(feature = "persistence")]
+    data.append_log(|| WalOperation::DropIndex {
+      name: name.to_string(),
+    })?;
+
+    data.indexes.remove(position);
+    Ok(())
+  }
+
+  fn index_definitions(data: &CollectionData) -> Vec<IndexDefinition> {
+    data.indexes.iter().map(|i| i.definition.clone()).collect()
+  }
+
+  fn document_keys(&self, data: &CollectionData, document: &Value) -> Vec<Vec<IndexKey>> {
+    data
+      .indexes
+      .iter()
+      .map(|index| self.index_keys(index, document))
+      .collect()
+  }
+
+  fn index_keys(&self, index: &Index, document: &Value) -> Vec<IndexKey> {
+    if let Some(filter) = &index.partial_filter {
+      // documents that cannot be compared with the filter are not part of the index
+      if !filter.matches(document).unwrap_or(false) {
+        return Vec::new();
+      }
+    }
+    index.keys(document)
   }
 
   fn perform_update<'d>(
@@ -153,7 +1054,12 @@ impl Engine {
     if has_update_operations(update)? {
       self.perform_update_operations(update, document)?;
     } else {
+      // replacement keeps document id unless it sets new one
+      let id = document.get(ID_FIELD).cloned();
       *document = update.clone();
+      if let (Some(id), Some(fields)) = (id, document.as_object_mut()) {
+        fields.entry(ID_FIELD).or_insert(id);
+      }
     }
 
     Ok(document)
@@ -206,7 +1112,7 @@ impl Engine {
 
     for (k, v) in update {
       if has_ops(k) {
-        return Err(Error::MQOpNotAllowedInMultipartKey);
+        return Err(Error::MQOpNotAllowedInMultipartKey(k.clone()));
       }
       let handler = |k: &str, d: &mut Value| {
         d[k] = v.clone();
@@ -265,41 +1171,51 @@ impl Engine {
 
     for (k, v) in update {
       if has_ops(k) {
-        return Err(Error::MQOpNotAllowedInMultipartKey);
+        return Err(Error::MQOpNotAllowedInMultipartKey(k.clone()));
       }
 
       if !v.is_number() {
-        return Err(Error::MQInvalidType);
+        return Err(Error::MQInvalidType {
+          path: k.clone(),
+          message: format!("{} requires a number, found {}", op_type.as_operator(), v),
+        });
       }
 
-      let handler = |k: &str, d: &mut Value| {
-        match (&d[k], &v.clone()) {
+      let handler = |field: &str, d: &mut Value| {
+        match (&d[field], &v.clone()) {
           (Value::Number(dk), Value::Number(v)) => {
             if let Some(d_f) = dk.as_f64() {
               if let Some(v_f) = v.as_f64() {
                 match op_type {
-                  MathOpType::Inc => d[k] = json!(sum(d_f, v_f)),
-                  MathOpType::Mul => d[k] = json!(mul(d_f, v_f)),
+                  MathOpType::Inc => d[field] = json!(sum(d_f, v_f)),
+                  MathOpType::Mul => d[field] = json!(mul(d_f, v_f)),
                 };
               }
             } else if let Some(d_i) = dk.as_i64() {
               if let Some(v_i) = v.as_i64() {
                 match op_type {
-                  MathOpType::Inc => d[k] = json!(sum(d_i, v_i)),
-                  MathOpType::Mul => d[k] = json!(mul(d_i, v_i)),
+                  MathOpType::Inc => d[field] = json!(sum(d_i, v_i)),
+                  MathOpType::Mul => d[field] = json!(mul(d_i, v_i)),
                 };
               }
             } else if let Some(d_u) = dk.as_u64() {
               if let Some(v_u) = v.as_u64() {
                 match op_type {
-                  MathOpType::Inc => d[k] = json!(sum(d_u, v_u)),
-                  MathOpType::Mul => d[k] = json!(mul(d_u, v_u)),
+                  MathOpType::Inc => d[field] = json!(sum(d_u, v_u)),
+                  MathOpType::Mul => d[field] = json!(mul(d_u, v_u)),
                 };
               }
             }
           }
           _ => {
-            return Err(Error::MQInvalidType);
+            return Err(Error::MQInvalidType {
+              path: k.clone(),
+              message: format!(
+                "{} cannot be applied to {}",
+                op_type.as_operator(),
+                d[field]
+              ),
+            });
           }
         };
 
@@ -311,210 +1227,4 @@ impl Engine {
 
     Ok(())
   }
-
-  fn get_document_value<'d>(&self, key: &str, document: &'d Value) -> Result<&'d Value, Error> {
-    // find if the value should be the immediate value of the key or embedded document
-    let (is_embedded, key_parts) = is_embedded_query(key);
-    let mut doc_value = &document[key];
-    if is_embedded {
-      doc_value = self.get_nested_document_value(key_parts, document)?;
-    }
-
-    Ok(doc_value)
-  }
-
-  fn perform_query(&self, query: &Value, document: &Value) -> Result<bool, Error> {
-    let query_obj = query.as_object().unwrap();
-    let mut is_found = false;
-
-    if query_obj.keys().len() == 0 {
-      return Ok(true);
-    }
-
-    for key in query_obj.keys() {
-      is_key_valid_op(key)?;
-      if is_logical_op(key) {
-        let logical_op_list = &query[key];
-        is_found = self.perform_logical_op(key, logical_op_list, document)?;
-        break;
-      } else if is_comparison_op(&query[key]) {
-        let (_, op, comp_value) = has_comparison_op(&query[key]);
-        is_found =
-          self.perform_comparison_op(op, comp_value, self.get_document_value(key, document)?)?;
-        continue;
-      } else {
-        is_found = &query[key] == self.get_document_value(key, document)?;
-        if !is_found {
-          break;
-        }
-      }
-    }
-
-    Ok(is_found)
-  }
-
-  fn perform_logical_op(
-    &self,
-    op: &str,
-    logical_op_list: &Value,
-    document: &Value,
-  ) -> Result<bool, Error> {
-    let op_list = match logical_op_list.as_array() {
-      Some(l) => l,
-      None => return Err(Error::MQError(String::from("Logical operation"))),
-    };
-
-    let mut op_success_list: Vec<bool> = Vec::new();
-
-    for op_query in op_list {
-      op_success_list.push(self.perform_query(op_query, document)?);
-    }
-    Ok(match op {
-      OR => any(op_success_list),
-      AND => all(op_success_list),
-      _ => false,
-    })
-  }
-
-  fn perform_comparison_op(
-    &self,
-    op: &str,
-    compare_to_value: &Value,
-    doc_value: &Value,
-  ) -> Result<bool, Error> {
-    match op {
-      GT | GTE | LT | LTE | NE | EQ => self.perform_value_compares(op, compare_to_value, doc_value),
-      IN | NIN => Ok(false),
-      _ => Err(Error::MQInvalidOp(op.to_string())),
-    }
-  }
-
-  fn perform_value_compares(
-    &self,
-    op: &str,
-    compare_to_value: &Value,
-    doc_value: &Value,
-  ) -> Result<bool, Error> {
-    if compare_to_value.is_object() {
-      return Err(Error::MQInvalidValue(format!(
-        "{} expects value not array or object.",
-        op
-      )));
-    }
-
-    match (doc_value, compare_to_value) {
-      (Value::Number(d), Value::Number(c)) => {
-        if let Some(doc_u) = d.as_u64() {
-          if let Some(comp_u) = c.as_u64() {
-            return self.compare(op, doc_u, comp_u);
-          }
-        } else if let Some(doc_i) = d.as_i64() {
-          if let Some(comp_i) = d.as_i64() {
-            return self.compare(op, doc_i, comp_i);
-          }
-        } else if let Some(doc_f) = d.as_f64() {
-          if let Some(comp_f) = d.as_f64() {
-            return self.compare(op, doc_f, comp_f);
-          }
-        }
-      }
-      (Value::String(d), Value::String(c)) => return self.compare(op, d, c),
-      (Value::Array(d), Value::Array(c)) => {
-        return self.perform_array_to_array_compare(op, d, c);
-      }
-      (Value::Array(d), Value::Number(c)) => {
-        return self.perform_array_to_value_compare(op, d, &serde_json::json!(c));
-      }
-      (Value::Array(d), Value::String(c)) => {
-        return self.perform_array_to_value_compare(op, d, &serde_json::json!(c))
-      }
-      _ => return Err(Error::MQInvalidType),
-    }
-
-    Err(Error::MQInvalidType)
-  }
-
-  fn compare<T: PartialOrd>(&self, op: &str, d: T, c: T) -> Result<bool, Error> {
-    Ok(match op {
-      GT => d > c,
-      GTE => d >= c,
-      LT => d < c,
-      LTE => d <= c,
-      NE => d != c,
-      EQ => d == c,
-      _ => {
-        return Err(Error::MQInvalidOp(format!(
-          "{} not supported for compare.",
-          op
-        )))
-      }
-    })
-  }
-
-  fn perform_array_to_array_compare(
-    &self,
-    op: &str,
-    document_value: &Vec<Value>,
-    compare_value: &Vec<Value>,
-  ) -> Result<bool, Error> {
-    if document_value == compare_value {
-      return Ok(true);
-    }
-
-    let mut matches: Vec<bool> = Vec::new();
-    for elem in document_value {
-      let is_match = match op {
-        NE => elem != &serde_json::json!(compare_value),
-        EQ => elem == &serde_json::json!(compare_value),
-        GT | GTE | LT | LTE | _ => {
-          return Err(Error::MQInvalidOp(format!(
-            "{} is not valid for array comparison.",
-            op
-          )));
-        }
-      };
-      matches.push(is_match);
-    }
-
-    Ok(any(matches))
-  }
-
-  fn perform_array_to_value_compare(
-    &self,
-    op: &str,
-    document_value: &Vec<Value>,
-    compare_value: &Value,
-  ) -> Result<bool, Error> {
-    let mut matches: Vec<bool> = Vec::new();
-    for elem in document_value {
-      let mut is_match = false;
-      if !elem.is_array() {
-        // do not check nested arrays
-        is_match = self.perform_value_compares(op, compare_value, elem)?
-      }
-      matches.push(is_match);
-    }
-
-    Ok(any(matches))
-  }
-
-  fn get_nested_document_value<'b>(
-    &self,
-    nested_keys: Vec<&str>,
-    document: &'b Value,
-  ) -> Result<&'b Value, Error> {
-    let mut current_value: &Value = document;
-
-    for key in nested_keys {
-      if is_op(key) {
-        return Err(Error::MQInvalidOp(format!(
-          "{} operators not allowed in nested paths.",
-          key
-        )));
-      }
-      current_value = &current_value[key];
-    }
-
-    Ok(current_value)
-  }
 }
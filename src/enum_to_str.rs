@@ -53,7 +53,10 @@ macro_rules! enum_to_str {
 }
 
 #[cfg(test)]
+#[allow(clippy::upper_case_acronyms)]
 mod tests {
+  use paste::paste;
+
   #[test]
   fn simple_enum() {
     enum_to_str! {
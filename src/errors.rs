@@ -1,34 +1,159 @@
 //! Errors reported by mem_query API.
+//!
+//! Every error has numeric `code` that does not change between releases.  Codes are the same
+//! as codes of corresponding MongoDB errors, so `MQDuplicateKey` is 11000 and
+//! `MQValidationFailed` is 121.  `Error::to_json` gives error as `{code, message, path}`
+//! object that language bindings return.
+//!
 
+use super::validation::ValidationFailure;
+use serde_json::{json, Value};
 use thiserror::Error;
 
+/// Error codes, same as codes of MongoDB errors.
+pub mod codes {
+  pub const INTERNAL_ERROR: i32 = 1;
+  pub const BAD_VALUE: i32 = 2;
+  pub const FAILED_TO_PARSE: i32 = 9;
+  pub const TYPE_MISMATCH: i32 = 14;
+  pub const NAMESPACE_NOT_FOUND: i32 = 26;
+  pub const INDEX_NOT_FOUND: i32 = 27;
+  pub const NO_MATCHING_DOCUMENT: i32 = 47;
+  pub const NAMESPACE_EXISTS: i32 = 48;
+  pub const DOCUMENT_VALIDATION_FAILURE: i32 = 121;
+  pub const CHANGE_STREAM_FATAL_ERROR: i32 = 280;
+  pub const CHANGE_STREAM_HISTORY_LOST: i32 = 286;
+  pub const DUPLICATE_KEY: i32 = 11000;
+}
+
+fn describe(failures: &[ValidationFailure]) -> String {
+  failures
+    .iter()
+    .map(|failure| failure.to_string())
+    .collect::<Vec<_>>()
+    .join("; ")
+}
+
 /// This represents all possible errors that can occur when calling mem_query API
 #[derive(Error, Debug)]
 pub enum Error {
-  #[error("MemQuery Error")]
+  #[error("{0}")]
   MQError(String),
 
-  #[error("Invalid Operator")]
+  #[error("Invalid operator: {0}")]
   MQInvalidOp(String),
 
-  #[error("Invalid Value")]
+  #[error("Invalid value: {0}")]
   MQInvalidValue(String),
 
-  #[error("Invalid Type")]
-  MQInvalidType,
+  #[error("Type mismatch at {path}: {message}")]
+  MQInvalidType { path: String, message: String },
 
-  #[error("Document Not Found")]
+  #[error("Document not found")]
   MQDocumentNotFound,
 
-  #[error("Collection Not Found")]
-  MQCollectionNotFound,
+  #[error("Collection {0} not found")]
+  MQCollectionNotFound(String),
+
+  #[error("Collection {0} already exists")]
+  MQCollectionExists(String),
+
+  #[error("Operator not allowed in dotted key {0}")]
+  MQOpNotAllowedInMultipartKey(String),
+
+  #[error("Index {0} not found")]
+  MQIndexNotFound(String),
+
+  #[error("Duplicate key {key} in index {index}")]
+  MQDuplicateKey { index: String, key: Value },
+
+  #[error("Document failed validation: {}", describe(.failures))]
+  MQValidationFailed { failures: Vec<ValidationFailure> },
 
-  #[error("Ops In Multipart Key Not Allowed")]
-  MQOpNotAllowedInMultipartKey,
+  #[error("Change stream lagged behind by {0} events")]
+  MQChangeStreamLagged(u64),
 
-  #[error("Serde failed")]
+  #[error("Change stream closed")]
+  MQChangeStreamClosed,
+
+  #[error("Import failed at line {line}: {error}")]
+  MQImportError { line: usize, error: Box<Error> },
+
+  #[error("JSON error: {0}")]
   SerdeJsonError(#[from] serde_json::Error),
 
   #[error(transparent)]
   IOError(#[from] std::io::Error),
+
+  #[cfg(feature = "bson")]
+  #[error("BSON deserialization failed: {0}")]
+  BsonDeError(#[from] bson::de::Error),
+
+  #[cfg(feature = "bson")]
+  #[error("BSON serialization failed: {0}")]
+  BsonSerError(#[from] bson::ser::Error),
+
+  #[cfg(feature = "bson")]
+  #[error("Extended JSON to BSON conversion failed: {0}")]
+  BsonExtJsonError(#[from] bson::extjson::de::Error),
+}
+
+impl Error {
+  /// Numeric code of error, see `codes`.
+  pub fn code(&self) -> i32 {
+    match self {
+      Error::MQError(_) | Error::IOError(_) => codes::INTERNAL_ERROR,
+      Error::MQInvalidOp(_) | Error::MQInvalidValue(_) | Error::MQOpNotAllowedInMultipartKey(_) => {
+        codes::BAD_VALUE
+      }
+      Error::MQInvalidType { .. } => codes::TYPE_MISMATCH,
+      Error::MQDocumentNotFound => codes::NO_MATCHING_DOCUMENT,
+      Error::MQCollectionNotFound(_) => codes::NAMESPACE_NOT_FOUND,
+      Error::MQCollectionExists(_) => codes::NAMESPACE_EXISTS,
+      Error::MQIndexNotFound(_) => codes::INDEX_NOT_FOUND,
+      Error::MQDuplicateKey { .. } => codes::DUPLICATE_KEY,
+      Error::MQValidationFailed { .. } => codes::DOCUMENT_VALIDATION_FAILURE,
+      Error::MQChangeStreamLagged(_) => codes::CHANGE_STREAM_HISTORY_LOST,
+      Error::MQChangeStreamClosed => codes::CHANGE_STREAM_FATAL_ERROR,
+      Error::MQImportError { error, .. } => error.code(),
+      Error::SerdeJsonError(_) => codes::FAILED_TO_PARSE,
+      #[cfg(feature = "bson")]
+      Error::BsonDeError(_) | Error::BsonExtJsonError(_) => codes::FAILED_TO_PARSE,
+      #[cfg(feature = "bson")]
+      Error::BsonSerError(_) => codes::INTERNAL_ERROR,
+    }
+  }
+
+  /// Dotted path of document field or key of query that caused error, if it is known.
+  /// Failed validation reports path of its first failure.
+  pub fn path(&self) -> Option<&str> {
+    match self {
+      Error::MQInvalidType { path, .. } | Error::MQOpNotAllowedInMultipartKey(path) => {
+        Some(path.as_str()).filter(|p| !p.is_empty())
+      }
+      Error::MQValidationFailed { failures } => failures
+        .first()
+        .map(|f| f.path.as_str())
+        .filter(|p| !p.is_empty()),
+      Error::MQImportError { error, .. } => error.path(),
+      _ => None,
+    }
+  }
+
+  /// Error as JSON object with `code`, `message` and `path` (`null` when not known).
+  ///
+  /// ```
+  /// use memquery::errors::Error;
+  ///
+  /// let error = Error::MQCollectionNotFound(String::from("Users"));
+  /// assert_eq!(error.to_json()["code"], 26);
+  /// assert_eq!(error.to_json()["message"], "Collection Users not found");
+  /// ```
+  pub fn to_json(&self) -> Value {
+    json!({
+      "code": self.code(),
+      "message": self.to_string(),
+      "path": self.path(),
+    })
+  }
 }
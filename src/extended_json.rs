@@ -0,0 +1,508 @@
+//! MongoDB Extended JSON support.
+//!
+//! Documents exported from MongoDB wrap values that JSON cannot represent in single key
+//! objects such as `{"$oid": "5f1d..."}` or `{"$date": "2020-07-01T00:00:00.000Z"}`.
+//! Engine recognizes these wrappers so that dates, object ids, binary data and 64-bit or
+//! decimal numbers compare and sort by value, and `$date` values can be queried by range:
+//!
+//! ```
+//! use memquery::{extended_json::date, query, query::CompiledQuery};
+//! use serde_json::json;
+//!
+//! let july = CompiledQuery::new(&query!({ "created": {
+//!   "$gte": { "$date": "2020-07-01T00:00:00Z" },
+//!   "$lt": date(1596240000000),
+//! } })).unwrap();
+//! assert!(july.matches(&json!({ "created": { "$date": "2020-07-15T12:00:00.000Z" } })).unwrap());
+//! assert!(!july.matches(&json!({ "created": { "$date": { "$numberLong": "0" } } })).unwrap());
+//! ```
+//!
+//! `parse` converts canonical or relaxed Extended JSON to relaxed form that collections
+//! store, `serialize` converts stored documents to either form.
+//!
+
+use super::errors::Error;
+use serde_json::{json, Map, Number, Value};
+
+pub const OID: &str = "$oid";
+pub const DATE: &str = "$date";
+pub const NUMBER_INT: &str = "$numberInt";
+pub const NUMBER_LONG: &str = "$numberLong";
+pub const NUMBER_DOUBLE: &str = "$numberDouble";
+pub const NUMBER_DECIMAL: &str = "$numberDecimal";
+pub const BINARY: &str = "$binary";
+
+const TYPE_KEYS: [&str; 7] = [
+  OID,
+  DATE,
+  NUMBER_INT,
+  NUMBER_LONG,
+  NUMBER_DOUBLE,
+  NUMBER_DECIMAL,
+  BINARY,
+];
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// Extended JSON output format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExtendedJsonMode {
+  /// Numbers are plain JSON numbers and dates are ISO-8601 strings where possible.
+  #[default]
+  Relaxed,
+  /// Every number and date keeps its exact type.
+  Canonical,
+}
+
+/// Value of Extended JSON type wrapper.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtendedValue {
+  ObjectId(String),
+  /// Milliseconds since Unix epoch.
+  Date(i64),
+  Int(i32),
+  Long(i64),
+  Double(f64),
+  Decimal(String),
+  Binary {
+    subtype: u8,
+    bytes: Vec<u8>,
+  },
+}
+
+impl ExtendedValue {
+  /// Recognizes type wrapper.  Other values, including malformed wrappers, give `None`.
+  pub fn from_value(value: &Value) -> Option<ExtendedValue> {
+    let (key, inner) = single_entry(value)?;
+    match (key, inner) {
+      (OID, Value::String(s)) if s.len() == 24 && s.chars().all(|c| c.is_ascii_hexdigit()) => {
+        Some(ExtendedValue::ObjectId(s.to_ascii_lowercase()))
+      }
+      (DATE, Value::String(s)) => parse_date(s).map(ExtendedValue::Date),
+      (DATE, Value::Number(n)) => n.as_i64().map(ExtendedValue::Date),
+      (DATE, inner) => match ExtendedValue::from_value(inner) {
+        Some(ExtendedValue::Long(millis)) => Some(ExtendedValue::Date(millis)),
+        _ => None,
+      },
+      (NUMBER_INT, Value::String(s)) => s.parse().ok().map(ExtendedValue::Int),
+      (NUMBER_LONG, Value::String(s)) => s.parse().ok().map(ExtendedValue::Long),
+      (NUMBER_DOUBLE, Value::String(s)) => parse_double(s).map(ExtendedValue::Double),
+      (NUMBER_DECIMAL, Value::String(s)) => {
+        parse_double(s).map(|_| ExtendedValue::Decimal(s.clone()))
+      }
+      (BINARY, Value::Object(binary)) if binary.len() == 2 => {
+        let subtype = binary.get("subType")?.as_str()?;
+        if subtype.is_empty() || subtype.len() > 2 {
+          return None;
+        }
+        Some(ExtendedValue::Binary {
+          subtype: u8::from_str_radix(subtype, 16).ok()?,
+          bytes: decode_base64(binary.get("base64")?.as_str()?)?,
+        })
+      }
+      _ => None,
+    }
+  }
+
+  /// Converts value to JSON in specified format.
+  pub fn to_value(&self, mode: ExtendedJsonMode) -> Value {
+    let canonical = mode == ExtendedJsonMode::Canonical;
+    match self {
+      ExtendedValue::ObjectId(id) => wrap(OID, Value::from(id.as_str())),
+      ExtendedValue::Date(millis) => {
+        if canonical || !(0..=253_402_300_799_999).contains(millis) {
+          wrap(DATE, wrap(NUMBER_LONG, Value::from(millis.to_string())))
+        } else {
+          wrap(DATE, Value::from(format_date(*millis)))
+        }
+      }
+      ExtendedValue::Int(i) if canonical => wrap(NUMBER_INT, Value::from(i.to_string())),
+      ExtendedValue::Int(i) => Value::from(*i),
+      ExtendedValue::Long(l) if canonical => wrap(NUMBER_LONG, Value::from(l.to_string())),
+      ExtendedValue::Long(l) => Value::from(*l),
+      ExtendedValue::Double(d) => match Number::from_f64(*d) {
+        Some(n) if !canonical => Value::Number(n),
+        _ => wrap(NUMBER_DOUBLE, Value::from(format_double(*d))),
+      },
+      ExtendedValue::Decimal(d) => wrap(NUMBER_DECIMAL, Value::from(d.as_str())),
+      ExtendedValue::Binary { subtype, bytes } => wrap(
+        BINARY,
+        json!({ "base64": encode_base64(bytes), "subType": format!("{:02x}", subtype) }),
+      ),
+    }
+  }
+
+  /// Numeric value of number types.
+  pub(crate) fn as_f64(&self) -> Option<f64> {
+    match self {
+      ExtendedValue::Int(i) => Some(f64::from(*i)),
+      ExtendedValue::Long(l) => Some(*l as f64),
+      ExtendedValue::Double(d) => Some(*d),
+      ExtendedValue::Decimal(d) => parse_double(d),
+      _ => None,
+    }
+  }
+
+  /// Integer value of integer types.
+  pub(crate) fn as_i64(&self) -> Option<i64> {
+    match self {
+      ExtendedValue::Int(i) => Some(i64::from(*i)),
+      ExtendedValue::Long(l) => Some(*l),
+      _ => None,
+    }
+  }
+}
+
+/// Returns true if value is Extended JSON type wrapper.
+pub fn is_extended(value: &Value) -> bool {
+  ExtendedValue::from_value(value).is_some()
+}
+
+/// Converts canonical or relaxed Extended JSON to relaxed form.
+///
+/// Returns `MQInvalidValue` error for malformed type wrappers such as `{"$oid": "x"}`.
+pub fn parse(value: Value) -> Result<Value, Error> {
+  match value {
+    Value::Object(fields) => {
+      if let Some((key, _)) = single_entry_of(&fields) {
+        if TYPE_KEYS.contains(&key) {
+          let value = Value::Object(fields);
+          return match ExtendedValue::from_value(&value) {
+            Some(typed) => Ok(typed.to_value(ExtendedJsonMode::Relaxed)),
+            None => Err(Error::MQInvalidValue(format!(
+              "Invalid Extended JSON value {}.",
+              value
+            ))),
+          };
+        }
+      }
+      let mut parsed = Map::new();
+      for (key, value) in fields {
+        parsed.insert(key, parse(value)?);
+      }
+      Ok(Value::Object(parsed))
+    }
+    Value::Array(items) => Ok(Value::Array(
+      items.into_iter().map(parse).collect::<Result<_, _>>()?,
+    )),
+    other => Ok(other),
+  }
+}
+
+/// Converts document to Extended JSON in specified format.
+pub fn serialize(value: &Value, mode: ExtendedJsonMode) -> Value {
+  if let Some(typed) = ExtendedValue::from_value(value) {
+    return typed.to_value(mode);
+  }
+  match value {
+    // Other Extended JSON types, such as `$timestamp`, are kept as they are.
+    Value::Object(fields) if !fields.is_empty() && fields.keys().all(|k| k.starts_with('$')) => {
+      value.clone()
+    }
+    Value::Object(fields) => Value::Object(
+      fields
+        .iter()
+        .map(|(k, v)| (k.clone(), serialize(v, mode)))
+        .collect(),
+    ),
+    Value::Array(items) => Value::Array(items.iter().map(|i| serialize(i, mode)).collect()),
+    Value::Number(n) if mode == ExtendedJsonMode::Canonical => {
+      let typed = match (n.as_i64(), n.as_f64()) {
+        (Some(i), _) if (i64::from(i32::MIN)..=i64::from(i32::MAX)).contains(&i) => {
+          ExtendedValue::Int(i as i32)
+        }
+        (Some(i), _) => ExtendedValue::Long(i),
+        (None, Some(f)) => ExtendedValue::Double(f),
+        (None, None) => return value.clone(),
+      };
+      typed.to_value(mode)
+    }
+    other => other.clone(),
+  }
+}
+
+/// `{"$date": ...}` value for milliseconds since Unix epoch.
+pub fn date(millis: i64) -> Value {
+  ExtendedValue::Date(millis).to_value(ExtendedJsonMode::Relaxed)
+}
+
+/// `{"$oid": ...}` value for 24 character hex string.
+pub fn object_id(hex: &str) -> Value {
+  wrap(OID, Value::from(hex.to_ascii_lowercase()))
+}
+
+/// `{"$numberLong": ...}` value.
+pub fn number_long(value: i64) -> Value {
+  ExtendedValue::Long(value).to_value(ExtendedJsonMode::Canonical)
+}
+
+/// `{"$binary": ...}` value with generic subtype.
+pub fn binary(bytes: &[u8]) -> Value {
+  ExtendedValue::Binary {
+    subtype: 0,
+    bytes: bytes.to_vec(),
+  }
+  .to_value(ExtendedJsonMode::Relaxed)
+}
+
+/// `{"$numberDecimal": ...}` value.
+pub fn number_decimal(value: &str) -> Value {
+  wrap(NUMBER_DECIMAL, Value::from(value))
+}
+
+/// Parses ISO-8601 date such as `2020-07-01T12:30:00.000Z` or `2020-07-01T14:30:00+02:00`
+/// to milliseconds since Unix epoch.  Date without time is midnight UTC.
+pub fn parse_date(s: &str) -> Option<i64> {
+  let b = s.as_bytes();
+  let digits = |from: usize, len: usize| -> Option<i64> {
+    let part = b.get(from..from + len)?;
+    if !part.iter().all(u8::is_ascii_digit) {
+      return None;
+    }
+    std::str::from_utf8(part).ok()?.parse().ok()
+  };
+
+  let (year, month, day) = (digits(0, 4)?, digits(5, 2)?, digits(8, 2)?);
+  if b.get(4) != Some(&b'-') || b.get(7) != Some(&b'-') {
+    return None;
+  }
+  if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+    return None;
+  }
+  let mut millis = days_from_civil(year, month, day) * MILLIS_PER_DAY;
+  if b.len() == 10 {
+    return Some(millis);
+  }
+
+  if !matches!(b.get(10), Some(b'T') | Some(b't') | Some(b' '))
+    || b.get(13) != Some(&b':')
+    || b.get(16) != Some(&b':')
+  {
+    return None;
+  }
+  let (hour, minute, second) = (digits(11, 2)?, digits(14, 2)?, digits(17, 2)?);
+  if hour > 23 || minute > 59 || second > 59 {
+    return None;
+  }
+  millis += ((hour * 60 + minute) * 60 + second) * 1000;
+
+  let mut pos = 19;
+  if b.get(pos) == Some(&b'.') {
+    let start = pos + 1;
+    pos = start;
+    while b.get(pos).is_some_and(u8::is_ascii_digit) {
+      pos += 1;
+    }
+    if pos == start {
+      return None;
+    }
+    let fraction = &s[start..pos.min(start + 3)];
+    millis += fraction.parse::<i64>().ok()? * 10_i64.pow(3 - fraction.len() as u32);
+  }
+
+  match &s[pos..] {
+    "Z" | "z" => Some(millis),
+    offset => {
+      let sign = match offset.as_bytes().first() {
+        Some(b'+') => 1,
+        Some(b'-') => -1,
+        _ => return None,
+      };
+      let (hours, minutes) = match offset.len() {
+        6 if offset.as_bytes()[3] == b':' => (digits(pos + 1, 2)?, digits(pos + 4, 2)?),
+        5 => (digits(pos + 1, 2)?, digits(pos + 3, 2)?),
+        _ => return None,
+      };
+      Some(millis - sign * (hours * 60 + minutes) * 60_000)
+    }
+  }
+}
+
+/// Formats milliseconds since Unix epoch as `2020-07-01T12:30:00.000Z`.
+pub fn format_date(millis: i64) -> String {
+  let days = millis.div_euclid(MILLIS_PER_DAY);
+  let time = millis.rem_euclid(MILLIS_PER_DAY);
+  let (year, month, day) = civil_from_days(days);
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+    year,
+    month,
+    day,
+    time / 3_600_000,
+    time / 60_000 % 60,
+    time / 1000 % 60,
+    time % 1000
+  )
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+  b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(bytes: &[u8]) -> String {
+  let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+  for chunk in bytes.chunks(3) {
+    let n = chunk
+      .iter()
+      .enumerate()
+      .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+    for i in 0..4 {
+      if i <= chunk.len() {
+        encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+      } else {
+        encoded.push('=');
+      }
+    }
+  }
+  encoded
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+  let data = encoded.trim_end_matches('=').as_bytes();
+  if !encoded.len().is_multiple_of(4) || encoded.len() - data.len() > 2 {
+    return None;
+  }
+  let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+  for chunk in data.chunks(4) {
+    let mut n = 0u32;
+    for (i, c) in chunk.iter().enumerate() {
+      let digit = BASE64_ALPHABET.iter().position(|a| a == c)? as u32;
+      n |= digit << (18 - 6 * i);
+    }
+    for i in 0..chunk.len() - 1 {
+      bytes.push((n >> (16 - 8 * i)) as u8);
+    }
+  }
+  Some(bytes)
+}
+
+fn wrap(key: &str, value: Value) -> Value {
+  let mut fields = Map::new();
+  fields.insert(key.to_string(), value);
+  Value::Object(fields)
+}
+
+fn single_entry(value: &Value) -> Option<(&str, &Value)> {
+  single_entry_of(value.as_object()?)
+}
+
+fn single_entry_of(fields: &Map<String, Value>) -> Option<(&str, &Value)> {
+  if fields.len() != 1 {
+    return None;
+  }
+  fields.iter().next().map(|(k, v)| (k.as_str(), v))
+}
+
+fn parse_double(s: &str) -> Option<f64> {
+  match s {
+    "Infinity" => Some(f64::INFINITY),
+    "-Infinity" => Some(f64::NEG_INFINITY),
+    "NaN" => Some(f64::NAN),
+    _ if s.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c)) => s.parse().ok(),
+    _ => None,
+  }
+}
+
+fn format_double(d: f64) -> String {
+  if d.is_nan() {
+    String::from("NaN")
+  } else if d.is_infinite() {
+    String::from(if d > 0.0 { "Infinity" } else { "-Infinity" })
+  } else {
+    format!("{:?}", d)
+  }
+}
+
+fn is_leap_year(year: i64) -> bool {
+  (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+  match month {
+    2 if is_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+/// Days since Unix epoch of proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year - era * 400;
+  let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of days since Unix epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days - era * 146_097;
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = year_of_era + era * 400;
+  (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_dates() {
+    assert_eq!(parse_date("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_date("2020-07-01"), Some(1_593_561_600_000));
+    assert_eq!(
+      parse_date("2020-07-01T02:00:00.5+02:00"),
+      Some(1_593_561_600_500)
+    );
+    assert_eq!(parse_date("1969-12-31T23:59:59.999Z"), Some(-1));
+    assert_eq!(parse_date("2021-02-29T00:00:00Z"), None);
+    assert_eq!(parse_date("2020-07-01T00:00:00"), None);
+    assert_eq!(format_date(1_593_561_600_500), "2020-07-01T00:00:00.500Z");
+    assert_eq!(format_date(-1), "1969-12-31T23:59:59.999Z");
+    for millis in [0, 951_782_400_000, 4_107_542_400_123, -62_135_596_800_000] {
+      assert_eq!(parse_date(&format_date(millis)), Some(millis));
+    }
+  }
+
+  #[test]
+  fn test_parse_canonical() -> Result<(), Error> {
+    let canonical = json!({
+      "_id": { "$oid": "5F1D7F3E9C4B2A0011223344" },
+      "count": { "$numberInt": "5" },
+      "views": { "$numberLong": "9007199254740993" },
+      "ratio": { "$numberDouble": "-Infinity" },
+      "price": { "$numberDecimal": "9.99" },
+      "created": { "$date": { "$numberLong": "1593561600000" } },
+      "tags": [{ "$numberInt": "1" }],
+    });
+    let relaxed = parse(canonical.clone())?;
+    assert_eq!(
+      relaxed,
+      json!({
+        "_id": { "$oid": "5f1d7f3e9c4b2a0011223344" },
+        "count": 5,
+        "views": 9_007_199_254_740_993_i64,
+        "ratio": { "$numberDouble": "-Infinity" },
+        "price": { "$numberDecimal": "9.99" },
+        "created": { "$date": "2020-07-01T00:00:00.000Z" },
+        "tags": [1],
+      })
+    );
+    let mut expected = canonical;
+    expected["_id"] = json!({ "$oid": "5f1d7f3e9c4b2a0011223344" });
+    assert_eq!(serialize(&relaxed, ExtendedJsonMode::Canonical), expected);
+
+    assert!(parse(json!({ "_id": { "$oid": "123" } })).is_err());
+    assert!(parse(json!({ "created": { "$date": "yesterday" } })).is_err());
+    Ok(())
+  }
+}
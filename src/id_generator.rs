@@ -0,0 +1,248 @@
+//! Generators of `_id` values for documents inserted without one.
+//!
+//! Collection uses `UuidV4` unless other generator is configured with
+//! `MemDb::with_id_generator` or `Collection::with_id_generator`.
+//!
+
+use serde_json::Value;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Produces `_id` for documents that do not have one.
+pub trait IdGenerator: Send + Sync {
+  fn generate(&self) -> Value;
+
+  /// Called with `_id` of document inserted with its own id, for example when
+  /// collection is loaded from snapshot.
+  fn observe(&self, _id: &Value) {}
+}
+
+/// Random UUID v4 string, the default generator.
+#[derive(Debug, Default)]
+pub struct UuidV4;
+
+impl IdGenerator for UuidV4 {
+  fn generate(&self) -> Value {
+    Value::from(Uuid::new_v4().to_string())
+  }
+}
+
+/// Time ordered UUID v7 string.
+///
+/// Ids generated by the same generator sort in generation order, also within the same millisecond.
+#[derive(Debug, Default)]
+pub struct UuidV7 {
+  last: Mutex<(u64, u16)>,
+}
+
+impl UuidV7 {
+  pub fn new() -> UuidV7 {
+    UuidV7::default()
+  }
+}
+
+impl IdGenerator for UuidV7 {
+  fn generate(&self) -> Value {
+    let (millis, counter) = {
+      let mut last = self.last.lock().unwrap();
+      let now = unix_time().as_millis() as u64;
+      *last = if now > last.0 {
+        (now, 0)
+      } else if last.1 < 0x0fff {
+        (last.0, last.1 + 1)
+      } else {
+        (last.0 + 1, 0)
+      };
+      *last
+    };
+
+    let mut bytes = *Uuid::new_v4().as_bytes();
+    bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+    bytes[6] = 0x70 | (counter >> 8) as u8;
+    bytes[7] = counter as u8;
+    // bytes 8..16 are random with RFC 4122 variant already set by v4
+    Value::from(Uuid::from_bytes(bytes).to_string())
+  }
+}
+
+/// MongoDB style ObjectId as 24 character hex string.
+///
+/// Consists of 4 byte timestamp in seconds, 5 byte random value unique to generator
+/// and 3 byte counter starting at random value.
+#[derive(Debug)]
+pub struct ObjectId {
+  process: [u8; 5],
+  counter: AtomicU32,
+}
+
+impl ObjectId {
+  pub fn new() -> ObjectId {
+    let random = Uuid::new_v4();
+    let random = random.as_bytes();
+    let mut process = [0u8; 5];
+    process.copy_from_slice(&random[..5]);
+    ObjectId {
+      process,
+      counter: AtomicU32::new(u32::from_be_bytes([0, random[5], random[6], random[7]])),
+    }
+  }
+}
+
+impl Default for ObjectId {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl IdGenerator for ObjectId {
+  fn generate(&self) -> Value {
+    let seconds = unix_time().as_secs() as u32;
+    let counter = self.counter.fetch_add(1, Ordering::SeqCst) & 0x00ff_ffff;
+
+    let mut bytes = Vec::with_capacity(12);
+    bytes.extend_from_slice(&seconds.to_be_bytes());
+    bytes.extend_from_slice(&self.process);
+    bytes.extend_from_slice(&counter.to_be_bytes()[1..]);
+    Value::from(to_hex(&bytes))
+  }
+}
+
+/// Integer ids incremented by one, starting at 1 by default.
+#[derive(Debug)]
+pub struct AutoIncrement {
+  next: AtomicU64,
+}
+
+impl AutoIncrement {
+  pub fn new() -> AutoIncrement {
+    AutoIncrement::starting_at(1)
+  }
+
+  pub fn starting_at(start: u64) -> AutoIncrement {
+    AutoIncrement {
+      next: AtomicU64::new(start),
+    }
+  }
+}
+
+impl Default for AutoIncrement {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl IdGenerator for AutoIncrement {
+  fn generate(&self) -> Value {
+    Value::from(self.next.fetch_add(1, Ordering::SeqCst))
+  }
+
+  /// Continues after the largest integer id already used.
+  fn observe(&self, id: &Value) {
+    if let Some(id) = id.as_u64() {
+      self.next.fetch_max(id.saturating_add(1), Ordering::SeqCst);
+    }
+  }
+}
+
+/// Deterministic UUID v4 shaped strings derived from seed, for reproducible tests.
+///
+/// Generators created with the same seed produce the same sequence of ids.
+#[derive(Debug)]
+pub struct Seeded {
+  state: AtomicU64,
+}
+
+impl Seeded {
+  pub fn new(seed: u64) -> Seeded {
+    Seeded {
+      state: AtomicU64::new(seed),
+    }
+  }
+
+  /// SplitMix64 step.
+  fn next_u64(&self) -> u64 {
+    let mut z = self
+      .state
+      .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::SeqCst)
+      .wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+}
+
+impl IdGenerator for Seeded {
+  fn generate(&self) -> Value {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&self.next_u64().to_be_bytes());
+    bytes[8..].copy_from_slice(&self.next_u64().to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    Value::from(Uuid::from_bytes(bytes).to_string())
+  }
+}
+
+fn unix_time() -> std::time::Duration {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_uuid_v7_is_ordered() {
+    let generator = UuidV7::new();
+    let ids: Vec<String> = (0..100)
+      .map(|_| generator.generate().as_str().unwrap().to_string())
+      .collect();
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(ids, sorted);
+    assert_eq!(&ids[0][14..15], "7");
+  }
+
+  #[test]
+  fn test_object_id_format() {
+    let generator = ObjectId::new();
+    let first = generator.generate();
+    let second = generator.generate();
+    assert_eq!(first.as_str().unwrap().len(), 24);
+    assert!(first
+      .as_str()
+      .unwrap()
+      .chars()
+      .all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(first, second);
+  }
+
+  #[test]
+  fn test_auto_increment() {
+    let generator = AutoIncrement::starting_at(10);
+    assert_eq!(generator.generate(), Value::from(10));
+    assert_eq!(generator.generate(), Value::from(11));
+    generator.observe(&Value::from(20));
+    generator.observe(&Value::from(5));
+    assert_eq!(generator.generate(), Value::from(21));
+  }
+
+  #[test]
+  fn test_seeded_is_deterministic() {
+    let first: Vec<Value> = (0..3).map(|_| Seeded::new(42).generate()).collect();
+    assert_eq!(first[0], first[1]);
+
+    let generator = Seeded::new(42);
+    let ids: Vec<Value> = (0..3).map(|_| generator.generate()).collect();
+    assert_eq!(ids[0], first[0]);
+    assert_ne!(ids[0], ids[1]);
+    assert!(Uuid::parse_str(ids[2].as_str().unwrap()).is_ok());
+  }
+}
@@ -0,0 +1,404 @@
+//! Import and export of collections in `mongoexport` formats.
+//!
+//! JSON Lines contains one document per line.  JSON array contains all documents in
+//! single array.  Both formats are streamed, so neither input nor output has to fit in
+//! memory at once.
+//!
+//! Imported documents may use canonical or relaxed MongoDB Extended JSON.  Type wrappers
+//! are stored in relaxed form, see `extended_json` module.
+//!
+
+#[cfg(feature = "bson")]
+use super::bson_format::to_bson;
+use super::{
+  errors::Error,
+  extended_json::{self, ExtendedJsonMode},
+  index::ID_FIELD,
+};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Bytes, Read, Write};
+
+/// What happens with `_id` of imported documents.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IdPolicy {
+  /// Keep `_id` from input.  Documents without one get new `_id`.
+  #[default]
+  Keep,
+  /// Ignore `_id` from input and give every document new `_id`.
+  Regenerate,
+}
+
+/// What happens when document cannot be parsed or inserted.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ErrorPolicy {
+  /// Stop import and return `MQImportError` with line number.  Documents before
+  /// failing line stay imported.
+  #[default]
+  Abort,
+  /// Record failing line in `ImportSummary` and continue.
+  Skip,
+}
+
+/// Options for `Collection::import_jsonl`, `Collection::import_json_array` and
+/// `Collection::import_bson`.
+#[derive(Clone, Debug)]
+pub struct ImportOptions {
+  pub id: IdPolicy,
+  pub on_error: ErrorPolicy,
+  /// Number of documents inserted under one lock of collection.
+  pub batch_size: usize,
+}
+
+impl Default for ImportOptions {
+  fn default() -> Self {
+    ImportOptions {
+      id: IdPolicy::default(),
+      on_error: ErrorPolicy::default(),
+      batch_size: 1000,
+    }
+  }
+}
+
+/// Options for `Collection::export_jsonl`, `Collection::export_json_array` and
+/// `Collection::export_bson`.
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+  /// Write `_id` of documents.
+  pub include_id: bool,
+  /// Extended JSON format of exported documents.  Not used by BSON export.
+  pub mode: ExtendedJsonMode,
+}
+
+impl Default for ExportOptions {
+  fn default() -> Self {
+    ExportOptions {
+      include_id: true,
+      mode: ExtendedJsonMode::default(),
+    }
+  }
+}
+
+/// Input line that was skipped because of `ErrorPolicy::Skip`.
+#[derive(Debug)]
+pub struct SkippedRecord {
+  /// Line of JSON Lines input, line where document starts in JSON array, or position of
+  /// document in BSON input.
+  pub line: usize,
+  pub error: Error,
+}
+
+/// Result of import.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+  pub inserted: u64,
+  pub skipped: Vec<SkippedRecord>,
+}
+
+impl ImportOptions {
+  pub(crate) fn batch_size(&self) -> usize {
+    self.batch_size.max(1)
+  }
+
+  /// Checks parsed document, converts Extended JSON and applies `_id` policy.
+  pub(crate) fn prepare(&self, document: Result<Value, Error>) -> Result<Value, Error> {
+    let mut document = extended_json::parse(document?)?;
+    match document.as_object_mut() {
+      Some(fields) => {
+        if self.id == IdPolicy::Regenerate {
+          fields.remove(ID_FIELD);
+        }
+        Ok(document)
+      }
+      None => Err(Error::MQError(String::from(
+        "Document must be a JSON object.",
+      ))),
+    }
+  }
+
+  /// Applies error policy to document that failed.
+  pub(crate) fn reject(
+    &self,
+    summary: &mut ImportSummary,
+    line: usize,
+    error: Error,
+  ) -> Result<(), Error> {
+    match self.on_error {
+      ErrorPolicy::Abort => Err(Error::MQImportError {
+        line,
+        error: Box::new(error),
+      }),
+      ErrorPolicy::Skip => {
+        summary.skipped.push(SkippedRecord { line, error });
+        Ok(())
+      }
+    }
+  }
+
+  /// Adds results of inserting batch to summary.
+  pub(crate) fn record_results(
+    &self,
+    summary: &mut ImportSummary,
+    lines: Vec<usize>,
+    results: Vec<Result<(), Error>>,
+  ) -> Result<(), Error> {
+    for (line, result) in lines.into_iter().zip(results) {
+      match result {
+        Ok(()) => summary.inserted += 1,
+        Err(e) => self.reject(summary, line, e)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Document read from input together with its line number.
+pub(crate) struct Record {
+  pub line: usize,
+  pub document: Result<Value, Error>,
+}
+
+/// Reads one document per line.  Empty lines are ignored.
+pub(crate) struct JsonLines<R> {
+  reader: BufReader<R>,
+  line: usize,
+}
+
+impl<R: Read> JsonLines<R> {
+  pub fn new(reader: R) -> JsonLines<R> {
+    JsonLines {
+      reader: BufReader::new(reader),
+      line: 0,
+    }
+  }
+}
+
+impl<R: Read> Iterator for JsonLines<R> {
+  type Item = Result<Record, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let mut data = Vec::new();
+      match self.reader.read_until(b'\n', &mut data) {
+        Ok(0) => return None,
+        Ok(_) => {
+          self.line += 1;
+          if data.iter().all(|b| b.is_ascii_whitespace()) {
+            continue;
+          }
+          return Some(Ok(Record {
+            line: self.line,
+            document: serde_json::from_slice(&data).map_err(Error::from),
+          }));
+        }
+        Err(e) => return Some(Err(e.into())),
+      }
+    }
+  }
+}
+
+#[derive(PartialEq)]
+enum ArrayState {
+  Start,
+  Elements,
+  Done,
+}
+
+/// Reads documents of JSON array one at a time.
+///
+/// Elements are split on commas outside of strings and nested values, so document that
+/// is not valid JSON can be skipped without losing the rest of the array.
+pub(crate) struct JsonArray<R> {
+  bytes: Bytes<BufReader<R>>,
+  line: usize,
+  state: ArrayState,
+}
+
+impl<R: Read> JsonArray<R> {
+  pub fn new(reader: R) -> JsonArray<R> {
+    JsonArray {
+      bytes: BufReader::new(reader).bytes(),
+      line: 1,
+      state: ArrayState::Start,
+    }
+  }
+
+  fn fail(&mut self, message: &str) -> Option<Result<Record, Error>> {
+    self.state = ArrayState::Done;
+    Some(Err(Error::MQImportError {
+      line: self.line,
+      error: Box::new(Error::MQError(message.to_string())),
+    }))
+  }
+
+  fn record(line: usize, element: &[u8]) -> Option<Result<Record, Error>> {
+    Some(Ok(Record {
+      line,
+      document: serde_json::from_slice(element).map_err(Error::from),
+    }))
+  }
+}
+
+impl<R: Read> Iterator for JsonArray<R> {
+  type Item = Result<Record, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let mut element = Vec::new();
+    let mut start_line = self.line;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    while self.state != ArrayState::Done {
+      let byte = match self.bytes.next() {
+        Some(Ok(byte)) => byte,
+        Some(Err(e)) => return Some(Err(e.into())),
+        None if self.state == ArrayState::Start => return None,
+        None => return self.fail("JSON array is not terminated."),
+      };
+      if byte == b'\n' {
+        self.line += 1;
+      }
+
+      if self.state == ArrayState::Start {
+        match byte {
+          b'[' => self.state = ArrayState::Elements,
+          b if b.is_ascii_whitespace() => (),
+          _ => return self.fail("Input is not a JSON array."),
+        }
+        continue;
+      }
+
+      if in_string {
+        element.push(byte);
+        if escaped {
+          escaped = false;
+        } else if byte == b'\\' {
+          escaped = true;
+        } else if byte == b'"' {
+          in_string = false;
+        }
+        continue;
+      }
+
+      match byte {
+        b']' if depth == 0 => {
+          self.state = ArrayState::Done;
+          if !element.is_empty() {
+            return Self::record(start_line, &element);
+          }
+        }
+        b',' if depth == 0 => {
+          if element.is_empty() {
+            return self.fail("Missing document in JSON array.");
+          }
+          return Self::record(start_line, &element);
+        }
+        b if b.is_ascii_whitespace() && element.is_empty() => (),
+        _ => {
+          if element.is_empty() {
+            start_line = self.line;
+          }
+          match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => (),
+          }
+          element.push(byte);
+        }
+      }
+    }
+
+    None
+  }
+}
+
+/// Output format of export.
+#[derive(Clone, Copy)]
+pub(crate) enum ExportFormat {
+  JsonLines,
+  JsonArray,
+  #[cfg(feature = "bson")]
+  Bson,
+}
+
+impl ExportFormat {
+  pub fn begin<W: Write>(self, writer: &mut W) -> Result<(), Error> {
+    if let ExportFormat::JsonArray = self {
+      writer.write_all(b"[")?;
+    }
+    Ok(())
+  }
+
+  pub fn write<W: Write>(self, writer: &mut W, document: &Value, first: bool) -> Result<(), Error> {
+    match self {
+      ExportFormat::JsonLines => {
+        serde_json::to_writer(&mut *writer, document)?;
+        writer.write_all(b"\n")?;
+      }
+      ExportFormat::JsonArray => {
+        writer.write_all(if first { b"\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut *writer, document)?;
+      }
+      #[cfg(feature = "bson")]
+      ExportFormat::Bson => to_bson(document)?.to_writer(&mut *writer)?,
+    }
+    Ok(())
+  }
+
+  pub fn end<W: Write>(self, writer: &mut W, count: u64) -> Result<(), Error> {
+    if let ExportFormat::JsonArray = self {
+      writer.write_all(if count > 0 { b"\n]\n" } else { b"]\n" })?;
+    }
+    writer.flush()?;
+    Ok(())
+  }
+}
+
+/// Prepares document for export.
+pub(crate) fn export_document(document: Value, options: &ExportOptions) -> Value {
+  let mut document = match options.mode {
+    ExtendedJsonMode::Relaxed => document,
+    mode => extended_json::serialize(&document, mode),
+  };
+  if !options.include_id {
+    if let Some(fields) = document.as_object_mut() {
+      fields.remove(ID_FIELD);
+    }
+  }
+  document
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lines<I: Iterator<Item = Result<Record, Error>>>(records: I) -> Vec<(usize, bool)> {
+    records
+      .map(|r| r.unwrap())
+      .map(|r| (r.line, r.document.is_ok()))
+      .collect()
+  }
+
+  #[test]
+  fn test_json_lines() {
+    let input = "{\"a\": 1}\n\n{\"a\": \n{\"a\": 3}";
+    assert_eq!(
+      lines(JsonLines::new(input.as_bytes())),
+      vec![(1, true), (3, false), (4, true)]
+    );
+  }
+
+  #[test]
+  fn test_json_array() {
+    let input = "[\n  {\"a\": \"x,]\\\"\"},\n  {\"a\": [1, {\"b\": 2}]},\n  {\"a\" 3},\n  {}\n]";
+    assert_eq!(
+      lines(JsonArray::new(input.as_bytes())),
+      vec![(2, true), (3, true), (4, false), (5, true)]
+    );
+    assert!(JsonArray::new(&b"[]"[..]).next().is_none());
+    assert!(JsonArray::new(&b""[..]).next().is_none());
+    assert!(JsonArray::new(&b"{}"[..]).next().unwrap().is_err());
+    assert!(JsonArray::new(&b"[{}"[..]).next().unwrap().is_err());
+  }
+}
@@ -0,0 +1,500 @@
+//! Indexes over documents stored in a collection.
+//!
+//! Every collection has unique `_id_` index.  Additional (optionally unique and partial)
+//! indexes can be created with `Collection::create_index`.
+//!
+//! Index with `expire_after` is TTL index.  Document expires once `expire_after` has passed
+//! since date in its indexed field, for array of dates since the earliest one.  Expired
+//! documents are not returned by queries and are removed by the next write or by
+//! `Collection::purge_expired`.  Index on `expireAt` with `expire_after` of zero expires
+//! every document at its own time.
+//!
+
+use super::{
+  errors::Error,
+  extended_json::{date, ExtendedValue},
+  query::CompiledQuery,
+  utils::get_path_value,
+};
+use im::{OrdMap, OrdSet};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Name of the index that is always present on `_id` field.
+pub const ID_INDEX_NAME: &str = "_id_";
+
+/// Name of the document id field.
+pub const ID_FIELD: &str = "_id";
+
+/// Options used when creating new index.
+#[derive(Clone, Debug, Default)]
+pub struct IndexOptions {
+  /// Name of the index.  Defaults to key paths joined with `_1`, for example `email_1`.
+  pub name: Option<String>,
+  /// Reject documents that would have the same key as already indexed document.
+  pub unique: bool,
+  /// Do not index documents that do not have any of the indexed fields.
+  pub sparse: bool,
+  /// Only index documents that match this query.
+  pub partial_filter_expression: Option<Value>,
+  /// Expire documents this long after date in the indexed field.  Index must have single key.
+  pub expire_after: Option<Duration>,
+}
+
+/// Index declared for document type, see `Document::indexes`.
+#[derive(Clone, Debug)]
+pub struct IndexModel {
+  pub keys: Vec<String>,
+  pub options: IndexOptions,
+}
+
+/// Describes index created on collection.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexDefinition {
+  pub name: String,
+  pub keys: Vec<String>,
+  pub unique: bool,
+  pub sparse: bool,
+  pub partial_filter_expression: Option<Value>,
+  #[serde(
+    default,
+    rename = "expire_after_seconds",
+    skip_serializing_if = "Option::is_none",
+    with = "seconds"
+  )]
+  pub expire_after: Option<Duration>,
+}
+
+/// Stores TTL as number of seconds like MongoDB `expireAfterSeconds`.
+mod seconds {
+  use serde::{de::Error, Deserialize, Deserializer, Serializer};
+  use std::time::Duration;
+
+  pub fn serialize<S: Serializer>(value: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    match value {
+      Some(d) if d.subsec_nanos() == 0 => s.serialize_some(&d.as_secs()),
+      Some(d) => s.serialize_some(&d.as_secs_f64()),
+      None => s.serialize_none(),
+    }
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+    Option::<f64>::deserialize(d)?
+      .map(|secs| Duration::try_from_secs_f64(secs).map_err(D::Error::custom))
+      .transpose()
+  }
+}
+
+impl IndexDefinition {
+  pub fn new(keys: &[&str], options: IndexOptions) -> Result<IndexDefinition, Error> {
+    if keys.is_empty() {
+      return Err(Error::MQInvalidValue(String::from(
+        "Index must have at least one key.",
+      )));
+    }
+
+    if keys
+      .iter()
+      .any(|k| k.is_empty() || k.split('.').any(|p| p.starts_with('$')))
+    {
+      return Err(Error::MQInvalidValue(format!(
+        "Invalid index keys {:?}.",
+        keys
+      )));
+    }
+
+    if let Some(filter) = &options.partial_filter_expression {
+      CompiledQuery::new(filter)?;
+    }
+
+    if options.expire_after.is_some() && (keys.len() != 1 || keys[0] == ID_FIELD) {
+      return Err(Error::MQInvalidValue(String::from(
+        "TTL index must have single key other than _id.",
+      )));
+    }
+
+    let name = options.name.unwrap_or_else(|| {
+      keys
+        .iter()
+        .map(|k| format!("{}_1", k))
+        .collect::<Vec<String>>()
+        .join("_")
+    });
+
+    Ok(IndexDefinition {
+      name,
+      keys: keys.iter().map(|k| k.to_string()).collect(),
+      unique: options.unique,
+      sparse: options.sparse,
+      partial_filter_expression: options.partial_filter_expression,
+      expire_after: options.expire_after,
+    })
+  }
+
+  /// Options that create the same index.
+  pub fn options(&self) -> IndexOptions {
+    IndexOptions {
+      name: Some(self.name.clone()),
+      unique: self.unique,
+      sparse: self.sparse,
+      partial_filter_expression: self.partial_filter_expression.clone(),
+      expire_after: self.expire_after,
+    }
+  }
+
+  /// Definition of the `_id_` index.
+  ///
+  /// Documents without `_id` field are not indexed.
+  pub fn id_index() -> IndexDefinition {
+    IndexDefinition {
+      name: ID_INDEX_NAME.to_string(),
+      keys: vec![ID_FIELD.to_string()],
+      unique: true,
+      sparse: true,
+      partial_filter_expression: None,
+      expire_after: None,
+    }
+  }
+}
+
+/// Index key with total ordering across JSON values.
+///
+/// Values of different types are ordered the same way MongoDB orders BSON types:
+/// null, numbers, strings, objects, arrays, binary data, object ids, booleans, dates.
+/// Numbers, including Extended JSON `$numberLong` and similar, are compared by value so
+/// `1` and `1.0` are the same key.
+#[derive(Clone, Debug)]
+pub(crate) struct IndexKey(pub Vec<Value>);
+
+impl PartialEq for IndexKey {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for IndexKey {}
+
+impl PartialOrd for IndexKey {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for IndexKey {
+  fn cmp(&self, other: &Self) -> Ordering {
+    compare_value_lists(&self.0, &other.0)
+  }
+}
+
+pub(crate) fn type_rank(value: &Value) -> u8 {
+  match value {
+    Value::Null => 1,
+    Value::Number(_) => 2,
+    Value::String(_) => 3,
+    Value::Object(_) => match ExtendedValue::from_value(value) {
+      Some(ExtendedValue::Binary { .. }) => 6,
+      Some(ExtendedValue::ObjectId(_)) => 7,
+      Some(ExtendedValue::Date(_)) => 9,
+      Some(_) => 2,
+      None => 4,
+    },
+    Value::Array(_) => 5,
+    Value::Bool(_) => 8,
+  }
+}
+
+/// Number as exact integer when possible, otherwise as float.
+enum Numeric {
+  Integer(i128),
+  Float(f64),
+}
+
+impl Numeric {
+  fn of(value: &Value) -> Option<Numeric> {
+    match value {
+      Value::Number(n) => Some(match (n.as_i64(), n.as_u64()) {
+        (Some(i), _) => Numeric::Integer(i128::from(i)),
+        (None, Some(u)) => Numeric::Integer(i128::from(u)),
+        _ => Numeric::Float(n.as_f64().unwrap_or(f64::NAN)),
+      }),
+      Value::Object(_) => {
+        let typed = ExtendedValue::from_value(value)?;
+        match typed.as_i64() {
+          Some(i) => Some(Numeric::Integer(i128::from(i))),
+          None => typed.as_f64().map(Numeric::Float),
+        }
+      }
+      _ => None,
+    }
+  }
+
+  fn as_f64(&self) -> f64 {
+    match self {
+      Numeric::Integer(i) => *i as f64,
+      Numeric::Float(f) => *f,
+    }
+  }
+}
+
+/// Compares numbers by value.  NaN is smaller than any other number.
+fn compare_numbers(x: &Numeric, y: &Numeric) -> Ordering {
+  if let (Numeric::Integer(x), Numeric::Integer(y)) = (x, y) {
+    return x.cmp(y);
+  }
+  let (x, y) = (x.as_f64(), y.as_f64());
+  match (x.is_nan(), y.is_nan()) {
+    (true, true) => Ordering::Equal,
+    (true, false) => Ordering::Less,
+    (false, true) => Ordering::Greater,
+    (false, false) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+  }
+}
+
+/// Compares values when at least one of them is Extended JSON type wrapper.
+fn compare_extended(a: &Value, b: &Value) -> Option<Ordering> {
+  let (x, y) = (ExtendedValue::from_value(a), ExtendedValue::from_value(b));
+  if x.is_none() && y.is_none() {
+    return None;
+  }
+  let rank = type_rank(a).cmp(&type_rank(b));
+  if rank != Ordering::Equal {
+    return Some(rank);
+  }
+  Some(match (x, y) {
+    (
+      Some(ExtendedValue::Binary {
+        subtype: xs,
+        bytes: xb,
+      }),
+      Some(ExtendedValue::Binary {
+        subtype: ys,
+        bytes: yb,
+      }),
+    ) => (xb.len(), xs, &xb).cmp(&(yb.len(), ys, &yb)),
+    (Some(ExtendedValue::ObjectId(x)), Some(ExtendedValue::ObjectId(y))) => x.cmp(&y),
+    (Some(ExtendedValue::Date(x)), Some(ExtendedValue::Date(y))) => x.cmp(&y),
+    _ => compare_numbers(&Numeric::of(a)?, &Numeric::of(b)?),
+  })
+}
+
+fn compare_value_lists(a: &[Value], b: &[Value]) -> Ordering {
+  for (x, y) in a.iter().zip(b.iter()) {
+    let ord = compare_values(x, y);
+    if ord != Ordering::Equal {
+      return ord;
+    }
+  }
+  a.len().cmp(&b.len())
+}
+
+/// Compares two JSON values using MongoDB like type ordering.
+pub(crate) fn compare_values(a: &Value, b: &Value) -> Ordering {
+  if a.is_object() || b.is_object() {
+    if let Some(ord) = compare_extended(a, b) {
+      return ord;
+    }
+  }
+  match (a, b) {
+    (Value::Number(_), Value::Number(_)) => match (Numeric::of(a), Numeric::of(b)) {
+      (Some(x), Some(y)) => compare_numbers(&x, &y),
+      _ => Ordering::Equal,
+    },
+    (Value::String(x), Value::String(y)) => x.cmp(y),
+    (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+    (Value::Array(x), Value::Array(y)) => compare_value_lists(x, y),
+    (Value::Object(x), Value::Object(y)) => {
+      for ((xk, xv), (yk, yv)) in x.iter().zip(y.iter()) {
+        let ord = xk.cmp(yk).then_with(|| compare_values(xv, yv));
+        if ord != Ordering::Equal {
+          return ord;
+        }
+      }
+      x.len().cmp(&y.len())
+    }
+    (Value::Null, Value::Null) => Ordering::Equal,
+    _ => type_rank(a).cmp(&type_rank(b)),
+  }
+}
+
+/// Index entries mapping keys to document sequence numbers.
+///
+/// Entries are persistent maps, so index is copied in constant time together with
+/// collection data and copies share unchanged entries.
+#[derive(Clone)]
+pub(crate) struct Index {
+  pub definition: IndexDefinition,
+  pub partial_filter: Option<CompiledQuery>,
+  pub entries: OrdMap<IndexKey, OrdSet<u64>>,
+}
+
+impl Index {
+  pub fn new(definition: IndexDefinition) -> Result<Index, Error> {
+    let partial_filter = match &definition.partial_filter_expression {
+      Some(filter) => Some(CompiledQuery::new(filter)?),
+      None => None,
+    };
+
+    Ok(Index {
+      definition,
+      partial_filter,
+      entries: OrdMap::new(),
+    })
+  }
+
+  pub fn id_index() -> Index {
+    Index {
+      definition: IndexDefinition::id_index(),
+      partial_filter: None,
+      entries: OrdMap::new(),
+    }
+  }
+
+  /// Returns keys for the document ignoring partial filter expression.
+  ///
+  /// Arrays are indexed by their elements so single document may have multiple keys.
+  pub fn keys(&self, document: &Value) -> Vec<IndexKey> {
+    let values: Vec<Option<&Value>> = self
+      .definition
+      .keys
+      .iter()
+      .map(|k| get_path_value(document, k))
+      .collect();
+
+    if self.definition.sparse && values.iter().all(|v| v.is_none()) {
+      return Vec::new();
+    }
+
+    let mut keys: Vec<Vec<Value>> = vec![Vec::new()];
+    for value in values {
+      let field_values = match value {
+        Some(Value::Array(items)) if !items.is_empty() => items.clone(),
+        Some(v) => vec![v.clone()],
+        None => vec![Value::Null],
+      };
+      keys = keys
+        .into_iter()
+        .flat_map(|prefix| {
+          field_values.iter().map(move |v| {
+            let mut key = prefix.clone();
+            key.push(v.clone());
+            key
+          })
+        })
+        .collect();
+    }
+
+    let unique_keys: BTreeSet<IndexKey> = keys.into_iter().map(IndexKey).collect();
+    unique_keys.into_iter().collect()
+  }
+
+  pub fn insert(&mut self, seq: u64, keys: &[IndexKey]) {
+    for key in keys {
+      self.entries.entry(key.clone()).or_default().insert(seq);
+    }
+  }
+
+  pub fn remove(&mut self, seq: u64, keys: &[IndexKey]) {
+    for key in keys {
+      if let Some(seqs) = self.entries.get_mut(key) {
+        seqs.remove(&seq);
+        if seqs.is_empty() {
+          self.entries.remove(key);
+        }
+      }
+    }
+  }
+
+  /// Documents whose indexed date is at least `expire_after` before `now`, none if index is
+  /// not TTL index.  Dates sort after all other values, so they are found by range scan.
+  pub fn expired(&self, now: i64) -> Vec<u64> {
+    let expire_after = match self.definition.expire_after {
+      Some(expire_after) => expire_after,
+      None => return Vec::new(),
+    };
+    let cutoff = now.saturating_sub(expire_after.as_millis() as i64);
+    let from = IndexKey(vec![date(i64::MIN)]);
+    let to = IndexKey(vec![date(cutoff)]);
+    self
+      .entries
+      .range(from..=to)
+      .flat_map(|(_, seqs)| seqs.iter().copied())
+      .collect()
+  }
+
+  /// Checks that none of the keys is already used by document that is not in `ignore` set.
+  pub fn check_unique(&self, keys: &[IndexKey], ignore: &BTreeSet<u64>) -> Result<(), Error> {
+    if !self.definition.unique {
+      return Ok(());
+    }
+
+    for key in keys {
+      if let Some(seqs) = self.entries.get(key) {
+        if seqs.iter().any(|s| !ignore.contains(s)) {
+          return Err(self.duplicate_key_error(key));
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn duplicate_key_error(&self, key: &IndexKey) -> Error {
+    let mut key_value = Map::new();
+    for (field, value) in self.definition.keys.iter().zip(key.0.iter()) {
+      key_value.insert(field.clone(), value.clone());
+    }
+    Error::MQDuplicateKey {
+      index: self.definition.name.clone(),
+      key: Value::Object(key_value),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_numbers_compare_by_value() {
+    assert_eq!(IndexKey(vec![json!(1)]), IndexKey(vec![json!(1.0)]));
+    assert!(IndexKey(vec![json!(-1)]) < IndexKey(vec![json!(2.5)]));
+  }
+
+  #[test]
+  fn test_type_order() {
+    assert!(IndexKey(vec![json!(null)]) < IndexKey(vec![json!(0)]));
+    assert!(IndexKey(vec![json!(10)]) < IndexKey(vec![json!("a")]));
+    assert!(IndexKey(vec![json!("z")]) < IndexKey(vec![json!({})]));
+    assert!(IndexKey(vec![json!([])]) < IndexKey(vec![json!(false)]));
+  }
+
+  #[test]
+  fn test_array_values_are_indexed_by_element() -> Result<(), Error> {
+    let index = Index::new(IndexDefinition::new(&["tags"], IndexOptions::default())?)?;
+    let keys = index.keys(&json!({ "tags": ["a", "b", "a"] }));
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0], IndexKey(vec![json!("a")]));
+    assert_eq!(keys[1], IndexKey(vec![json!("b")]));
+    Ok(())
+  }
+
+  #[test]
+  fn test_missing_field() -> Result<(), Error> {
+    let index = Index::new(IndexDefinition::new(&["email"], IndexOptions::default())?)?;
+    assert_eq!(index.keys(&json!({})), vec![IndexKey(vec![json!(null)])]);
+
+    let sparse = Index::new(IndexDefinition::new(
+      &["email"],
+      IndexOptions {
+        sparse: true,
+        ..Default::default()
+      },
+    )?)?;
+    assert!(sparse.keys(&json!({})).is_empty());
+    Ok(())
+  }
+}
@@ -4,17 +4,17 @@
 //!
 //! This is not a database and it is not trying to do any optimizations.  It is meant for unit tests or simple projects that require small in memory document store.
 //!
-//! The library uses async API that support tokio.  The library also has a sync API that may be enabled via `sync` feature flag.
+//! The library has async API that runs on any executor (`memquery::r#async`, also at `memquery::memdb`) and blocking API (`memquery::blocking`).  Both are compiled together and share one engine.
 //!
 //! # Example Usage
 //!
 //! ## Create Database
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //!     let memdb = MemDb::new();
 //! #   Ok(())
@@ -24,13 +24,13 @@
 //! ## Create Collection
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //!     let memdb = MemDb::new();
-//!     memdb.create_collection("TestCollection").await;
+//!     memdb.create_collection("TestCollection").await?;
 //! #   Ok(())
 //! # }
 //! ```
@@ -38,13 +38,13 @@
 //! ## Get Collection Handle
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //!     let coll = memdb.collection("TestCollection").await?;
 //! #   Ok(())
 //! # }
@@ -53,13 +53,13 @@
 //! ## Insert Document
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //!     let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
 //! #   Ok(())
@@ -69,13 +69,13 @@
 //! ## Find Document
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //!     let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Tom", "age": 25 })).await?;
 //!     let docs = coll.find(query!({"name": "Tom", "age": 25})).await?;
@@ -88,13 +88,13 @@
 //! ### $and
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll
 //!         .find(query!({ "$and": [{ "name": "Bob" }, { "age": 20 }] }))
@@ -106,13 +106,13 @@
 //! ### $or
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll
 //!       .find(query!({ "$or": [{ "name": "Bob" }, { "age": 30 }] }))
@@ -128,13 +128,13 @@
 //! Compare on field:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll.find(query!({ "qty": { "$eq": 20 } })).await?;
 //! #   Ok(())
@@ -144,13 +144,13 @@
 //! Or in embedded document:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll.find(query!({ "item.name": { "$eq": "ab" } })).await?;
 //! #   Ok(())
@@ -160,13 +160,13 @@
 //! You can also compare array with embedded arrays:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll
 //!       .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -200,13 +200,13 @@
 //! Or value in the embedded array:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll
 //!       .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -240,13 +240,13 @@
 //! ### $gt
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll.find(query!({ "qty": { "$gt": 20 } })).await?;
 //! #   Ok(())
@@ -256,13 +256,13 @@
 //! ### $gte
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll.find(query!({ "qty": { "$gte": 20 } })).await?;
 //! #   Ok(())
@@ -272,13 +272,13 @@
 //! ### $lt
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll.find(query!({ "qty": { "$lt": 20 } })).await?;
 //! #   Ok(())
@@ -288,13 +288,13 @@
 //! ### $lte
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll.find(query!({ "qty": { "$lte": 20 } })).await?;
 //! #   Ok(())
@@ -304,13 +304,13 @@
 //! ### Find All Documents
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     let docs = coll.find(query!({})).await?;
 //! #   Ok(())
@@ -324,13 +324,13 @@
 //! Update document by replacing entire document:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query, update};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
 //!     coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -355,13 +355,13 @@
 //! Update specific field(s) in the document:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query, update};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
 //!     coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -387,13 +387,13 @@
 //! Update document to remove field:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query, update};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
 //!     coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -433,13 +433,13 @@
 //! Increment value of the field in the document:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query, update};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
 //!     coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -461,13 +461,13 @@
 //! Multiply value of a field in the document:
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query, update};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
 //!     coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -489,13 +489,13 @@
 //! # Delete Documents
 //!
 //! ```rust
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! use memquery::{errors::Error, memdb::MemDb, doc, query};
 //!
-//! # #[cfg(not(feature = "sync"))]
+//! # #[cfg(feature = "async")]
 //! # async fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection").await;
+//! #   memdb.create_collection("TestCollection").await?;
 //! #   let coll = memdb.collection("TestCollection").await?;
 //!     coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
 //!     coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -510,18 +510,17 @@
 //! # }
 //! ```
 //!
-//! # Sync API
+//! # Blocking API
 //!
-//! To use sync API you need to enable it using `sync` feature flag.
+//! Blocking API is in `blocking` module.  It can be used together with async API, which is
+//! also available as `r#async` module.
 //!
 //! ```
-//! # #[cfg(feature = "sync")]
-//! use memquery::{doc, errors::Error, query, memdb::MemDb};
+//! use memquery::{blocking::MemDb, doc, errors::Error, query};
 //!
-//! # #[cfg(feature = "sync")]
 //! # fn play() -> Result<(), Error> {
 //! #   let memdb = MemDb::new();
-//! #   memdb.create_collection("TestCollection");
+//! #   memdb.create_collection("TestCollection")?;
 //! #   let coll = memdb.collection("TestCollection")?;
 //!     coll.insert(
 //!       doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -542,11 +541,43 @@
 //! #   Ok(())
 //! # }
 //! ```
+#[cfg(feature = "async")]
+pub mod r#async;
+pub mod blocking;
+#[cfg(feature = "bson")]
+pub mod bson_format;
+pub mod builder;
+pub mod change_stream;
+pub mod clock;
+#[cfg(feature = "async")]
 pub mod collection;
+mod database;
+pub mod document;
 mod engine;
+#[macro_use]
+mod enum_to_str;
 pub mod errors;
+pub mod extended_json;
+pub mod id_generator;
+pub mod import_export;
+pub mod index;
 pub mod macros;
+#[cfg(feature = "async")]
 pub mod memdb;
+pub mod options;
+pub mod planner;
+pub mod query;
+pub mod snapshot;
+pub mod stats;
+pub mod tailable;
+pub mod transaction;
+pub mod typed_collection;
 mod utils;
+pub mod validation;
+pub mod view;
+#[cfg(feature = "persistence")]
+pub mod wal;
 
-pub use engine::{DocumentCollection, Documents};
+pub use document::Document;
+pub use engine::{CollectionData, DocumentCollection, Documents};
+pub use memquery_derive::Document;
@@ -1,14 +1,15 @@
 /// Construct a `serde_json::Value` from a JSON literal
 /// representing document.
 ///
-/// This is equivalent to serde_json::json but also adds
-/// _id field with uuid
+/// This is equivalent to serde_json::json but checks that
+/// value is an object.  `_id` field is not added, documents
+/// inserted without it get one from `IdGenerator` of collection.
 ///
 /// ```
-/// # #[cfg(not(feature = "sync"))]
+/// # #[cfg(feature = "async")]
 /// use memquery::{doc, errors::Error, memdb::MemDb};
 ///
-/// # #[cfg(not(feature = "sync"))]
+/// # #[cfg(feature = "async")]
 /// async fn play() -> Result<(), Error> {
 ///   let memdb = MemDb::new();
 ///   let coll = memdb.collection("TestCollection").await?;
@@ -21,9 +22,8 @@
 macro_rules! doc {
   ($($json:tt)+) => {
     {
-    let mut v = serde_json::json!($($json)+);
+    let v = serde_json::json!($($json)+);
     assert!(v.is_object());
-    v["_id"] = serde_json::json!(uuid::Uuid::new_v4());
     v
     }
   };
@@ -34,10 +34,10 @@ macro_rules! doc {
 ///
 ///
 /// ```
-/// # #[cfg(not(feature = "sync"))]
+/// # #[cfg(feature = "async")]
 /// use memquery::{doc, errors::Error, memdb::MemDb, query};
 ///
-/// # #[cfg(not(feature = "sync"))]
+/// # #[cfg(feature = "async")]
 /// async fn play() -> Result<(), Error> {
 ///   let memdb = MemDb::new();
 ///   let coll = memdb.collection("TestCollection").await?;
@@ -59,10 +59,10 @@ macro_rules! query {
 ///
 ///
 /// ```
-/// # #[cfg(not(feature = "sync"))]
+/// # #[cfg(feature = "async")]
 /// use memquery::{doc, errors::Error, memdb::MemDb, query, update};
 ///
-/// # #[cfg(not(feature = "sync"))]
+/// # #[cfg(feature = "async")]
 /// async fn play() -> Result<(), Error> {
 ///   let memdb = MemDb::new();
 ///   let coll = memdb.collection("TestCollection").await?;
@@ -87,10 +87,10 @@ macro_rules! update {
 #[cfg(test)]
 mod tests {
   #[test]
-  fn test_adding_id() {
+  fn test_no_id_added() {
     let doc = doc!({ "name": "test", "value": 1 });
-    assert_ne!(doc["_id"], serde_json::Value::Null);
-    println!("doc {}", doc);
+    assert_eq!(doc, serde_json::json!({ "name": "test", "value": 1 }));
+    assert!(doc.get("_id").is_none());
   }
 
   #[test]
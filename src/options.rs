@@ -0,0 +1,109 @@
+//! Options of collections and queries.
+//!
+//! Collection options are given to `MemDb::create_collection_with_options` and kept in
+//! snapshots and write-ahead log together with the collection.
+//!
+
+use super::{errors::Error, validation::Validator};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+  value == &T::default()
+}
+
+/// Options of collection.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectionOptions {
+  /// Bounded collection that evicts oldest documents, see `Capped`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub capped: Option<Capped>,
+  /// Query document that inserted and updated documents must match, may contain
+  /// `$jsonSchema` (see `validation` module).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub validator: Option<Value>,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub validation_level: ValidationLevel,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub validation_action: ValidationAction,
+}
+
+impl CollectionOptions {
+  pub fn is_default(&self) -> bool {
+    is_default(self)
+  }
+
+  pub(crate) fn validate(&self) -> Result<(), Error> {
+    if let Some(capped) = &self.capped {
+      if capped.max_docs.is_none() && capped.max_bytes.is_none() {
+        return Err(Error::MQInvalidValue(String::from(
+          "Capped collection must have max_docs or max_bytes.",
+        )));
+      }
+      if capped.max_docs == Some(0) || capped.max_bytes == Some(0) {
+        return Err(Error::MQInvalidValue(String::from(
+          "Capped collection limits must be greater than 0.",
+        )));
+      }
+    }
+    if let Some(validator) = &self.validator {
+      Validator::new(validator)?;
+    }
+    Ok(())
+  }
+}
+
+/// Documents that validator checks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationLevel {
+  /// Every insert and update.
+  #[default]
+  Strict,
+  /// Inserts and updates of documents that were valid before update, so documents that
+  /// existed before validator was added can still be changed.
+  Moderate,
+}
+
+/// What happens to document that does not satisfy validator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationAction {
+  /// Change fails with `MQValidationFailed` error.
+  #[default]
+  Error,
+  /// Change is made and failures are logged as warnings with `log` crate.
+  Warn,
+}
+
+/// Limits of capped collection.
+///
+/// Insert that exceeds a limit evicts oldest documents in insertion order until collection
+/// fits again.  Size of document is length of its JSON text.  Document larger than
+/// `max_bytes` cannot be inserted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capped {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_docs: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_bytes: Option<u64>,
+}
+
+/// Options for `Collection::find_with_options`.
+#[derive(Clone, Debug, Default)]
+pub struct FindOptions {
+  /// Return documents in reverse insertion order, newest first.
+  pub newest_first: bool,
+  /// Return at most this many documents.
+  pub limit: Option<usize>,
+}
+
+/// Options for `Collection::count_documents`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CountOptions {
+  /// Do not count this many matching documents.
+  pub skip: usize,
+  /// Count at most this many documents after skipped ones.
+  pub limit: Option<usize>,
+}
@@ -0,0 +1,400 @@
+//! Query planner chooses between collection scan and index scan.
+//!
+//! Planner looks at equality and range conditions on top level fields (including fields
+//! inside top level `$and`) and picks index whose first key matches one of them.  Documents
+//! returned by index scan are always checked against the full query so index only narrows
+//! down the documents that have to be examined.
+//!
+
+use super::{
+  extended_json::is_extended,
+  index::{compare_values, type_rank, Index, IndexKey},
+  query::{ComparisonOp, CompiledQuery, Condition},
+};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+
+/// How much information `Collection::explain` returns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExplainVerbosity {
+  /// Only the chosen plan.
+  QueryPlanner,
+  /// Chosen plan and statistics from executing it.
+  ExecutionStats,
+}
+
+/// Options for `Collection::explain`.
+#[derive(Clone, Debug)]
+pub struct ExplainOptions {
+  pub verbosity: ExplainVerbosity,
+}
+
+impl Default for ExplainOptions {
+  fn default() -> Self {
+    ExplainOptions {
+      verbosity: ExplainVerbosity::ExecutionStats,
+    }
+  }
+}
+
+/// Range of values on single field.
+///
+/// Both bounds always have the same type, range never crosses values of different types
+/// the same way comparison operators do not match values of different types.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexBounds {
+  pub lower: Bound<Value>,
+  pub upper: Bound<Value>,
+}
+
+impl IndexBounds {
+  fn equal(value: &Value) -> IndexBounds {
+    IndexBounds {
+      lower: Bound::Included(value.clone()),
+      upper: Bound::Included(value.clone()),
+    }
+  }
+
+  fn from_op(op: ComparisonOp, value: &Value) -> Option<IndexBounds> {
+    if !(value.is_number() || value.is_string() || is_extended(value)) {
+      return None;
+    }
+
+    let bounds = match op {
+      ComparisonOp::Eq => IndexBounds::equal(value),
+      ComparisonOp::Gt => IndexBounds {
+        lower: Bound::Excluded(value.clone()),
+        upper: Bound::Unbounded,
+      },
+      ComparisonOp::Gte => IndexBounds {
+        lower: Bound::Included(value.clone()),
+        upper: Bound::Unbounded,
+      },
+      ComparisonOp::Lt => IndexBounds {
+        lower: Bound::Unbounded,
+        upper: Bound::Excluded(value.clone()),
+      },
+      ComparisonOp::Lte => IndexBounds {
+        lower: Bound::Unbounded,
+        upper: Bound::Included(value.clone()),
+      },
+      _ => return None,
+    };
+    Some(bounds)
+  }
+
+  fn value_type(&self) -> u8 {
+    match (&self.lower, &self.upper) {
+      (Bound::Included(v), _) | (Bound::Excluded(v), _) => type_rank(v),
+      (_, Bound::Included(v)) | (_, Bound::Excluded(v)) => type_rank(v),
+      _ => 0,
+    }
+  }
+
+  fn is_equality(&self) -> bool {
+    matches!((&self.lower, &self.upper), (Bound::Included(l), Bound::Included(u)) if l == u)
+  }
+
+  /// Narrows the bounds with another condition on the same field.
+  fn intersect(self, other: IndexBounds) -> IndexBounds {
+    if self.value_type() != other.value_type() {
+      return self;
+    }
+
+    let lower = match (&self.lower, &other.lower) {
+      (Bound::Unbounded, _) => other.lower.clone(),
+      (_, Bound::Unbounded) => self.lower.clone(),
+      (a, b) => {
+        let (av, bv) = (bound_value(a), bound_value(b));
+        match compare_values(av, bv) {
+          Ordering::Less => b.clone(),
+          Ordering::Greater => a.clone(),
+          Ordering::Equal if matches!(b, Bound::Excluded(_)) => b.clone(),
+          Ordering::Equal => a.clone(),
+        }
+      }
+    };
+
+    let upper = match (&self.upper, &other.upper) {
+      (Bound::Unbounded, _) => other.upper.clone(),
+      (_, Bound::Unbounded) => self.upper.clone(),
+      (a, b) => {
+        let (av, bv) = (bound_value(a), bound_value(b));
+        match compare_values(av, bv) {
+          Ordering::Less => a.clone(),
+          Ordering::Greater => b.clone(),
+          Ordering::Equal if matches!(b, Bound::Excluded(_)) => b.clone(),
+          Ordering::Equal => a.clone(),
+        }
+      }
+    };
+
+    IndexBounds { lower, upper }
+  }
+
+  fn above_lower(&self, value: &Value) -> bool {
+    match &self.lower {
+      Bound::Included(l) => compare_values(value, l) != Ordering::Less,
+      Bound::Excluded(l) => compare_values(value, l) == Ordering::Greater,
+      Bound::Unbounded => true,
+    }
+  }
+
+  fn below_upper(&self, value: &Value) -> bool {
+    match &self.upper {
+      Bound::Included(u) => compare_values(value, u) != Ordering::Greater,
+      Bound::Excluded(u) => compare_values(value, u) == Ordering::Less,
+      Bound::Unbounded => true,
+    }
+  }
+
+  pub fn to_json(&self) -> Value {
+    let bound_json = |b: &Bound<Value>| match b {
+      Bound::Included(v) | Bound::Excluded(v) => v.clone(),
+      Bound::Unbounded => Value::Null,
+    };
+    json!({
+      "lower": bound_json(&self.lower),
+      "lowerInclusive": matches!(self.lower, Bound::Included(_)),
+      "upper": bound_json(&self.upper),
+      "upperInclusive": matches!(self.upper, Bound::Included(_)),
+    })
+  }
+}
+
+fn bound_value(bound: &Bound<Value>) -> &Value {
+  match bound {
+    Bound::Included(v) | Bound::Excluded(v) => v,
+    Bound::Unbounded => &Value::Null,
+  }
+}
+
+/// Plan used to find documents that match query.
+///
+/// Plan lives only while query runs, so size of `IndexScan` does not matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Plan {
+  /// Examine every document in collection.
+  CollectionScan,
+  /// Examine only documents with index keys within bounds on the first key of the index.
+  IndexScan {
+    index: String,
+    field: String,
+    bounds: IndexBounds,
+  },
+}
+
+impl Plan {
+  /// Describes the plan using MongoDB like stage names.
+  pub fn to_json(&self) -> Value {
+    match self {
+      Plan::CollectionScan => json!({ "stage": "COLLSCAN" }),
+      Plan::IndexScan {
+        index,
+        field,
+        bounds,
+      } => {
+        let mut index_bounds = Map::new();
+        index_bounds.insert(field.clone(), bounds.to_json());
+        json!({
+          "stage": "FETCH",
+          "inputStage": {
+            "stage": "IXSCAN",
+            "indexName": index,
+            "indexBounds": index_bounds,
+          }
+        })
+      }
+    }
+  }
+}
+
+/// Counters collected while executing plan.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExecutionStats {
+  pub keys_examined: u64,
+  pub docs_examined: u64,
+  pub returned: u64,
+}
+
+/// Collects bounds for fields that must match for the whole query to match.
+fn field_bounds(condition: &Condition, bounds: &mut BTreeMap<String, IndexBounds>) {
+  let (path, field_bounds) = match condition {
+    Condition::And(conditions) => {
+      for c in conditions {
+        field_bounds(c, bounds);
+      }
+      return;
+    }
+    Condition::Equals { field, value }
+      if value.is_number() || value.is_string() || value.is_boolean() =>
+    {
+      (&field.path, IndexBounds::equal(value))
+    }
+    Condition::Compare { field, op, value } => match IndexBounds::from_op(*op, value) {
+      Some(b) => (&field.path, b),
+      None => return,
+    },
+    _ => return,
+  };
+
+  let merged = match bounds.remove(path) {
+    Some(existing) => existing.intersect(field_bounds),
+    None => field_bounds,
+  };
+  bounds.insert(path.clone(), merged);
+}
+
+/// Chooses plan for query.
+///
+/// Equality conditions are preferred over ranges.  Partial indexes are never used since
+/// they may not contain all matching documents.
+pub(crate) fn plan(query: &CompiledQuery, indexes: &[Index]) -> Plan {
+  let mut bounds = BTreeMap::new();
+  field_bounds(&query.root, &mut bounds);
+
+  let mut best: Option<(u8, &Index, &String, &IndexBounds)> = None;
+  for index in indexes {
+    if index.definition.partial_filter_expression.is_some() {
+      continue;
+    }
+    let field = &index.definition.keys[0];
+    if let Some(field_bounds) = bounds.get(field) {
+      let score = if field_bounds.is_equality() { 2 } else { 1 };
+      if best.map(|(s, _, _, _)| score > s).unwrap_or(true) {
+        best = Some((score, index, field, field_bounds));
+      }
+    }
+  }
+
+  match best {
+    Some((_, index, field, field_bounds)) => Plan::IndexScan {
+      index: index.definition.name.clone(),
+      field: field.clone(),
+      bounds: field_bounds.clone(),
+    },
+    None => Plan::CollectionScan,
+  }
+}
+
+impl Index {
+  /// Returns sequence numbers of documents which first key is within bounds.
+  pub(crate) fn scan(&self, bounds: &IndexBounds, stats: &mut ExecutionStats) -> BTreeSet<u64> {
+    let value_type = bounds.value_type();
+    let start = match &bounds.lower {
+      Bound::Included(v) | Bound::Excluded(v) => Bound::Included(IndexKey(vec![v.clone()])),
+      Bound::Unbounded => Bound::Unbounded,
+    };
+
+    let mut seqs = BTreeSet::new();
+    for (key, key_seqs) in self.entries.range((start, Bound::Unbounded)) {
+      stats.keys_examined += 1;
+      let value = &key.0[0];
+      let key_type = type_rank(value);
+      if key_type < value_type || !bounds.above_lower(value) {
+        continue;
+      }
+      if key_type > value_type || !bounds.below_upper(value) {
+        break;
+      }
+      seqs.extend(key_seqs.iter());
+    }
+    seqs
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::index::{IndexDefinition, IndexOptions};
+
+  fn index(keys: &[&str], options: IndexOptions) -> Index {
+    Index::new(IndexDefinition::new(keys, options).unwrap()).unwrap()
+  }
+
+  fn compile(query: Value) -> CompiledQuery {
+    CompiledQuery::new(&query).unwrap()
+  }
+
+  #[test]
+  fn test_no_index_collection_scan() {
+    let indexes = vec![Index::id_index()];
+    assert_eq!(
+      plan(&compile(json!({ "age": { "$gt": 20 } })), &indexes),
+      Plan::CollectionScan
+    );
+  }
+
+  #[test]
+  fn test_equality_preferred_over_range() {
+    let indexes = vec![
+      index(&["age"], IndexOptions::default()),
+      index(&["name"], IndexOptions::default()),
+    ];
+    match plan(
+      &compile(json!({ "age": { "$gt": 20 }, "name": "Bob" })),
+      &indexes,
+    ) {
+      Plan::IndexScan { index, .. } => assert_eq!(index, "name_1"),
+      p => panic!("unexpected plan {:?}", p),
+    }
+  }
+
+  #[test]
+  fn test_and_bounds_are_intersected() {
+    let indexes = vec![index(&["age"], IndexOptions::default())];
+    let query = json!({ "$and": [{ "age": { "$gte": 20 } }, { "age": { "$lt": 30 } }] });
+    match plan(&compile(query), &indexes) {
+      Plan::IndexScan { bounds, .. } => {
+        assert_eq!(bounds.lower, Bound::Included(json!(20)));
+        assert_eq!(bounds.upper, Bound::Excluded(json!(30)));
+      }
+      p => panic!("unexpected plan {:?}", p),
+    }
+  }
+
+  #[test]
+  fn test_or_and_partial_index_not_used() {
+    let indexes = vec![index(
+      &["age"],
+      IndexOptions {
+        partial_filter_expression: Some(json!({ "active": true })),
+        ..Default::default()
+      },
+    )];
+    assert_eq!(
+      plan(&compile(json!({ "age": 20 })), &indexes),
+      Plan::CollectionScan
+    );
+
+    let indexes = vec![index(&["age"], IndexOptions::default())];
+    assert_eq!(
+      plan(
+        &compile(json!({ "$or": [{ "age": 20 }, { "age": 30 }] })),
+        &indexes
+      ),
+      Plan::CollectionScan
+    );
+  }
+
+  #[test]
+  fn test_scan_stays_within_type() {
+    let mut idx = index(&["age"], IndexOptions::default());
+    idx.insert(0, &[IndexKey(vec![json!(null)])]);
+    idx.insert(1, &[IndexKey(vec![json!(10)])]);
+    idx.insert(2, &[IndexKey(vec![json!(20)])]);
+    idx.insert(3, &[IndexKey(vec![json!("30")])]);
+
+    let mut stats = ExecutionStats::default();
+    let bounds = IndexBounds::from_op(ComparisonOp::Lt, &json!(15)).unwrap();
+    let seqs: Vec<u64> = idx.scan(&bounds, &mut stats).into_iter().collect();
+    assert_eq!(seqs, vec![1]);
+
+    let bounds = IndexBounds::from_op(ComparisonOp::Gt, &json!(10)).unwrap();
+    let seqs: Vec<u64> = idx.scan(&bounds, &mut stats).into_iter().collect();
+    assert_eq!(seqs, vec![2]);
+  }
+}
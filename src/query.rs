@@ -0,0 +1,561 @@
+//! Queries compiled into reusable predicates.
+//!
+//! `CompiledQuery` validates query once (operators, paths, regular expressions) so that
+//! invalid query fails before any document is examined, and then can be matched against
+//! any number of documents.
+//!
+
+use super::{
+  errors::Error,
+  extended_json::is_extended,
+  index::{compare_values, type_rank},
+  utils::*,
+};
+use paste::paste;
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+pub const REGEX_OPTIONS: &str = "$options";
+
+enum_to_str! {
+  /// Operators that can be used on document fields.
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  pub enum ComparisonOp {
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Ne,
+    In,
+    Nin,
+    All,
+    Regex,
+  }
+}
+
+impl ComparisonOp {
+  const ALL: [ComparisonOp; 10] = [
+    ComparisonOp::Eq,
+    ComparisonOp::Gt,
+    ComparisonOp::Gte,
+    ComparisonOp::Lt,
+    ComparisonOp::Lte,
+    ComparisonOp::Ne,
+    ComparisonOp::In,
+    ComparisonOp::Nin,
+    ComparisonOp::All,
+    ComparisonOp::Regex,
+  ];
+
+  /// Resolves operator such as `$gte` to enum value.
+  pub fn from_operator(op: &str) -> Option<ComparisonOp> {
+    ComparisonOp::ALL
+      .iter()
+      .find(|o| o.as_operator() == op)
+      .copied()
+  }
+}
+
+/// Dot separated path to document field split into its parts.
+#[derive(Clone, Debug)]
+pub struct FieldPath {
+  pub path: String,
+  parts: Vec<String>,
+}
+
+impl FieldPath {
+  pub fn new(path: &str) -> FieldPath {
+    FieldPath {
+      path: path.to_string(),
+      parts: path.split('.').map(|p| p.to_string()).collect(),
+    }
+  }
+
+  /// Returns value at path or null if field is missing.
+  pub fn value<'d>(&self, document: &'d Value) -> &'d Value {
+    let mut current_value = document;
+    for part in self.parts.iter() {
+      current_value = &current_value[part.as_str()];
+    }
+    current_value
+  }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Condition {
+  And(Vec<Condition>),
+  Or(Vec<Condition>),
+  Equals {
+    field: FieldPath,
+    value: Value,
+  },
+  Compare {
+    field: FieldPath,
+    op: ComparisonOp,
+    value: Value,
+  },
+  Regex {
+    field: FieldPath,
+    regex: Regex,
+  },
+}
+
+/// Query validated and parsed into predicate that can be applied to many documents.
+///
+/// ```
+/// use memquery::{query, query::CompiledQuery};
+/// use serde_json::json;
+///
+/// let adults = CompiledQuery::new(&query!({ "age": { "$gte": 21 } })).unwrap();
+/// assert!(adults.matches(&json!({ "name": "Tom", "age": 25 })).unwrap());
+/// assert!(!adults.matches(&json!({ "name": "Bob", "age": 20 })).unwrap());
+///
+/// assert!(CompiledQuery::new(&query!({ "age": { "$gtee": 21 } })).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct CompiledQuery {
+  source: Value,
+  pub(crate) root: Condition,
+}
+
+impl CompiledQuery {
+  /// Validates and compiles query.
+  pub fn new(query: &Value) -> Result<CompiledQuery, Error> {
+    Ok(CompiledQuery {
+      source: query.clone(),
+      root: Condition::And(compile_query(query)?),
+    })
+  }
+
+  /// Query that was compiled.
+  pub fn as_value(&self) -> &Value {
+    &self.source
+  }
+
+  /// Returns true if document matches the query.
+  pub fn matches(&self, document: &Value) -> Result<bool, Error> {
+    matches_condition(&self.root, document)
+  }
+}
+
+fn compile_query(query: &Value) -> Result<Vec<Condition>, Error> {
+  let query = match query.as_object() {
+    Some(q) => q,
+    None => return Err(Error::MQError(String::from("Query must be a JSON object."))),
+  };
+
+  let mut conditions = Vec::new();
+  for (key, value) in query {
+    if is_logical_op(key) {
+      let op_list = match value.as_array() {
+        Some(l) => l,
+        None => return Err(Error::MQError(String::from("Logical operation"))),
+      };
+      let mut sub_conditions = Vec::new();
+      for op_query in op_list {
+        sub_conditions.push(Condition::And(compile_query(op_query)?));
+      }
+      conditions.push(match key.as_str() {
+        AND => Condition::And(sub_conditions),
+        _ => Condition::Or(sub_conditions),
+      });
+    } else if is_op(key) {
+      return Err(Error::MQInvalidOp(format!("{} is not supported.", key)));
+    } else if has_ops(key) {
+      return Err(Error::MQOpNotAllowedInMultipartKey(key.clone()));
+    } else {
+      compile_field(FieldPath::new(key), value, &mut conditions)?;
+    }
+  }
+
+  Ok(conditions)
+}
+
+fn compile_field(
+  field: FieldPath,
+  value: &Value,
+  conditions: &mut Vec<Condition>,
+) -> Result<(), Error> {
+  let operators = match value.as_object() {
+    Some(o) if o.keys().any(|k| is_op(k)) && !is_extended(value) => o,
+    _ => {
+      conditions.push(Condition::Equals {
+        field,
+        value: value.clone(),
+      });
+      return Ok(());
+    }
+  };
+
+  if !operators.keys().all(|k| is_op(k)) {
+    return Err(Error::MQInvalidOp(format!(
+      "Cannot mix operators with keys in {}.",
+      field.path
+    )));
+  }
+
+  let regex_options = operators.get(REGEX_OPTIONS);
+  if regex_options.is_some() && !operators.contains_key(ComparisonOp::Regex.as_operator()) {
+    return Err(Error::MQInvalidOp(format!(
+      "{} requires {}.",
+      REGEX_OPTIONS,
+      ComparisonOp::Regex.as_operator()
+    )));
+  }
+
+  for (key, operand) in operators {
+    if key == REGEX_OPTIONS {
+      continue;
+    }
+
+    let op = match ComparisonOp::from_operator(key) {
+      Some(op) => op,
+      None => return Err(Error::MQInvalidOp(format!("{} is not supported.", key))),
+    };
+
+    match op {
+      ComparisonOp::In | ComparisonOp::Nin | ComparisonOp::All => {
+        if !operand.is_array() {
+          return Err(Error::MQInvalidValue(format!(
+            "{} expects array.",
+            op.as_operator()
+          )));
+        }
+      }
+      ComparisonOp::Regex => {
+        conditions.push(Condition::Regex {
+          field: field.clone(),
+          regex: compile_regex(operand, regex_options)?,
+        });
+        continue;
+      }
+      _ => {
+        if operand.is_object() && !is_extended(operand) {
+          return Err(Error::MQInvalidValue(format!(
+            "{} expects value not array or object.",
+            op.as_operator()
+          )));
+        }
+      }
+    }
+
+    conditions.push(Condition::Compare {
+      field: field.clone(),
+      op,
+      value: operand.clone(),
+    });
+  }
+
+  Ok(())
+}
+
+fn compile_regex(pattern: &Value, options: Option<&Value>) -> Result<Regex, Error> {
+  let pattern = match pattern.as_str() {
+    Some(p) => p,
+    None => {
+      return Err(Error::MQInvalidValue(String::from(
+        "$regex expects string pattern.",
+      )))
+    }
+  };
+
+  let options = match options {
+    Some(Value::String(o)) => o.as_str(),
+    Some(_) => {
+      return Err(Error::MQInvalidValue(String::from(
+        "$options expects string.",
+      )))
+    }
+    None => "",
+  };
+
+  let mut builder = RegexBuilder::new(pattern);
+  for option in options.chars() {
+    match option {
+      'i' => builder.case_insensitive(true),
+      'm' => builder.multi_line(true),
+      's' => builder.dot_matches_new_line(true),
+      'x' => builder.ignore_whitespace(true),
+      _ => {
+        return Err(Error::MQInvalidValue(format!(
+          "{} is invalid $regex option.",
+          option
+        )))
+      }
+    };
+  }
+
+  builder
+    .build()
+    .map_err(|e| Error::MQInvalidValue(format!("Invalid $regex: {}", e)))
+}
+
+fn matches_condition(condition: &Condition, document: &Value) -> Result<bool, Error> {
+  match condition {
+    Condition::And(conditions) => {
+      for c in conditions {
+        if !matches_condition(c, document)? {
+          return Ok(false);
+        }
+      }
+      Ok(true)
+    }
+    Condition::Or(conditions) => {
+      for c in conditions {
+        if matches_condition(c, document)? {
+          return Ok(true);
+        }
+      }
+      Ok(false)
+    }
+    Condition::Equals { field, value } => Ok(is_equal(value, field.value(document))),
+    Condition::Compare { field, op, value } => {
+      let doc_value = field.value(document);
+      match op {
+        ComparisonOp::In => Ok(is_in(doc_value, value)),
+        ComparisonOp::Nin => Ok(!is_in(doc_value, value)),
+        ComparisonOp::All => Ok(is_all(doc_value, value)),
+        // path is known only here, comparisons of array items report the array
+        _ => perform_value_compares(*op, value, doc_value).map_err(|e| match e {
+          Error::MQInvalidType { message, .. } => Error::MQInvalidType {
+            path: field.path.clone(),
+            message,
+          },
+          e => e,
+        }),
+      }
+    }
+    Condition::Regex { field, regex } => Ok(match field.value(document) {
+      Value::String(s) => regex.is_match(s),
+      Value::Array(items) => items
+        .iter()
+        .any(|i| i.as_str().map(|s| regex.is_match(s)).unwrap_or(false)),
+      _ => false,
+    }),
+  }
+}
+
+/// Equality of query value and document value.  Extended JSON values are equal when
+/// they have the same type and value, so `{"$numberLong": "5"}` equals `5`.
+fn is_equal(value: &Value, doc_value: &Value) -> bool {
+  if is_extended(value) || is_extended(doc_value) {
+    compare_values(value, doc_value) == Ordering::Equal
+  } else {
+    value == doc_value
+  }
+}
+
+fn is_in(doc_value: &Value, values: &Value) -> bool {
+  match values.as_array() {
+    Some(v) => v.iter().any(|v| contains_value(doc_value, v)),
+    None => false,
+  }
+}
+
+/// True if every value is present in document array (or equals document value).
+/// Empty list never matches.
+fn is_all(doc_value: &Value, values: &Value) -> bool {
+  match values.as_array() {
+    Some(v) if !v.is_empty() => v.iter().all(|v| contains_value(doc_value, v)),
+    _ => false,
+  }
+}
+
+fn contains_value(doc_value: &Value, value: &Value) -> bool {
+  let is_equal = |a: &Value, b: &Value| compare_values(a, b) == Ordering::Equal;
+  is_equal(doc_value, value)
+    || doc_value
+      .as_array()
+      .map(|items| items.iter().any(|i| is_equal(i, value)))
+      .unwrap_or(false)
+}
+
+fn perform_value_compares(
+  op: ComparisonOp,
+  compare_to_value: &Value,
+  doc_value: &Value,
+) -> Result<bool, Error> {
+  match (doc_value, compare_to_value) {
+    (Value::Number(_), Value::Number(_)) => compare(
+      op,
+      compare_values(doc_value, compare_to_value),
+      Ordering::Equal,
+    ),
+    (Value::String(d), Value::String(c)) => compare(op, d, c),
+    (Value::Array(d), Value::Array(c)) => perform_array_to_array_compare(op, d, c),
+    (Value::Array(d), Value::Number(_)) | (Value::Array(d), Value::String(_)) => {
+      perform_array_to_value_compare(op, d, compare_to_value)
+    }
+    (Value::Array(d), Value::Object(_)) if is_extended(compare_to_value) => {
+      perform_array_to_value_compare(op, d, compare_to_value)
+    }
+    _ if (is_extended(doc_value) || is_extended(compare_to_value))
+      && type_rank(doc_value) == type_rank(compare_to_value) =>
+    {
+      compare(
+        op,
+        compare_values(doc_value, compare_to_value),
+        Ordering::Equal,
+      )
+    }
+    _ => Err(Error::MQInvalidType {
+      path: String::new(),
+      message: format!(
+        "{} cannot compare {} with {}",
+        op.as_operator(),
+        doc_value,
+        compare_to_value
+      ),
+    }),
+  }
+}
+
+fn compare<T: PartialOrd>(op: ComparisonOp, d: T, c: T) -> Result<bool, Error> {
+  Ok(match op {
+    ComparisonOp::Gt => d > c,
+    ComparisonOp::Gte => d >= c,
+    ComparisonOp::Lt => d < c,
+    ComparisonOp::Lte => d <= c,
+    ComparisonOp::Ne => d != c,
+    ComparisonOp::Eq => d == c,
+    _ => {
+      return Err(Error::MQInvalidOp(format!(
+        "{} not supported for compare.",
+        op.as_operator()
+      )))
+    }
+  })
+}
+
+fn perform_array_to_array_compare(
+  op: ComparisonOp,
+  document_value: &[Value],
+  compare_value: &[Value],
+) -> Result<bool, Error> {
+  if document_value == compare_value {
+    return Ok(true);
+  }
+
+  let compare_value = Value::from(compare_value);
+  let mut matches: Vec<bool> = Vec::new();
+  for elem in document_value {
+    let is_match = match op {
+      ComparisonOp::Ne => elem != &compare_value,
+      ComparisonOp::Eq => elem == &compare_value,
+      _ => {
+        return Err(Error::MQInvalidOp(format!(
+          "{} is not valid for array comparison.",
+          op.as_operator()
+        )));
+      }
+    };
+    matches.push(is_match);
+  }
+
+  Ok(any(matches))
+}
+
+fn perform_array_to_value_compare(
+  op: ComparisonOp,
+  document_value: &[Value],
+  compare_value: &Value,
+) -> Result<bool, Error> {
+  let mut matches: Vec<bool> = Vec::new();
+  for elem in document_value {
+    let mut is_match = false;
+    if !elem.is_array() {
+      // do not check nested arrays
+      is_match = perform_value_compares(op, compare_value, elem)?
+    }
+    matches.push(is_match);
+  }
+
+  Ok(any(matches))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_operators_resolved() {
+    assert_eq!(ComparisonOp::from_operator("$gte"), Some(ComparisonOp::Gte));
+    assert_eq!(ComparisonOp::from_operator("$nin"), Some(ComparisonOp::Nin));
+    assert_eq!(ComparisonOp::from_operator("$gtee"), None);
+  }
+
+  #[test]
+  fn test_invalid_queries_rejected() {
+    assert!(CompiledQuery::new(&json!(1)).is_err());
+    assert!(CompiledQuery::new(&json!({ "age": { "$gtee": 1 } })).is_err());
+    assert!(CompiledQuery::new(&json!({ "$nor": [] })).is_err());
+    assert!(CompiledQuery::new(&json!({ "$and": {} })).is_err());
+    assert!(CompiledQuery::new(&json!({ "age.$gt": 1 })).is_err());
+    assert!(CompiledQuery::new(&json!({ "age": { "$gt": 1, "b": 2 } })).is_err());
+    assert!(CompiledQuery::new(&json!({ "age": { "$gt": {} } })).is_err());
+    assert!(CompiledQuery::new(&json!({ "age": { "$in": 1 } })).is_err());
+    assert!(CompiledQuery::new(&json!({ "name": { "$regex": "(" } })).is_err());
+    assert!(CompiledQuery::new(&json!({ "name": { "$options": "i" } })).is_err());
+  }
+
+  #[test]
+  fn test_multiple_operators_on_field() -> Result<(), Error> {
+    let query = CompiledQuery::new(&json!({ "age": { "$gte": 20, "$lt": 30 } }))?;
+    assert!(query.matches(&json!({ "age": 20 }))?);
+    assert!(!query.matches(&json!({ "age": 30 }))?);
+    Ok(())
+  }
+
+  #[test]
+  fn test_in_and_nin() -> Result<(), Error> {
+    let query = CompiledQuery::new(&json!({ "tags": { "$in": ["A", "Z"] } }))?;
+    assert!(query.matches(&json!({ "tags": ["B", "A"] }))?);
+    assert!(query.matches(&json!({ "tags": "Z" }))?);
+    assert!(!query.matches(&json!({ "tags": ["B"] }))?);
+
+    let query = CompiledQuery::new(&json!({ "qty": { "$nin": [1, 2] } }))?;
+    assert!(!query.matches(&json!({ "qty": 1.0 }))?);
+    assert!(query.matches(&json!({ "qty": 3 }))?);
+    Ok(())
+  }
+
+  #[test]
+  fn test_all() -> Result<(), Error> {
+    let query = CompiledQuery::new(&json!({ "tags": { "$all": ["A", "B"] } }))?;
+    assert!(query.matches(&json!({ "tags": ["B", "C", "A"] }))?);
+    assert!(!query.matches(&json!({ "tags": ["A"] }))?);
+    assert!(
+      !CompiledQuery::new(&json!({ "tags": { "$all": [] } }))?.matches(&json!({ "tags": [] }))?
+    );
+    assert!(CompiledQuery::new(&json!({ "tags": { "$all": "A" } })).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_regex() -> Result<(), Error> {
+    let query = CompiledQuery::new(&json!({ "name": { "$regex": "^b", "$options": "i" } }))?;
+    assert!(query.matches(&json!({ "name": "Bob" }))?);
+    assert!(!query.matches(&json!({ "name": "Rob" }))?);
+    assert!(!query.matches(&json!({ "age": 1 }))?);
+    Ok(())
+  }
+
+  #[test]
+  fn test_compiled_query_is_reusable() -> Result<(), Error> {
+    let query = CompiledQuery::new(&json!({ "item.name": "ab" }))?;
+    let docs = [
+      json!({ "item": { "name": "ab" } }),
+      json!({ "item": { "name": "cd" } }),
+      json!({ "item": "ab" }),
+    ];
+    let mut matched = Vec::new();
+    for doc in docs.iter() {
+      matched.push(query.matches(doc)?);
+    }
+    assert_eq!(matched, vec![true, false, false]);
+    Ok(())
+  }
+}
@@ -0,0 +1,182 @@
+//! Versioned JSON snapshot of a database used by `MemDb::save_to` and `MemDb::load_from`.
+//!
+//! Snapshot looks like this:
+//!
+//! ```json
+//! {
+//!   "format": "memquery-snapshot",
+//!   "version": 1,
+//!   "collections": {
+//!     "Users": {
+//!       "indexes": [{ "name": "email_1", "keys": ["email"], "unique": true, ... }],
+//!       "documents": [{ "_id": "...", "email": "rob@test.com" }]
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! The `_id_` index is always present so it is not stored.  Collection created with
+//! options, such as capped collection, also has `"options"` field.
+//!
+
+use super::{errors::Error, index::IndexDefinition, options::CollectionOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Value of `format` field identifying snapshot files.
+pub const SNAPSHOT_FORMAT: &str = "memquery-snapshot";
+
+/// Version of snapshot format written by this library.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Contents of all collections in database.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+  pub format: String,
+  pub version: u32,
+  /// Log sequence number up to which collection list is included, when written by compaction.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub lsn: Option<u64>,
+  pub collections: BTreeMap<String, CollectionSnapshot>,
+}
+
+/// Options, index definitions and documents of single collection.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectionSnapshot {
+  #[serde(default, skip_serializing_if = "CollectionOptions::is_default")]
+  pub options: CollectionOptions,
+  pub indexes: Vec<IndexDefinition>,
+  pub documents: Vec<Value>,
+  /// Log sequence number up to which collection is included, when written by compaction.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub lsn: Option<u64>,
+}
+
+impl Snapshot {
+  pub fn new() -> Snapshot {
+    Snapshot {
+      format: SNAPSHOT_FORMAT.to_string(),
+      version: SNAPSHOT_VERSION,
+      lsn: None,
+      collections: BTreeMap::new(),
+    }
+  }
+
+  /// Parses snapshot and checks that its format and version are supported.
+  pub fn from_slice(data: &[u8]) -> Result<Snapshot, Error> {
+    let snapshot: Snapshot = serde_json::from_slice(data)?;
+    if snapshot.format != SNAPSHOT_FORMAT {
+      return Err(Error::MQInvalidValue(format!(
+        "{} is not a MemQuery snapshot.",
+        snapshot.format
+      )));
+    }
+    if snapshot.version > SNAPSHOT_VERSION {
+      return Err(Error::MQInvalidValue(format!(
+        "Snapshot version {} is not supported.",
+        snapshot.version
+      )));
+    }
+    Ok(snapshot)
+  }
+
+  pub fn to_vec(&self) -> Result<Vec<u8>, Error> {
+    Ok(serde_json::to_vec(self)?)
+  }
+}
+
+impl Default for Snapshot {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Temporary file next to `path` that is renamed over it once fully written.
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+  let mut file_name = path
+    .file_name()
+    .map(|f| f.to_os_string())
+    .unwrap_or_else(|| OsString::from("snapshot"));
+  file_name.push(".tmp");
+  path.with_file_name(file_name)
+}
+
+/// Writes file through temporary file and rename so that reader never sees partial snapshot.
+pub(crate) fn write_atomically(path: &Path, data: &[u8]) -> Result<(), Error> {
+  use std::io::Write;
+
+  let temp = temp_path(path);
+  let result = std::fs::File::create(&temp)
+    .and_then(|mut file| {
+      file.write_all(data)?;
+      file.sync_all()
+    })
+    .and_then(|_| std::fs::rename(&temp, path));
+  if result.is_err() {
+    let _ = std::fs::remove_file(&temp);
+  }
+  Ok(result?)
+}
+
+/// Async version of `write_atomically` used by async front-end.
+#[cfg(feature = "tokio")]
+pub(crate) async fn write_atomically_async(path: &Path, data: &[u8]) -> Result<(), Error> {
+  use tokio::io::AsyncWriteExt;
+
+  let temp = temp_path(path);
+  let result = async {
+    let mut file = tokio::fs::File::create(&temp).await?;
+    file.write_all(data).await?;
+    file.sync_all().await?;
+    tokio::fs::rename(&temp, path).await
+  }
+  .await;
+  if result.is_err() {
+    let _ = tokio::fs::remove_file(&temp).await;
+  }
+  Ok(result?)
+}
+
+/// Without `tokio` feature file is written on the calling task, so any executor can run it.
+#[cfg(all(feature = "async", not(feature = "tokio")))]
+pub(crate) async fn write_atomically_async(path: &Path, data: &[u8]) -> Result<(), Error> {
+  write_atomically(path, data)
+}
+
+/// Reads whole file for async front-end, with `tokio::fs` if `tokio` feature is enabled.
+#[cfg(feature = "async")]
+pub(crate) async fn read_async(path: &Path) -> Result<Vec<u8>, Error> {
+  #[cfg(feature = "tokio")]
+  let data = tokio::fs::read(path).await?;
+  #[cfg(not(feature = "tokio"))]
+  let data = std::fs::read(path)?;
+  Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rejects_unknown_format_and_version() {
+    assert!(
+      Snapshot::from_slice(br#"{"format": "other", "version": 1, "collections": {}}"#).is_err()
+    );
+    assert!(Snapshot::from_slice(
+      br#"{"format": "memquery-snapshot", "version": 99, "collections": {}}"#
+    )
+    .is_err());
+    assert!(Snapshot::from_slice(&Snapshot::new().to_vec().unwrap()).is_ok());
+  }
+
+  #[test]
+  fn test_temp_path() {
+    assert_eq!(
+      temp_path(Path::new("/data/db.json")),
+      PathBuf::from("/data/db.json.tmp")
+    );
+  }
+}
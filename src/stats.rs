@@ -0,0 +1,196 @@
+//! Statistics of collections and databases.
+//!
+//! `Collection::stats` and `MemDb::stats` report number of documents, approximate memory
+//! taken by documents and indexes and counters of operations since collection was created.
+//! Sizes are estimated by walking `serde_json::Value` trees, they count heap allocations of
+//! strings, arrays and objects but not allocator overhead or memory shared between point-in-time
+//! copies of collection.  Field names follow MongoDB `collStats` and `dbStats` commands when
+//! stats are serialized.
+//!
+
+use super::index::Index;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Approximate number of bytes that value takes in memory.
+///
+/// ```
+/// use memquery::stats::value_size;
+/// use serde_json::json;
+///
+/// assert!(value_size(&json!({ "name": "Tom" })) > value_size(&json!({})));
+/// ```
+pub fn value_size(value: &Value) -> u64 {
+  let heap = match value {
+    Value::String(s) => s.capacity() as u64,
+    Value::Array(items) => items.iter().map(value_size).sum(),
+    Value::Object(fields) => fields
+      .iter()
+      .map(|(key, value)| (size_of::<String>() + key.capacity()) as u64 + value_size(value))
+      .sum(),
+    _ => 0,
+  };
+  size_of::<Value>() as u64 + heap
+}
+
+/// Approximate number of bytes that entries of index take in memory.
+pub(crate) fn index_size(index: &Index) -> u64 {
+  index
+    .entries
+    .iter()
+    .map(|(key, seqs)| {
+      let key_size: u64 = key.0.iter().map(value_size).sum();
+      size_of::<Vec<Value>>() as u64 + key_size + (seqs.len() * size_of::<u64>()) as u64
+    })
+    .sum()
+}
+
+/// Number of operations on collection since it was created or loaded.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationStats {
+  /// Documents inserted.
+  pub inserts: u64,
+  /// Calls of `find`, `find_one`, `find_with_options` and `count_documents`.
+  pub queries: u64,
+  /// Documents updated.
+  pub updates: u64,
+  /// Documents deleted by `find_and_delete`, expired and evicted documents are not counted.
+  pub deletes: u64,
+  /// Documents examined by queries, updates, deletes and explain.
+  pub docs_scanned: u64,
+}
+
+impl OperationStats {
+  fn add(&mut self, other: &OperationStats) {
+    self.inserts += other.inserts;
+    self.queries += other.queries;
+    self.updates += other.updates;
+    self.deletes += other.deletes;
+    self.docs_scanned += other.docs_scanned;
+  }
+}
+
+/// Operation counters shared by all handles and copies of collection data, so operations on
+/// views and in transactions that are rolled back are counted too.
+#[derive(Debug, Default)]
+pub(crate) struct OperationCounters {
+  inserts: AtomicU64,
+  queries: AtomicU64,
+  updates: AtomicU64,
+  deletes: AtomicU64,
+  docs_scanned: AtomicU64,
+}
+
+impl OperationCounters {
+  pub fn inserted(&self, count: u64) {
+    self.inserts.fetch_add(count, Ordering::Relaxed);
+  }
+
+  pub fn queried(&self) {
+    self.queries.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn updated(&self, count: u64) {
+    self.updates.fetch_add(count, Ordering::Relaxed);
+  }
+
+  pub fn deleted(&self, count: u64) {
+    self.deletes.fetch_add(count, Ordering::Relaxed);
+  }
+
+  pub fn scanned(&self, count: u64) {
+    self.docs_scanned.fetch_add(count, Ordering::Relaxed);
+  }
+
+  pub fn stats(&self) -> OperationStats {
+    OperationStats {
+      inserts: self.inserts.load(Ordering::Relaxed),
+      queries: self.queries.load(Ordering::Relaxed),
+      updates: self.updates.load(Ordering::Relaxed),
+      deletes: self.deletes.load(Ordering::Relaxed),
+      docs_scanned: self.docs_scanned.load(Ordering::Relaxed),
+    }
+  }
+}
+
+/// Statistics of collection, see `Collection::stats`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionStats {
+  /// Number of documents that have not expired.
+  pub count: u64,
+  /// Approximate memory taken by documents in bytes.
+  pub size: u64,
+  /// `size` divided by `count`, 0 for empty collection.
+  pub avg_obj_size: u64,
+  /// Approximate memory taken by entries of all indexes in bytes.
+  pub total_index_size: u64,
+  /// Approximate memory taken by entries of every index, by index name.
+  pub index_sizes: BTreeMap<String, u64>,
+  pub operations: OperationStats,
+}
+
+impl CollectionStats {
+  pub(crate) fn new(
+    documents: &[&Value],
+    indexes: &[Index],
+    operations: OperationStats,
+  ) -> CollectionStats {
+    let count = documents.len() as u64;
+    let size: u64 = documents.iter().map(|document| value_size(document)).sum();
+    let index_sizes: BTreeMap<String, u64> = indexes
+      .iter()
+      .map(|index| (index.definition.name.clone(), index_size(index)))
+      .collect();
+    CollectionStats {
+      count,
+      size,
+      avg_obj_size: size.checked_div(count).unwrap_or(0),
+      total_index_size: index_sizes.values().sum(),
+      index_sizes,
+      operations,
+    }
+  }
+}
+
+/// Statistics of all collections of database, see `MemDb::stats`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseStats {
+  pub collections: u64,
+  /// Number of documents in all collections.
+  pub objects: u64,
+  /// Approximate memory taken by documents in bytes.
+  pub data_size: u64,
+  /// `data_size` divided by `objects`, 0 for empty database.
+  pub avg_obj_size: u64,
+  /// Number of indexes including `_id` indexes.
+  pub indexes: u64,
+  /// Approximate memory taken by entries of all indexes in bytes.
+  pub index_size: u64,
+  /// Operations on all collections.
+  pub operations: OperationStats,
+}
+
+impl DatabaseStats {
+  pub(crate) fn new<'a, I>(collections: I) -> DatabaseStats
+  where
+    I: IntoIterator<Item = &'a CollectionStats>,
+  {
+    let mut stats = DatabaseStats::default();
+    for collection in collections {
+      stats.collections += 1;
+      stats.objects += collection.count;
+      stats.data_size += collection.size;
+      stats.indexes += collection.index_sizes.len() as u64;
+      stats.index_size += collection.total_index_size;
+      stats.operations.add(&collection.operations);
+    }
+    stats.avg_obj_size = stats.data_size.checked_div(stats.objects).unwrap_or(0);
+    stats
+  }
+}
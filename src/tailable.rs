@@ -0,0 +1,154 @@
+//! Tailable cursors over capped collections.
+//!
+//! Cursor returned by `Collection::tail` first yields documents that matched its filter when
+//! it was opened, then waits for newly inserted matching documents, like `tail -f` over a log.
+//! It is built on change stream of collection (see `change_stream` module), so it reports
+//! `MQChangeStreamLagged` if it falls too far behind and ends when collection is deleted.
+//!
+
+use super::{
+  change_stream::{ChangeEvent, ChangeReceiver, OperationType, Subscription},
+  engine::Documents,
+  errors::Error,
+};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+#[cfg(feature = "async")]
+use {
+  super::change_stream::ChangeStream,
+  futures_core::Stream,
+  std::{
+    pin::Pin,
+    task::{Context, Poll},
+  },
+};
+
+fn inserted(event: ChangeEvent) -> Option<Value> {
+  match event.operation_type {
+    OperationType::Insert => event.full_document,
+    _ => None,
+  }
+}
+
+/// Blocking tailable cursor returned by `blocking::Collection::tail`.
+///
+/// Iterating over cursor waits for new documents and ends when collection is deleted.
+pub struct TailableCursor {
+  buffered: VecDeque<Value>,
+  changes: ChangeReceiver,
+}
+
+impl TailableCursor {
+  pub(crate) fn new(documents: Documents, subscription: Subscription) -> TailableCursor {
+    TailableCursor {
+      buffered: documents.into(),
+      changes: ChangeReceiver::new(subscription),
+    }
+  }
+
+  /// Waits for next document.  Returns `MQChangeStreamClosed` once collection is deleted.
+  pub fn next_document(&mut self) -> Result<Value, Error> {
+    if let Some(document) = self.buffered.pop_front() {
+      return Ok(document);
+    }
+    loop {
+      if let Some(document) = inserted(self.changes.recv()?) {
+        return Ok(document);
+      }
+    }
+  }
+
+  /// Waits at most `timeout` for next document, returns `None` if there was none.
+  pub fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Value>, Error> {
+    if let Some(document) = self.buffered.pop_front() {
+      return Ok(Some(document));
+    }
+    let deadline = Instant::now() + timeout;
+    loop {
+      let left = deadline.saturating_duration_since(Instant::now());
+      match self.changes.recv_timeout(left)? {
+        Some(event) => {
+          if let Some(document) = inserted(event) {
+            return Ok(Some(document));
+          }
+        }
+        None => return Ok(None),
+      }
+    }
+  }
+
+  /// Returns next document if it is already available.
+  pub fn try_next(&mut self) -> Result<Option<Value>, Error> {
+    if let Some(document) = self.buffered.pop_front() {
+      return Ok(Some(document));
+    }
+    while let Some(event) = self.changes.try_recv()? {
+      if let Some(document) = inserted(event) {
+        return Ok(Some(document));
+      }
+    }
+    Ok(None)
+  }
+}
+
+impl Iterator for TailableCursor {
+  type Item = Result<Value, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self.next_document() {
+      Err(Error::MQChangeStreamClosed) => None,
+      result => Some(result),
+    }
+  }
+}
+
+/// Async tailable cursor returned by `r#async::Collection::tail`.
+///
+/// Stream ends when collection is deleted.
+#[cfg(feature = "async")]
+pub struct TailableStream {
+  buffered: VecDeque<Value>,
+  changes: ChangeStream,
+}
+
+#[cfg(feature = "async")]
+impl TailableStream {
+  pub(crate) fn new(documents: Documents, subscription: Subscription) -> TailableStream {
+    TailableStream {
+      buffered: documents.into(),
+      changes: ChangeStream::new(subscription),
+    }
+  }
+
+  /// Waits for next document.  Returns `MQChangeStreamClosed` once collection is deleted.
+  pub async fn next_document(&mut self) -> Result<Value, Error> {
+    std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx))
+      .await
+      .unwrap_or(Err(Error::MQChangeStreamClosed))
+  }
+}
+
+#[cfg(feature = "async")]
+impl Stream for TailableStream {
+  type Item = Result<Value, Error>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+    if let Some(document) = this.buffered.pop_front() {
+      return Poll::Ready(Some(Ok(document)));
+    }
+    loop {
+      match Pin::new(&mut this.changes).poll_next(cx) {
+        Poll::Ready(Some(Ok(event))) => {
+          if let Some(document) = inserted(event) {
+            return Poll::Ready(Some(Ok(document)));
+          }
+        }
+        Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+        Poll::Ready(None) => return Poll::Ready(None),
+        Poll::Pending => return Poll::Pending,
+      }
+    }
+  }
+}
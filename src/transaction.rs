@@ -0,0 +1,194 @@
+//! Transactions that change documents of several collections together.
+//!
+//! Transaction is started with `MemDb::transaction` for collections named by the caller and
+//! takes write lock of each of them, in order of collection names, before the closure runs.
+//! Locks are always taken in the same order, so transactions cannot deadlock with each other,
+//! and other operations never hold more than one collection lock.  Other handles of these
+//! collections wait until transaction ends, so it does not see their changes and they do not
+//! see changes of transaction before commit.  Other collections and the collection list are
+//! not locked.  Closure of transaction must not use handles of its collections itself, they
+//! would wait for locks that its own thread holds.
+//!
+//! Collection data is copied in constant time when it is changed in transaction for the
+//! first time, see `CollectionData`.  If closure returns error every changed collection is
+//! restored from its copy.  In durable database
+//! all changes are written to the log as one entry when transaction commits.  Watchers of
+//! changed collections receive change events only after commit.
+//!
+
+#[cfg(feature = "persistence")]
+use super::wal::{WalChange, WalOperation};
+use super::{
+  engine::{CollectionData, DocumentCollection, Documents, Engine},
+  errors::Error,
+  query::CompiledQuery,
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::RwLockWriteGuard;
+
+/// Locked collection together with its copy from before the transaction changed it.
+struct LockedCollection<'a> {
+  engine: Engine,
+  data: RwLockWriteGuard<'a, CollectionData>,
+  original: Option<CollectionData>,
+}
+
+impl<'a> LockedCollection<'a> {
+  /// Copies collection before its first change.
+  fn for_change(&mut self) -> (&Engine, &mut CollectionData) {
+    if self.original.is_none() {
+      self.original = Some(self.data.clone());
+      self.data.begin_pending();
+    }
+    (&self.engine, &mut self.data)
+  }
+}
+
+/// Collections locked by transaction, see `MemDb::transaction`.
+pub struct Transaction<'a> {
+  collections: BTreeMap<&'a str, LockedCollection<'a>>,
+}
+
+impl<'a> Transaction<'a> {
+  /// Locks collections in order of their names.
+  pub(crate) fn begin(collections: &'a BTreeMap<String, DocumentCollection>) -> Transaction<'a> {
+    Transaction {
+      collections: collections
+        .iter()
+        .map(|(name, collection)| {
+          let locked = LockedCollection {
+            engine: Engine::with_collection(collection.clone()),
+            data: collection.write().unwrap(),
+            original: None,
+          };
+          (name.as_str(), locked)
+        })
+        .collect(),
+    }
+  }
+
+  /// Retrieves collection by name, only collections named when transaction started can be
+  /// used.
+  ///
+  /// ```
+  /// use memquery::{doc, errors::Error, blocking::MemDb, query};
+  ///
+  /// fn play() -> Result<(), Error> {
+  ///   let memdb = MemDb::new();
+  ///   memdb.create_collection("Pending")?;
+  ///   memdb.create_collection("Done")?;
+  ///   memdb.transaction(&["Pending", "Done"], |tx| {
+  ///     let moved = tx.collection("Pending")?.find_and_delete(query!({ "name": "Rob" }))?;
+  ///     for document in moved {
+  ///       tx.collection("Done")?.insert(document)?;
+  ///     }
+  ///     Ok(())
+  ///   })?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn collection(&mut self, name: &str) -> Result<TransactionCollection<'_, 'a>, Error> {
+    match self.collections.get_mut(name) {
+      Some(locked) => Ok(TransactionCollection { locked }),
+      None => Err(Error::MQInvalidValue(format!(
+        "Collection {} is not part of transaction.",
+        name
+      ))),
+    }
+  }
+
+  /// Writes changes to the log, reports them to watchers and releases locks.  If log cannot
+  /// be written changes are rolled back.
+  pub(crate) fn commit(mut self) -> Result<(), Error> {
+    #[cfg(feature = "persistence")]
+    {
+      let mut changes = Vec::new();
+      let mut wal = None;
+      for (name, locked) in self.collections.iter_mut() {
+        if locked.original.is_none() {
+          continue;
+        }
+        if let Some(log) = locked.data.log() {
+          wal = Some(log.wal.clone());
+        }
+        changes.extend(
+          locked
+            .data
+            .take_pending()
+            .into_iter()
+            .map(|operation| WalChange {
+              collection: name.to_string(),
+              operation,
+            }),
+        );
+      }
+      if let Some(wal) = wal {
+        if !changes.is_empty() {
+          if let Err(e) = wal.append("", WalOperation::Transaction { changes }) {
+            self.rollback();
+            return Err(e);
+          }
+        }
+      }
+    }
+    for locked in self.collections.values_mut() {
+      locked.data.publish_pending();
+    }
+    Ok(())
+  }
+
+  /// Restores collections changed by transaction and releases locks.
+  pub(crate) fn rollback(self) {
+    for (_, mut locked) in self.collections {
+      if let Some(original) = locked.original.take() {
+        *locked.data = original;
+      }
+    }
+  }
+}
+
+/// Collection used inside transaction.
+///
+/// Operations see changes made earlier in the same transaction.
+pub struct TransactionCollection<'t, 'a> {
+  locked: &'t mut LockedCollection<'a>,
+}
+
+impl<'t, 'a> TransactionCollection<'t, 'a> {
+  /// Inserts document into collection.
+  pub fn insert(&mut self, document: Value) -> Result<(), Error> {
+    let (engine, data) = self.locked.for_change();
+    engine.insert_document(data, document)
+  }
+
+  /// Finds documents matching query.
+  pub fn find(&self, query: Value) -> Result<Documents, Error> {
+    self
+      .locked
+      .engine
+      .find_documents(&self.locked.data, &CompiledQuery::new(&query)?)
+  }
+
+  /// Finds first document matching query.
+  pub fn find_one(&self, query: Value) -> Result<Option<Value>, Error> {
+    self
+      .locked
+      .engine
+      .find_first_document(&self.locked.data, &CompiledQuery::new(&query)?)
+  }
+
+  /// Updates documents matching query and returns number of updated documents.
+  pub fn find_and_update(&mut self, query: Value, update: Value) -> Result<u64, Error> {
+    let query = CompiledQuery::new(&query)?;
+    let (engine, data) = self.locked.for_change();
+    engine.update_documents(data, &query, &update)
+  }
+
+  /// Deletes documents matching query and returns them.
+  pub fn find_and_delete(&mut self, query: Value) -> Result<Documents, Error> {
+    let query = CompiledQuery::new(&query)?;
+    let (engine, data) = self.locked.for_change();
+    engine.delete_documents(data, &query)
+  }
+}
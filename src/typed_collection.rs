@@ -0,0 +1,84 @@
+//! TypedCollection stores Rust values that serialize to JSON objects.
+//!
+//! Values are converted with serde on the way in and out, so queries still use
+//! the same JSON query documents as `Collection`.  Types that implement `Document`
+//! are converted with `Document::to_document` and `Document::from_document` instead.
+//!
+//! `TypedCollection` at this path is the async one, see `blocking::TypedCollection` for
+//! blocking API.
+//!
+
+use super::{document::Document, errors::Error};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+#[cfg(feature = "async")]
+pub use super::r#async::TypedCollection;
+
+/// Converts values of `T` to documents and back, shared by both typed collections.
+pub(crate) struct Codec<T> {
+  to_document: fn(&T) -> Result<Value, Error>,
+  from_document: fn(Value) -> Result<T, Error>,
+}
+
+impl<T> Clone for Codec<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for Codec<T> {}
+
+fn to_document<T: Serialize>(value: &T) -> Result<Value, Error> {
+  let document = serde_json::to_value(value)?;
+  if !document.is_object() {
+    return Err(Error::MQError(String::from(
+      "Document must be a JSON object.",
+    )));
+  }
+  Ok(document)
+}
+
+fn from_document<T: DeserializeOwned>(document: Value) -> Result<T, Error> {
+  Ok(serde_json::from_value(document)?)
+}
+
+impl<T> Codec<T> {
+  /// Converts with serde.
+  pub fn serde() -> Codec<T>
+  where
+    T: Serialize + DeserializeOwned,
+  {
+    Codec {
+      to_document,
+      from_document,
+    }
+  }
+
+  /// Converts with `Document` implementation.
+  pub fn document() -> Codec<T>
+  where
+    T: Document,
+  {
+    Codec {
+      to_document: T::to_document,
+      from_document: T::from_document,
+    }
+  }
+
+  pub fn encode(&self, value: &T) -> Result<Value, Error> {
+    (self.to_document)(value)
+  }
+
+  pub fn decode_all(&self, documents: Vec<Value>) -> Result<Vec<T>, Error> {
+    documents.into_iter().map(self.from_document).collect()
+  }
+
+  pub fn decode(&self, document: Value) -> Result<T, Error> {
+    (self.from_document)(document)
+  }
+
+  pub fn decode_one(&self, document: Option<Value>) -> Result<Option<T>, Error> {
+    document.map(self.from_document).transpose()
+  }
+}
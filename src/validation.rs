@@ -0,0 +1,742 @@
+//! Validators of collection documents.
+//!
+//! Validator is given in `CollectionOptions::validator` as query document, like validator of
+//! MongoDB collection.  Every document inserted or updated must match the query.  Query may
+//! contain `$jsonSchema` with JSON Schema (draft 2020-12) that document must satisfy.
+//!
+//! Supported JSON Schema keywords are `type`, `enum`, `const`, `properties`,
+//! `patternProperties`, `additionalProperties`, `required`, `minProperties`,
+//! `maxProperties`, `prefixItems`, `items`, `minItems`, `maxItems`, `uniqueItems`,
+//! `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`, `minLength`,
+//! `maxLength`, `pattern`, `allOf`, `anyOf`, `oneOf` and `not`.  MongoDB `bsonType` keyword
+//! is also supported, so `"bsonType": "date"` matches `{"$date": ...}` values.  Annotations
+//! such as `title` or `description` are ignored and references (`$ref`) are not supported.
+//!
+//! Document that does not satisfy validator is reported as `MQValidationFailed` error with
+//! one `ValidationFailure` for every path that failed.
+//!
+
+use super::{
+  errors::Error,
+  extended_json::{is_extended, ExtendedValue},
+  query::CompiledQuery,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::convert::TryFrom;
+use std::fmt;
+
+pub const JSON_SCHEMA: &str = "$jsonSchema";
+
+/// Part of document that does not satisfy validator.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidationFailure {
+  /// Dotted path of field, empty for document itself.
+  pub path: String,
+  /// JSON Schema keyword or `$query` for query clause of validator.
+  pub rule: String,
+  pub message: String,
+}
+
+impl fmt::Display for ValidationFailure {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.path.as_str() {
+      "" => write!(f, "document {}", self.message),
+      path => write!(f, "{} {}", path, self.message),
+    }
+  }
+}
+
+/// Compiled `CollectionOptions::validator`.
+#[derive(Clone, Debug)]
+pub(crate) struct Validator {
+  schema: Option<Schema>,
+  /// Top level clauses of query checked one by one, so that failure names the field.
+  clauses: Vec<(String, CompiledQuery)>,
+}
+
+impl Validator {
+  pub(crate) fn new(validator: &Value) -> Result<Validator, Error> {
+    let fields = match validator.as_object() {
+      Some(fields) => fields,
+      None => {
+        return Err(Error::MQInvalidValue(String::from(
+          "Validator must be a JSON object.",
+        )))
+      }
+    };
+    let mut schema = None;
+    let mut clauses = Vec::new();
+    for (key, value) in fields {
+      if key == JSON_SCHEMA {
+        schema = Some(Schema::compile(value)?);
+      } else {
+        let mut clause = Map::new();
+        clause.insert(key.clone(), value.clone());
+        clauses.push((key.clone(), CompiledQuery::new(&Value::Object(clause))?));
+      }
+    }
+    Ok(Validator { schema, clauses })
+  }
+
+  /// Returns every failure of document, empty if document is valid.
+  pub(crate) fn validate(&self, document: &Value) -> Vec<ValidationFailure> {
+    let mut failures = Vec::new();
+    if let Some(schema) = &self.schema {
+      schema.validate(document, "", &mut failures);
+    }
+    for (key, clause) in &self.clauses {
+      let path = if key.starts_with('$') { "" } else { key };
+      match clause.matches(document) {
+        Ok(true) => {}
+        Ok(false) => failures.push(failure(
+          path,
+          "$query",
+          format!("does not match {}", clause.as_value()),
+        )),
+        Err(e) => failures.push(failure(
+          path,
+          "$query",
+          format!("cannot be compared with {}: {}", clause.as_value(), e),
+        )),
+      }
+    }
+    failures
+  }
+}
+
+fn failure(path: &str, rule: &str, message: String) -> ValidationFailure {
+  ValidationFailure {
+    path: path.to_string(),
+    rule: rule.to_string(),
+    message,
+  }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+  match path {
+    "" => key.to_string(),
+    _ => format!("{}.{}", path, key),
+  }
+}
+
+/// Types of `type` and `bsonType` keywords.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SchemaType {
+  Null,
+  Boolean,
+  Object,
+  Array,
+  Number,
+  Integer,
+  String,
+  // BSON types
+  Int,
+  Long,
+  Double,
+  Decimal,
+  Date,
+  ObjectId,
+  BinData,
+}
+
+impl SchemaType {
+  fn parse(name: &str, bson: bool) -> Option<SchemaType> {
+    let schema_type = match (name, bson) {
+      ("null", _) => SchemaType::Null,
+      ("boolean", false) | ("bool", true) => SchemaType::Boolean,
+      ("object", _) => SchemaType::Object,
+      ("array", _) => SchemaType::Array,
+      ("number", _) => SchemaType::Number,
+      ("integer", false) => SchemaType::Integer,
+      ("string", _) => SchemaType::String,
+      ("int", true) => SchemaType::Int,
+      ("long", true) => SchemaType::Long,
+      ("double", true) => SchemaType::Double,
+      ("decimal", true) => SchemaType::Decimal,
+      ("date", true) => SchemaType::Date,
+      ("objectId", true) => SchemaType::ObjectId,
+      ("binData", true) => SchemaType::BinData,
+      _ => return None,
+    };
+    Some(schema_type)
+  }
+
+  fn matches(&self, value: &Value) -> bool {
+    let extended = ExtendedValue::from_value(value);
+    match self {
+      SchemaType::Null => value.is_null(),
+      SchemaType::Boolean => value.is_boolean(),
+      SchemaType::Object => value.is_object() && !is_extended(value),
+      SchemaType::Array => value.is_array(),
+      SchemaType::Number => {
+        number(value).is_some() || matches!(extended, Some(ExtendedValue::Decimal(_)))
+      }
+      SchemaType::Integer => number(value).is_some_and(|n| n.fract() == 0.0),
+      SchemaType::String => value.is_string(),
+      SchemaType::Int => match extended {
+        Some(ExtendedValue::Int(_)) => true,
+        _ => value.as_i64().is_some_and(|n| i32::try_from(n).is_ok()),
+      },
+      SchemaType::Long => {
+        matches!(
+          extended,
+          Some(ExtendedValue::Long(_)) | Some(ExtendedValue::Int(_))
+        ) || value.as_i64().is_some()
+      }
+      SchemaType::Double => matches!(extended, Some(ExtendedValue::Double(_))) || value.is_f64(),
+      SchemaType::Decimal => matches!(extended, Some(ExtendedValue::Decimal(_))),
+      SchemaType::Date => matches!(extended, Some(ExtendedValue::Date(_))),
+      SchemaType::ObjectId => matches!(extended, Some(ExtendedValue::ObjectId(_))),
+      SchemaType::BinData => matches!(extended, Some(ExtendedValue::Binary { .. })),
+    }
+  }
+}
+
+/// Name of JSON Schema type of value used in messages.
+fn type_name(value: &Value) -> &'static str {
+  match value {
+    Value::Null => "null",
+    Value::Bool(_) => "boolean",
+    Value::Number(n) if n.is_f64() => "number",
+    Value::Number(_) => "integer",
+    Value::String(_) => "string",
+    Value::Array(_) => "array",
+    Value::Object(_) => match ExtendedValue::from_value(value) {
+      Some(ExtendedValue::ObjectId(_)) => "objectId",
+      Some(ExtendedValue::Date(_)) => "date",
+      Some(ExtendedValue::Binary { .. }) => "binData",
+      Some(_) => "number",
+      None => "object",
+    },
+  }
+}
+
+/// Numeric value of JSON number or Extended JSON number wrapper.
+fn number(value: &Value) -> Option<f64> {
+  match ExtendedValue::from_value(value) {
+    Some(ExtendedValue::Int(n)) => Some(n as f64),
+    Some(ExtendedValue::Long(n)) => Some(n as f64),
+    Some(ExtendedValue::Double(n)) => Some(n),
+    Some(_) => None,
+    None => value.as_f64(),
+  }
+}
+
+/// Equality of JSON Schema, numbers are equal when they have the same value.
+fn values_equal(a: &Value, b: &Value) -> bool {
+  match (a, b) {
+    (Value::Number(_), Value::Number(_)) => a.as_f64() == b.as_f64(),
+    (Value::Array(a), Value::Array(b)) => {
+      a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_equal(a, b))
+    }
+    (Value::Object(a), Value::Object(b)) => {
+      a.len() == b.len()
+        && a
+          .iter()
+          .all(|(key, value)| b.get(key).is_some_and(|other| values_equal(value, other)))
+    }
+    _ => a == b,
+  }
+}
+
+#[derive(Clone, Debug)]
+enum Keyword {
+  Type(Vec<SchemaType>, String),
+  Enum(Vec<Value>),
+  Const(Value),
+  Properties(Vec<(String, Schema)>),
+  PatternProperties(Vec<(Regex, Schema)>),
+  AdditionalProperties {
+    schema: Box<Schema>,
+    known: Vec<String>,
+    patterns: Vec<Regex>,
+  },
+  Required(Vec<String>),
+  MinProperties(usize),
+  MaxProperties(usize),
+  PrefixItems(Vec<Schema>),
+  Items {
+    schema: Box<Schema>,
+    skip: usize,
+  },
+  MinItems(usize),
+  MaxItems(usize),
+  UniqueItems,
+  Minimum(f64),
+  Maximum(f64),
+  ExclusiveMinimum(f64),
+  ExclusiveMaximum(f64),
+  MultipleOf(f64),
+  MinLength(usize),
+  MaxLength(usize),
+  Pattern(Regex),
+  AllOf(Vec<Schema>),
+  AnyOf(Vec<Schema>),
+  OneOf(Vec<Schema>),
+  Not(Box<Schema>),
+}
+
+/// Compiled JSON Schema.
+#[derive(Clone, Debug)]
+enum Schema {
+  Bool(bool),
+  Keywords(Vec<(String, Keyword)>),
+}
+
+fn invalid_schema(message: String) -> Error {
+  Error::MQInvalidValue(format!("Invalid JSON Schema: {}", message))
+}
+
+fn count(name: &str, value: &Value) -> Result<usize, Error> {
+  value
+    .as_u64()
+    .map(|n| n as usize)
+    .ok_or_else(|| invalid_schema(format!("{} must be a non-negative integer.", name)))
+}
+
+fn limit(name: &str, value: &Value) -> Result<f64, Error> {
+  value
+    .as_f64()
+    .ok_or_else(|| invalid_schema(format!("{} must be a number.", name)))
+}
+
+fn regex(name: &str, value: &Value) -> Result<Regex, Error> {
+  let pattern = value
+    .as_str()
+    .ok_or_else(|| invalid_schema(format!("{} must be a string.", name)))?;
+  Regex::new(pattern).map_err(|e| invalid_schema(format!("{} {}: {}", name, pattern, e)))
+}
+
+fn object<'v>(name: &str, value: &'v Value) -> Result<&'v Map<String, Value>, Error> {
+  value
+    .as_object()
+    .ok_or_else(|| invalid_schema(format!("{} must be an object.", name)))
+}
+
+fn schemas(name: &str, value: &Value) -> Result<Vec<Schema>, Error> {
+  match value.as_array() {
+    Some(list) if !list.is_empty() => list.iter().map(Schema::compile).collect(),
+    _ => Err(invalid_schema(format!(
+      "{} must be a non-empty array of schemas.",
+      name
+    ))),
+  }
+}
+
+impl Schema {
+  fn compile(schema: &Value) -> Result<Schema, Error> {
+    let fields = match schema {
+      Value::Bool(b) => return Ok(Schema::Bool(*b)),
+      Value::Object(fields) => fields,
+      _ => return Err(invalid_schema(String::from("schema must be an object."))),
+    };
+
+    let mut keywords = Vec::new();
+    for (name, value) in fields {
+      let keyword = match name.as_str() {
+        "type" | "bsonType" => {
+          let bson = name == "bsonType";
+          let names: Vec<&Value> = match value {
+            Value::Array(names) => names.iter().collect(),
+            name => vec![name],
+          };
+          let mut types = Vec::new();
+          for type_name in names {
+            let parsed = type_name.as_str().and_then(|t| SchemaType::parse(t, bson));
+            match parsed {
+              Some(schema_type) => types.push(schema_type),
+              None => return Err(invalid_schema(format!("unknown {} {}.", name, type_name))),
+            }
+          }
+          Keyword::Type(types, value.to_string())
+        }
+        "enum" => match value.as_array() {
+          Some(values) => Keyword::Enum(values.clone()),
+          None => return Err(invalid_schema(String::from("enum must be an array."))),
+        },
+        "const" => Keyword::Const(value.clone()),
+        "properties" => Keyword::Properties(
+          object(name, value)?
+            .iter()
+            .map(|(key, schema)| Ok((key.clone(), Schema::compile(schema)?)))
+            .collect::<Result<_, Error>>()?,
+        ),
+        "patternProperties" => Keyword::PatternProperties(
+          object(name, value)?
+            .iter()
+            .map(|(pattern, schema)| {
+              Ok((
+                regex(name, &Value::from(pattern.as_str()))?,
+                Schema::compile(schema)?,
+              ))
+            })
+            .collect::<Result<_, Error>>()?,
+        ),
+        "additionalProperties" => Keyword::AdditionalProperties {
+          schema: Box::new(Schema::compile(value)?),
+          known: fields
+            .get("properties")
+            .and_then(|p| p.as_object())
+            .map(|p| p.keys().cloned().collect())
+            .unwrap_or_default(),
+          patterns: match fields.get("patternProperties").and_then(|p| p.as_object()) {
+            Some(patterns) => patterns
+              .keys()
+              .map(|pattern| regex("patternProperties", &Value::from(pattern.as_str())))
+              .collect::<Result<_, Error>>()?,
+            None => Vec::new(),
+          },
+        },
+        "required" => match value.as_array() {
+          Some(names) if names.iter().all(|n| n.is_string()) => Keyword::Required(
+            names
+              .iter()
+              .filter_map(|n| n.as_str().map(|n| n.to_string()))
+              .collect(),
+          ),
+          _ => {
+            return Err(invalid_schema(String::from(
+              "required must be an array of strings.",
+            )))
+          }
+        },
+        "minProperties" => Keyword::MinProperties(count(name, value)?),
+        "maxProperties" => Keyword::MaxProperties(count(name, value)?),
+        "prefixItems" => Keyword::PrefixItems(schemas(name, value)?),
+        "items" => Keyword::Items {
+          schema: Box::new(Schema::compile(value)?),
+          skip: fields
+            .get("prefixItems")
+            .and_then(|p| p.as_array())
+            .map(|p| p.len())
+            .unwrap_or(0),
+        },
+        "minItems" => Keyword::MinItems(count(name, value)?),
+        "maxItems" => Keyword::MaxItems(count(name, value)?),
+        "uniqueItems" => match value.as_bool() {
+          Some(true) => Keyword::UniqueItems,
+          Some(false) => continue,
+          None => {
+            return Err(invalid_schema(String::from(
+              "uniqueItems must be a boolean.",
+            )))
+          }
+        },
+        "minimum" => Keyword::Minimum(limit(name, value)?),
+        "maximum" => Keyword::Maximum(limit(name, value)?),
+        "exclusiveMinimum" => Keyword::ExclusiveMinimum(limit(name, value)?),
+        "exclusiveMaximum" => Keyword::ExclusiveMaximum(limit(name, value)?),
+        "multipleOf" => match value.as_f64() {
+          Some(n) if n > 0.0 => Keyword::MultipleOf(n),
+          _ => {
+            return Err(invalid_schema(String::from(
+              "multipleOf must be greater than 0.",
+            )))
+          }
+        },
+        "minLength" => Keyword::MinLength(count(name, value)?),
+        "maxLength" => Keyword::MaxLength(count(name, value)?),
+        "pattern" => Keyword::Pattern(regex(name, value)?),
+        "allOf" => Keyword::AllOf(schemas(name, value)?),
+        "anyOf" => Keyword::AnyOf(schemas(name, value)?),
+        "oneOf" => Keyword::OneOf(schemas(name, value)?),
+        "not" => Keyword::Not(Box::new(Schema::compile(value)?)),
+        "$ref" | "$dynamicRef" | "$recursiveRef" => {
+          return Err(invalid_schema(format!("{} is not supported.", name)))
+        }
+        // annotations and unknown keywords do not affect validation
+        _ => continue,
+      };
+      keywords.push((name.clone(), keyword));
+    }
+    Ok(Schema::Keywords(keywords))
+  }
+
+  fn is_valid(&self, value: &Value) -> bool {
+    let mut failures = Vec::new();
+    self.validate(value, "", &mut failures);
+    failures.is_empty()
+  }
+
+  fn validate(&self, value: &Value, path: &str, failures: &mut Vec<ValidationFailure>) {
+    let keywords = match self {
+      Schema::Bool(true) => return,
+      Schema::Bool(false) => {
+        failures.push(failure(path, "false", String::from("is not allowed")));
+        return;
+      }
+      Schema::Keywords(keywords) => keywords,
+    };
+
+    for (name, keyword) in keywords {
+      let mut fail = |message: String| failures.push(failure(path, name, message));
+      match keyword {
+        Keyword::Type(types, expected) => {
+          if !types.iter().any(|t| t.matches(value)) {
+            fail(format!(
+              "must be of type {}, found {}",
+              expected,
+              type_name(value)
+            ));
+          }
+        }
+        Keyword::Enum(values) => {
+          if !values.iter().any(|v| values_equal(v, value)) {
+            fail(format!("must be one of {}", Value::from(values.clone())));
+          }
+        }
+        Keyword::Const(expected) => {
+          if !values_equal(expected, value) {
+            fail(format!("must be {}", expected));
+          }
+        }
+        Keyword::Properties(properties) => {
+          if let Some(fields) = value.as_object() {
+            for (key, schema) in properties {
+              if let Some(field) = fields.get(key) {
+                schema.validate(field, &child_path(path, key), failures);
+              }
+            }
+          }
+        }
+        Keyword::PatternProperties(patterns) => {
+          if let Some(fields) = value.as_object() {
+            for (key, field) in fields {
+              for (pattern, schema) in patterns {
+                if pattern.is_match(key) {
+                  schema.validate(field, &child_path(path, key), failures);
+                }
+              }
+            }
+          }
+        }
+        Keyword::AdditionalProperties {
+          schema,
+          known,
+          patterns,
+        } => {
+          if let Some(fields) = value.as_object() {
+            for (key, field) in fields {
+              if known.contains(key) || patterns.iter().any(|p| p.is_match(key)) {
+                continue;
+              }
+              match schema.as_ref() {
+                Schema::Bool(false) => failures.push(failure(
+                  &child_path(path, key),
+                  name,
+                  String::from("is not allowed"),
+                )),
+                schema => schema.validate(field, &child_path(path, key), failures),
+              }
+            }
+          }
+        }
+        Keyword::Required(names) => {
+          if let Some(fields) = value.as_object() {
+            for key in names.iter().filter(|key| !fields.contains_key(*key)) {
+              failures.push(failure(
+                &child_path(path, key),
+                name,
+                String::from("is required"),
+              ));
+            }
+          }
+        }
+        Keyword::MinProperties(min) => {
+          if value.as_object().is_some_and(|fields| fields.len() < *min) {
+            fail(format!("must have at least {} properties", min));
+          }
+        }
+        Keyword::MaxProperties(max) => {
+          if value.as_object().is_some_and(|fields| fields.len() > *max) {
+            fail(format!("must have at most {} properties", max));
+          }
+        }
+        Keyword::PrefixItems(schemas) => {
+          if let Some(items) = value.as_array() {
+            for (i, (item, schema)) in items.iter().zip(schemas).enumerate() {
+              schema.validate(item, &child_path(path, &i.to_string()), failures);
+            }
+          }
+        }
+        Keyword::Items { schema, skip } => {
+          if let Some(items) = value.as_array() {
+            for (i, item) in items.iter().enumerate().skip(*skip) {
+              schema.validate(item, &child_path(path, &i.to_string()), failures);
+            }
+          }
+        }
+        Keyword::MinItems(min) => {
+          if value.as_array().is_some_and(|items| items.len() < *min) {
+            fail(format!("must have at least {} items", min));
+          }
+        }
+        Keyword::MaxItems(max) => {
+          if value.as_array().is_some_and(|items| items.len() > *max) {
+            fail(format!("must have at most {} items", max));
+          }
+        }
+        Keyword::UniqueItems => {
+          if let Some(items) = value.as_array() {
+            let duplicate = items
+              .iter()
+              .enumerate()
+              .any(|(i, a)| items[i + 1..].iter().any(|b| values_equal(a, b)));
+            if duplicate {
+              fail(String::from("must have unique items"));
+            }
+          }
+        }
+        Keyword::Minimum(min) => {
+          if number(value).is_some_and(|n| n < *min) {
+            fail(format!("must be greater than or equal to {}", min));
+          }
+        }
+        Keyword::Maximum(max) => {
+          if number(value).is_some_and(|n| n > *max) {
+            fail(format!("must be less than or equal to {}", max));
+          }
+        }
+        Keyword::ExclusiveMinimum(min) => {
+          if number(value).is_some_and(|n| n <= *min) {
+            fail(format!("must be greater than {}", min));
+          }
+        }
+        Keyword::ExclusiveMaximum(max) => {
+          if number(value).is_some_and(|n| n >= *max) {
+            fail(format!("must be less than {}", max));
+          }
+        }
+        Keyword::MultipleOf(divisor) => {
+          if let Some(n) = number(value) {
+            let quotient = n / divisor;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+              fail(format!("must be a multiple of {}", divisor));
+            }
+          }
+        }
+        Keyword::MinLength(min) => {
+          if value.as_str().is_some_and(|s| s.chars().count() < *min) {
+            fail(format!("must be at least {} characters long", min));
+          }
+        }
+        Keyword::MaxLength(max) => {
+          if value.as_str().is_some_and(|s| s.chars().count() > *max) {
+            fail(format!("must be at most {} characters long", max));
+          }
+        }
+        Keyword::Pattern(pattern) => {
+          if value.as_str().is_some_and(|s| !pattern.is_match(s)) {
+            fail(format!("must match pattern {}", pattern));
+          }
+        }
+        Keyword::AllOf(schemas) => {
+          for schema in schemas {
+            schema.validate(value, path, failures);
+          }
+        }
+        Keyword::AnyOf(schemas) => {
+          if !schemas.iter().any(|schema| schema.is_valid(value)) {
+            fail(String::from("must match at least one schema of anyOf"));
+          }
+        }
+        Keyword::OneOf(schemas) => {
+          let matched = schemas
+            .iter()
+            .filter(|schema| schema.is_valid(value))
+            .count();
+          if matched != 1 {
+            fail(format!(
+              "must match exactly one schema of oneOf, matched {}",
+              matched
+            ));
+          }
+        }
+        Keyword::Not(schema) => {
+          if schema.is_valid(value) {
+            fail(String::from("must not match schema of not"));
+          }
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  /// Failed paths in sorted order, keys of JSON objects are not ordered without
+  /// `preserve_order` feature of `serde_json`.
+  fn failed_paths(validator: &Value, document: &Value) -> Vec<String> {
+    let mut paths: Vec<String> = Validator::new(validator)
+      .unwrap()
+      .validate(document)
+      .into_iter()
+      .map(|f| f.path)
+      .collect();
+    paths.sort();
+    paths
+  }
+
+  #[test]
+  fn test_json_schema() {
+    let validator = json!({
+      "$jsonSchema": {
+        "type": "object",
+        "required": ["name", "age"],
+        "properties": {
+          "name": { "type": "string", "minLength": 1 },
+          "age": { "type": "integer", "minimum": 0 },
+          "tags": { "type": "array", "items": { "enum": ["a", "b"] }, "uniqueItems": true },
+          "created": { "bsonType": "date" },
+          "address": {
+            "properties": { "city": { "type": "string" } },
+            "additionalProperties": false
+          }
+        }
+      }
+    });
+    let valid = json!({
+      "name": "Tom",
+      "age": 25.0,
+      "tags": ["a", "b"],
+      "created": { "$date": "2020-07-01T00:00:00Z" },
+      "address": { "city": "Boston" }
+    });
+    assert!(failed_paths(&validator, &valid).is_empty());
+
+    let invalid = json!({
+      "age": "25",
+      "tags": ["a", "c", "a"],
+      "created": "2020-07-01",
+      "address": { "city": "Boston", "zip": 2134 }
+    });
+    assert_eq!(
+      failed_paths(&validator, &invalid),
+      vec!["address.zip", "age", "created", "name", "tags", "tags.1"]
+    );
+  }
+
+  #[test]
+  fn test_query_clauses() {
+    let validator =
+      json!({ "age": { "$gte": 18 }, "$or": [{ "role": "admin" }, { "role": "user" }] });
+    assert!(failed_paths(&validator, &json!({ "age": 20, "role": "user" })).is_empty());
+    assert_eq!(
+      failed_paths(&validator, &json!({ "age": 10, "role": "guest" })),
+      vec!["", "age"]
+    );
+  }
+
+  #[test]
+  fn test_invalid_schema() {
+    assert!(Validator::new(&json!({ "$jsonSchema": { "type": "text" } })).is_err());
+    assert!(Validator::new(&json!({ "$jsonSchema": { "$ref": "#/$defs/a" } })).is_err());
+    assert!(Validator::new(&json!({ "$jsonSchema": { "pattern": "(" } })).is_err());
+    assert!(Validator::new(&json!({ "age": { "$gtee": 1 } })).is_err());
+  }
+}
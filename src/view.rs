@@ -0,0 +1,195 @@
+//! Point-in-time views of collections and databases.
+//!
+//! View is immutable copy of collection data taken under read lock in constant time, see
+//! `CollectionData`.  Queries on view do not take the lock of collection, so writers proceed
+//! while view is scanned and exported, and view does not see their changes.
+//!
+//! Database view copies all collections at the same moment, their locks are taken in order
+//! of collection names like in transaction.
+//!
+
+use super::{
+  engine::{CollectionData, DocumentCollection, Documents, Engine},
+  errors::Error,
+  import_export::{ExportFormat, ExportOptions},
+  index::IndexDefinition,
+  planner::ExplainOptions,
+  query::CompiledQuery,
+  snapshot::{CollectionSnapshot, Snapshot},
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+
+/// Immutable point-in-time view of collection.
+///
+/// ```
+/// use memquery::{doc, errors::Error, blocking::MemDb, query};
+///
+/// fn play() -> Result<(), Error> {
+///   let memdb = MemDb::new();
+///   memdb.create_collection("TestCollection")?;
+///   let coll = memdb.collection("TestCollection")?;
+///   coll.insert(doc!({ "name": "Tom", "age": 25 }))?;
+///
+///   let view = coll.view();
+///   coll.insert(doc!({ "name": "Bob", "age": 30 }))?;
+///   assert_eq!(view.find(query!({}))?.len(), 1);
+///   assert_eq!(coll.find(query!({}))?.len(), 2);
+///   Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CollectionView {
+  data: DocumentCollection,
+  lsn: Option<u64>,
+}
+
+impl CollectionView {
+  /// Copies collection data detached from write-ahead log.
+  pub(crate) fn new(data: &CollectionData) -> CollectionView {
+    let lsn = data.log_position();
+    #[allow(unused_mut)]
+    let mut data = data.clone();
+    #[cfg(feature = "persistence")]
+    data.set_log(None);
+    CollectionView {
+      data: Arc::new(RwLock::new(data)),
+      lsn,
+    }
+  }
+
+  fn engine(&self) -> Engine {
+    Engine::with_collection(self.data.clone())
+  }
+
+  /// Number of documents in view.
+  pub fn len(&self) -> usize {
+    self.data.read().unwrap().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Return documents that match specified criteria.
+  pub fn find(&self, query: Value) -> Result<Documents, Error> {
+    self.engine().find(&CompiledQuery::new(&query)?)
+  }
+
+  /// Return first document that matches specified criteria.
+  pub fn find_one(&self, query: Value) -> Result<Option<Value>, Error> {
+    self.engine().find_one(&CompiledQuery::new(&query)?)
+  }
+
+  /// Return documents that match query compiled with `CompiledQuery::new`.
+  pub fn find_compiled(&self, query: &CompiledQuery) -> Result<Documents, Error> {
+    self.engine().find(query)
+  }
+
+  /// Returns definitions of all indexes on collection.
+  pub fn list_indexes(&self) -> Vec<IndexDefinition> {
+    self.engine().list_indexes()
+  }
+
+  /// Returns copy of index definitions (except `_id_`) and documents.
+  pub fn snapshot(&self) -> CollectionSnapshot {
+    CollectionSnapshot {
+      lsn: self.lsn,
+      ..self.engine().snapshot()
+    }
+  }
+
+  /// Writes all documents of view as JSON Lines.
+  pub fn export_jsonl<W: Write>(&self, writer: W, options: ExportOptions) -> Result<u64, Error> {
+    self
+      .engine()
+      .export(writer, options, ExportFormat::JsonLines)
+  }
+
+  /// Writes all documents of view as single JSON array.
+  pub fn export_json_array<W: Write>(
+    &self,
+    writer: W,
+    options: ExportOptions,
+  ) -> Result<u64, Error> {
+    self
+      .engine()
+      .export(writer, options, ExportFormat::JsonArray)
+  }
+
+  /// Writes all documents of view as concatenated BSON documents (`bson` feature).
+  #[cfg(feature = "bson")]
+  pub fn export_bson<W: Write>(&self, writer: W, options: ExportOptions) -> Result<u64, Error> {
+    self.engine().export(writer, options, ExportFormat::Bson)
+  }
+
+  /// Describes how query is executed on view.
+  pub fn explain(&self, query: Value, options: ExplainOptions) -> Result<Value, Error> {
+    self.engine().explain(&CompiledQuery::new(&query)?, options)
+  }
+}
+
+/// Immutable point-in-time view of all collections of database.
+///
+/// ```
+/// use memquery::{doc, errors::Error, blocking::MemDb, query};
+///
+/// fn play() -> Result<(), Error> {
+///   let memdb = MemDb::new();
+///   memdb.create_collection("Users")?;
+///   memdb.create_collection("Orders")?;
+///   let view = memdb.view();
+///   memdb.collection("Users")?.insert(doc!({ "name": "Tom" }))?;
+///   assert!(view.collection("Users")?.is_empty());
+///   assert_eq!(view.collection_names(), vec!["Orders", "Users"]);
+///   Ok(())
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct DatabaseView {
+  collections: BTreeMap<String, CollectionView>,
+}
+
+impl DatabaseView {
+  /// Copies collections holding read locks of all of them, taken in order of their names.
+  pub(crate) fn new(collections: &HashMap<String, DocumentCollection>) -> DatabaseView {
+    let sorted: BTreeMap<&String, &DocumentCollection> = collections.iter().collect();
+    let locked: Vec<_> = sorted
+      .into_iter()
+      .map(|(name, collection)| (name, collection.read().unwrap()))
+      .collect();
+    DatabaseView {
+      collections: locked
+        .iter()
+        .map(|(name, data)| (name.to_string(), CollectionView::new(data)))
+        .collect(),
+    }
+  }
+
+  /// Retrieves view of collection by name.
+  pub fn collection(&self, name: &str) -> Result<CollectionView, Error> {
+    self
+      .collections
+      .get(name)
+      .cloned()
+      .ok_or_else(|| Error::MQCollectionNotFound(name.to_string()))
+  }
+
+  /// Names of collections in view, sorted.
+  pub fn collection_names(&self) -> Vec<String> {
+    self.collections.keys().cloned().collect()
+  }
+
+  /// Copies all collections with their index definitions.
+  pub fn to_snapshot(&self) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    for (name, collection) in self.collections.iter() {
+      snapshot
+        .collections
+        .insert(name.clone(), collection.snapshot());
+    }
+    snapshot
+  }
+}
@@ -0,0 +1,621 @@
+//! Append-only write-ahead log that makes MemDb durable (`persistence` feature).
+//!
+//! Database directory contains `snapshot.json` (see `snapshot` module) and `wal.jsonl`.
+//! Every change is appended to the log as single JSON line before the call returns:
+//!
+//! ```json
+//! {"lsn":7,"collection":"Users","op":"insert","document":{"_id":"...","name":"Rob"}}
+//! {"lsn":8,"collection":"Users","op":"update","query":{"name":"Rob"},"update":{"$set":{"age":26}}}
+//! ```
+//!
+//! Changes of a transaction are written as one entry, so either all or none of them are
+//! replayed:
+//!
+//! ```json
+//! {"lsn":9,"collection":"","op":"transaction","changes":[{"collection":"Stock","op":"update",...},{"collection":"Orders","op":"insert",...}]}
+//! ```
+//!
+//! On startup snapshot is loaded and log is replayed over it.  Compaction writes new snapshot
+//! and drops log entries that the snapshot already contains.  Each log entry has log sequence
+//! number (lsn) and snapshot remembers lsn it covers for every collection, so compaction does
+//! not have to stop writers.
+//!
+//! Log is written with blocking file I/O also when async API is used.
+//!
+
+use super::{
+  errors::Error,
+  index::IndexDefinition,
+  options::CollectionOptions,
+  snapshot::{write_atomically, Snapshot},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Name of snapshot file in database directory.
+pub const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// Name of log file in database directory.
+pub const LOG_FILE: &str = "wal.jsonl";
+
+/// When log file is flushed to disk with fsync.
+///
+/// Every entry is always written to the OS before operation returns, so entries survive
+/// process crash.  Fsync policy decides how many entries may be lost if machine crashes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FsyncPolicy {
+  /// Fsync after every entry.
+  #[default]
+  Always,
+  /// Fsync after every n entries.
+  EveryN(u64),
+  /// Leave flushing to the OS.
+  Never,
+}
+
+/// Options for `MemDb::open` and `MemDb::with_persistence`.
+#[derive(Clone, Debug)]
+pub struct PersistenceOptions {
+  /// Directory with snapshot and log files.  Created if it does not exist.
+  pub dir: PathBuf,
+  pub fsync: FsyncPolicy,
+}
+
+impl PersistenceOptions {
+  pub fn new<P: AsRef<Path>>(dir: P) -> PersistenceOptions {
+    PersistenceOptions {
+      dir: dir.as_ref().to_path_buf(),
+      fsync: FsyncPolicy::default(),
+    }
+  }
+
+  pub fn snapshot_path(&self) -> PathBuf {
+    self.dir.join(SNAPSHOT_FILE)
+  }
+
+  pub fn log_path(&self) -> PathBuf {
+    self.dir.join(LOG_FILE)
+  }
+}
+
+/// Change recorded in the log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum WalOperation {
+  CreateCollection {
+    #[serde(default, skip_serializing_if = "CollectionOptions::is_default")]
+    options: CollectionOptions,
+  },
+  DeleteCollection,
+  RenameCollection {
+    to: String,
+    drop_target: bool,
+  },
+  Insert {
+    document: Value,
+  },
+  Update {
+    query: Value,
+    update: Value,
+  },
+  Delete {
+    query: Value,
+  },
+  CreateIndex {
+    index: IndexDefinition,
+  },
+  DropIndex {
+    name: String,
+  },
+  Transaction {
+    changes: Vec<WalChange>,
+  },
+}
+
+/// Change of one collection made in transaction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WalChange {
+  pub collection: String,
+  #[serde(flatten)]
+  pub operation: WalOperation,
+}
+
+impl WalOperation {
+  /// Operations on collection itself rather than on its documents or indexes.
+  pub fn is_collection_operation(&self) -> bool {
+    matches!(
+      self,
+      WalOperation::CreateCollection { .. }
+        | WalOperation::DeleteCollection
+        | WalOperation::RenameCollection { .. }
+    )
+  }
+}
+
+/// Single line of the log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WalEntry {
+  pub lsn: u64,
+  pub collection: String,
+  #[serde(flatten)]
+  pub operation: WalOperation,
+}
+
+impl WalEntry {
+  /// True if snapshot already contains effect of this entry.  Transaction is contained
+  /// only if all of its changes are.
+  pub fn is_in_snapshot(&self, snapshot: &Snapshot) -> bool {
+    if let WalOperation::Transaction { .. } = self.operation {
+      return self
+        .clone()
+        .changes()
+        .iter()
+        .all(|change| change.is_in_snapshot(snapshot));
+    }
+    let snapshot_lsn = snapshot.lsn.unwrap_or(0);
+    if self.operation.is_collection_operation() {
+      return self.lsn < snapshot_lsn;
+    }
+    match snapshot.collections.get(&self.collection) {
+      Some(collection) => self.lsn < collection.lsn.unwrap_or(snapshot_lsn),
+      None => self.lsn < snapshot_lsn,
+    }
+  }
+
+  /// Splits transaction into entries with its changes, each with lsn of the transaction.
+  /// Any other entry is returned as it is.
+  pub fn changes(self) -> Vec<WalEntry> {
+    let lsn = self.lsn;
+    match self.operation {
+      WalOperation::Transaction { changes } => changes
+        .into_iter()
+        .map(|change| WalEntry {
+          lsn,
+          collection: change.collection,
+          operation: change.operation,
+        })
+        .collect(),
+      _ => vec![self],
+    }
+  }
+}
+
+/// Parses log contents.
+///
+/// Last line may be incomplete if process crashed while writing it, so it is ignored
+/// when it cannot be parsed.  Any other invalid line is an error.
+pub fn parse_log(contents: &str) -> Result<Vec<WalEntry>, Error> {
+  let lines: Vec<&str> = contents.lines().collect();
+  let mut entries = Vec::with_capacity(lines.len());
+  for (number, line) in lines.iter().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    match serde_json::from_str::<WalEntry>(line) {
+      Ok(entry) => entries.push(entry),
+      Err(_) if number + 1 == lines.len() && !contents.ends_with('\n') => break,
+      Err(e) => {
+        return Err(Error::MQError(format!(
+          "Invalid log entry at line {}: {}",
+          number + 1,
+          e
+        )))
+      }
+    }
+  }
+  Ok(entries)
+}
+
+/// Reads snapshot written by compaction, or empty snapshot if there is none yet.
+pub fn read_snapshot(path: &Path) -> Result<Snapshot, Error> {
+  match std::fs::read(path) {
+    Ok(data) => Snapshot::from_slice(&data),
+    Err(e) if e.kind() == ErrorKind::NotFound => Ok(Snapshot::new()),
+    Err(e) => Err(e.into()),
+  }
+}
+
+/// Reads all log entries.
+///
+/// Incomplete last line is removed from the file so that new entries start on their own line.
+pub fn read_log(path: &Path) -> Result<Vec<WalEntry>, Error> {
+  let data = match std::fs::read(path) {
+    Ok(data) => data,
+    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => return Err(e.into()),
+  };
+  let entries = parse_log(&String::from_utf8_lossy(&data))?;
+  if data.last().is_some_and(|b| *b != b'\n') {
+    write_atomically(path, &entries_to_vec(&entries)?)?;
+  }
+  Ok(entries)
+}
+
+fn entries_to_vec<'e, I>(entries: I) -> Result<Vec<u8>, Error>
+where
+  I: IntoIterator<Item = &'e WalEntry>,
+{
+  let mut data = Vec::new();
+  for entry in entries {
+    data.extend(serde_json::to_vec(entry)?);
+    data.push(b'\n');
+  }
+  Ok(data)
+}
+
+/// File that entries are appended to.
+trait LogSink: Write + Send {
+  fn size(&self) -> std::io::Result<u64>;
+  fn truncate(&mut self, size: u64) -> std::io::Result<()>;
+  fn sync(&mut self) -> std::io::Result<()>;
+}
+
+impl LogSink for File {
+  fn size(&self) -> std::io::Result<u64> {
+    Ok(self.metadata()?.len())
+  }
+
+  fn truncate(&mut self, size: u64) -> std::io::Result<()> {
+    self.set_len(size)
+  }
+
+  fn sync(&mut self) -> std::io::Result<()> {
+    self.sync_data()
+  }
+}
+
+struct LogFile {
+  file: Box<dyn LogSink>,
+  unsynced: u64,
+  /// Failed append left its entry in the file and it could not be cut off.  Replay would
+  /// apply operation that was reported as failed, or reject the log if entry is partly
+  /// written and next one continues the same line.
+  poisoned: bool,
+}
+
+impl LogFile {
+  /// Writes line and syncs file as `fsync` policy requires.
+  fn write(&mut self, line: &[u8], fsync: FsyncPolicy) -> std::io::Result<()> {
+    self.file.write_all(line)?;
+    match fsync {
+      FsyncPolicy::Always => self.file.sync()?,
+      FsyncPolicy::EveryN(n) => {
+        if self.unsynced + 1 >= n {
+          self.file.sync()?;
+          self.unsynced = 0;
+        } else {
+          self.unsynced += 1;
+        }
+      }
+      FsyncPolicy::Never => (),
+    }
+    Ok(())
+  }
+}
+
+/// Log file shared by all collections of the database.
+pub struct WriteAheadLog {
+  path: PathBuf,
+  fsync: FsyncPolicy,
+  file: Mutex<LogFile>,
+  next_lsn: AtomicU64,
+}
+
+impl WriteAheadLog {
+  /// Opens log for appending.  Entries get sequence numbers starting at `next_lsn`.
+  pub fn open(path: &Path, fsync: FsyncPolicy, next_lsn: u64) -> Result<WriteAheadLog, Error> {
+    Ok(WriteAheadLog {
+      path: path.to_path_buf(),
+      fsync,
+      file: Mutex::new(LogFile {
+        file: Box::new(open_append(path)?),
+        unsynced: 0,
+        poisoned: false,
+      }),
+      next_lsn: AtomicU64::new(next_lsn),
+    })
+  }
+
+  #[cfg(test)]
+  fn with_sink(path: &Path, fsync: FsyncPolicy, sink: Box<dyn LogSink>) -> WriteAheadLog {
+    WriteAheadLog {
+      path: path.to_path_buf(),
+      fsync,
+      file: Mutex::new(LogFile {
+        file: sink,
+        unsynced: 0,
+        poisoned: false,
+      }),
+      next_lsn: AtomicU64::new(0),
+    }
+  }
+
+  /// Sequence number that next entry will get.
+  pub fn next_lsn(&self) -> u64 {
+    self.next_lsn.load(Ordering::SeqCst)
+  }
+
+  /// Appends entry and returns its sequence number.
+  ///
+  /// If entry is written only partly or is not synced as `fsync` policy requires, the file
+  /// is cut back to its previous size.  If that fails too, this and later appends return
+  /// error until log is compacted.
+  pub fn append(&self, collection: &str, operation: WalOperation) -> Result<u64, Error> {
+    let mut log = self.file.lock().unwrap();
+    if log.poisoned {
+      return Err(Error::MQError(String::from(
+        "Write-ahead log has failed entry that could not be removed, compact database to recover.",
+      )));
+    }
+    let entry = WalEntry {
+      lsn: self.next_lsn(),
+      collection: collection.to_string(),
+      operation,
+    };
+    let mut line = serde_json::to_vec(&entry)?;
+    line.push(b'\n');
+    let size = log.file.size()?;
+    if let Err(e) = log.write(&line, self.fsync) {
+      if log.file.truncate(size).is_err() {
+        log.poisoned = true;
+      }
+      return Err(e.into());
+    }
+    self.next_lsn.fetch_add(1, Ordering::SeqCst);
+    Ok(entry.lsn)
+  }
+
+  /// Rewrites log without entries that are contained in `snapshot`.
+  ///
+  /// Appends wait while log is rewritten.
+  pub fn compact(&self, snapshot: &Snapshot) -> Result<(), Error> {
+    let mut log = self.file.lock().unwrap();
+    log.file.sync()?;
+
+    let entries = parse_log(&String::from_utf8_lossy(&std::fs::read(&self.path)?))?;
+    let data = entries_to_vec(entries.iter().filter(|e| !e.is_in_snapshot(snapshot)))?;
+    write_atomically(&self.path, &data)?;
+    log.file = Box::new(open_append(&self.path)?);
+    log.unsynced = 0;
+    log.poisoned = false;
+    Ok(())
+  }
+}
+
+fn open_append(path: &Path) -> Result<File, Error> {
+  Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+/// Log attached to collection data together with collection name.
+#[derive(Clone)]
+pub struct CollectionLog {
+  pub wal: Arc<WriteAheadLog>,
+  pub collection: String,
+}
+
+impl CollectionLog {
+  pub fn append(&self, operation: WalOperation) -> Result<u64, Error> {
+    self.wal.append(&self.collection, operation)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::snapshot::CollectionSnapshot;
+  use serde_json::json;
+  use std::io;
+  use std::sync::Arc;
+
+  /// Log file in memory.  When `fail` is set next write stores half of the entry and the
+  /// one after it fails.
+  #[derive(Clone, Default)]
+  struct FailingSink {
+    data: Arc<Mutex<Vec<u8>>>,
+    fail: Arc<Mutex<bool>>,
+    truncate_fails: bool,
+    sync_fails: bool,
+    torn: bool,
+  }
+
+  impl Write for FailingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      let mut fail = self.fail.lock().unwrap();
+      if *fail && self.torn {
+        *fail = false;
+        self.torn = false;
+        return Err(io::Error::other("disk full"));
+      }
+      let len = if *fail {
+        self.torn = true;
+        buf.len() / 2
+      } else {
+        buf.len()
+      };
+      self.data.lock().unwrap().extend_from_slice(&buf[..len]);
+      Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  impl LogSink for FailingSink {
+    fn size(&self) -> io::Result<u64> {
+      Ok(self.data.lock().unwrap().len() as u64)
+    }
+
+    fn truncate(&mut self, size: u64) -> io::Result<()> {
+      if self.truncate_fails {
+        return Err(io::Error::other("read-only file system"));
+      }
+      self.data.lock().unwrap().truncate(size as usize);
+      Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+      if self.sync_fails {
+        return Err(io::Error::other("I/O error"));
+      }
+      Ok(())
+    }
+  }
+
+  fn create() -> WalOperation {
+    WalOperation::CreateCollection {
+      options: Default::default(),
+    }
+  }
+
+  #[test]
+  fn test_failed_append_is_cut_off() {
+    let sink = FailingSink::default();
+    let wal = WriteAheadLog::with_sink(
+      Path::new("unused"),
+      FsyncPolicy::Never,
+      Box::new(sink.clone()),
+    );
+    assert_eq!(wal.append("A", create()).unwrap(), 0);
+    *sink.fail.lock().unwrap() = true;
+    assert!(wal.append("B", create()).is_err());
+    assert_eq!(wal.append("C", create()).unwrap(), 1);
+
+    let data = sink.data.lock().unwrap().clone();
+    let entries = parse_log(&String::from_utf8(data).unwrap()).unwrap();
+    let collections: Vec<&str> = entries.iter().map(|e| e.collection.as_str()).collect();
+    assert_eq!(collections, vec!["A", "C"]);
+  }
+
+  #[test]
+  fn test_log_is_poisoned_if_torn_entry_stays() {
+    let sink = FailingSink {
+      truncate_fails: true,
+      ..Default::default()
+    };
+    let wal = WriteAheadLog::with_sink(
+      Path::new("unused"),
+      FsyncPolicy::Never,
+      Box::new(sink.clone()),
+    );
+    *sink.fail.lock().unwrap() = true;
+    assert!(wal.append("A", create()).is_err());
+    assert!(matches!(wal.append("B", create()), Err(Error::MQError(_))));
+    assert_eq!(
+      sink
+        .data
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|b| **b == b'\n')
+        .count(),
+      0
+    );
+  }
+
+  #[test]
+  fn test_failed_sync_is_cut_off() {
+    let sink = FailingSink {
+      sync_fails: true,
+      ..Default::default()
+    };
+    let wal = WriteAheadLog::with_sink(
+      Path::new("unused"),
+      FsyncPolicy::Always,
+      Box::new(sink.clone()),
+    );
+    assert!(wal.append("A", create()).is_err());
+    assert_eq!(wal.next_lsn(), 0);
+    assert!(sink.data.lock().unwrap().is_empty());
+
+    let sink = FailingSink {
+      sync_fails: true,
+      truncate_fails: true,
+      ..Default::default()
+    };
+    let wal = WriteAheadLog::with_sink(Path::new("unused"), FsyncPolicy::Always, Box::new(sink));
+    assert!(wal.append("A", create()).is_err());
+    assert!(matches!(wal.append("B", create()), Err(Error::MQError(_))));
+  }
+
+  #[test]
+  fn test_entry_format() {
+    let entry = WalEntry {
+      lsn: 3,
+      collection: "Users".to_string(),
+      operation: WalOperation::Delete {
+        query: json!({ "name": "Rob" }),
+      },
+    };
+    let line = serde_json::to_value(&entry).unwrap();
+    assert_eq!(
+      line,
+      json!({ "lsn": 3, "collection": "Users", "op": "delete", "query": { "name": "Rob" } })
+    );
+    assert_eq!(serde_json::from_value::<WalEntry>(line).unwrap(), entry);
+  }
+
+  #[test]
+  fn test_parse_ignores_torn_last_line() {
+    let contents =
+      "{\"lsn\":0,\"collection\":\"A\",\"op\":\"createCollection\"}\n{\"lsn\":1,\"coll";
+    assert_eq!(parse_log(contents).unwrap().len(), 1);
+    assert!(
+      parse_log("{\"lsn\n{\"lsn\":0,\"collection\":\"A\",\"op\":\"createCollection\"}\n").is_err()
+    );
+  }
+
+  #[test]
+  fn test_is_in_snapshot() {
+    let mut snapshot = Snapshot::new();
+    snapshot.lsn = Some(5);
+    snapshot.collections.insert(
+      "Users".to_string(),
+      CollectionSnapshot {
+        lsn: Some(8),
+        ..Default::default()
+      },
+    );
+    let entry = |lsn, collection: &str, operation| WalEntry {
+      lsn,
+      collection: collection.to_string(),
+      operation,
+    };
+    let insert = || WalOperation::Insert {
+      document: json!({}),
+    };
+
+    assert!(entry(7, "Users", insert()).is_in_snapshot(&snapshot));
+    assert!(!entry(8, "Users", insert()).is_in_snapshot(&snapshot));
+    assert!(!entry(6, "Users", WalOperation::DeleteCollection).is_in_snapshot(&snapshot));
+    assert!(entry(4, "Orders", insert()).is_in_snapshot(&snapshot));
+    assert!(!entry(6, "Orders", insert()).is_in_snapshot(&snapshot));
+
+    let transaction = entry(
+      6,
+      "",
+      WalOperation::Transaction {
+        changes: vec![
+          WalChange {
+            collection: "Users".to_string(),
+            operation: insert(),
+          },
+          WalChange {
+            collection: "Orders".to_string(),
+            operation: insert(),
+          },
+        ],
+      },
+    );
+    assert!(!transaction.is_in_snapshot(&snapshot));
+    let changes = transaction.changes();
+    assert_eq!(changes.len(), 2);
+    assert!(changes[0].is_in_snapshot(&snapshot));
+    assert!(!changes[1].is_in_snapshot(&snapshot));
+  }
+}
@@ -0,0 +1,122 @@
+#[cfg(all(feature = "bson", feature = "async"))]
+use bson::{doc, oid::ObjectId, spec::BinarySubtype, Binary, Bson, DateTime, Decimal128};
+#[cfg(all(feature = "bson", feature = "async"))]
+use memquery::{
+  errors::Error,
+  extended_json::date,
+  import_export::{ErrorPolicy, ExportOptions, ImportOptions},
+  index::IndexOptions,
+  query,
+  r#async::MemDb,
+};
+
+#[tokio::test]
+#[cfg(all(feature = "bson", feature = "async"))]
+async fn test_insert_and_find_bson() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Files").await?;
+  let coll = memdb.collection("Files").await?;
+  let id = ObjectId::new();
+  let document = doc! {
+    "_id": id,
+    "uploaded": DateTime::from_millis(1_593_561_600_000),
+    "size": "1024.50".parse::<Decimal128>().unwrap(),
+    "data": Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+    "views": 9_007_199_254_740_993_i64,
+  };
+  coll.insert_bson(document.clone()).await?;
+
+  let docs = coll
+    .find_bson(query!({ "uploaded": { "$gte": date(1_593_561_600_000) } }))
+    .await?;
+  assert_eq!(docs, vec![document]);
+  let docs = coll
+    .find(query!({ "_id": { "$oid": id.to_hex() } }))
+    .await?;
+  assert_eq!(docs[0]["size"]["$numberDecimal"], "1024.50");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(all(feature = "bson", feature = "async"))]
+async fn test_export_and_import_bson() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Source").await?;
+  memdb.create_collection("Target").await?;
+  let source = memdb.collection("Source").await?;
+  for age in 0..3 {
+    source
+      .insert_bson(doc! { "_id": ObjectId::new(), "age": age })
+      .await?;
+  }
+
+  let mut output = Vec::new();
+  let exported = source
+    .export_bson(&mut output, ExportOptions::default())
+    .await?;
+  assert_eq!(exported, 3);
+
+  output.extend(&[5, 0, 0, 0, 1]);
+  let target = memdb.collection("Target").await?;
+  let options = ImportOptions {
+    on_error: ErrorPolicy::Skip,
+    ..Default::default()
+  };
+  let summary = target.import_bson(&output[..], options).await?;
+  assert_eq!(summary.inserted, 3);
+  assert_eq!(summary.skipped.len(), 1);
+  assert_eq!(summary.skipped[0].line, 4);
+  assert_eq!(
+    target.find(query!({})).await?,
+    source.find(query!({})).await?
+  );
+
+  output.truncate(output.len() - 6);
+  assert!(target
+    .import_bson(&output[..], ImportOptions::default())
+    .await
+    .is_err());
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(all(feature = "bson", feature = "async"))]
+async fn test_dump_and_restore() -> Result<(), Error> {
+  let dir = std::env::temp_dir().join(format!("memquery-dump-{}", uuid::Uuid::new_v4()));
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection("Users").await?;
+  users
+    .create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  users
+    .insert_bson(doc! { "email": "rob@test.com", "joined": DateTime::now() })
+    .await?;
+  memdb.dump_bson(&dir).await?;
+  assert!(dir.join("Users.bson").exists());
+  assert!(dir.join("Users.metadata.json").exists());
+
+  let restored = MemDb::new();
+  restored.restore_bson(&dir).await?;
+  let restored_users = restored.collection("Users").await?;
+  assert_eq!(
+    restored_users.list_indexes().await,
+    users.list_indexes().await
+  );
+  let docs = restored_users.find_bson(query!({})).await?;
+  assert_eq!(docs, users.find_bson(query!({})).await?);
+  assert!(matches!(docs[0].get("joined"), Some(Bson::DateTime(_))));
+  assert!(restored_users
+    .insert_bson(doc! { "email": "rob@test.com" })
+    .await
+    .is_err());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
@@ -0,0 +1,74 @@
+#[cfg(feature = "async")]
+use memquery::{
+  builder::{Filter, Update, UpdateOps},
+  doc,
+  errors::Error,
+  r#async::MemDb,
+};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_find_with_filter() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll
+    .insert(doc!({ "name": "Rob", "age": 25, "tags": ["a", "b"] }))
+    .await?;
+  coll
+    .insert(doc!({ "name": "Bob", "age": 20, "tags": ["a"] }))
+    .await?;
+  coll
+    .insert(doc!({ "name": "Tom", "age": 30, "tags": ["b"] }))
+    .await?;
+
+  let filter = Filter::field("age")
+    .gte(21)
+    .and(Filter::field("tags").all(vec!["a"]));
+  let docs = coll.find(filter.into()).await?;
+
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+
+  let docs = coll
+    .find(
+      Filter::field("name")
+        .eq("Bob")
+        .or(Filter::field("name").regex("^t"))
+        .into(),
+    )
+    .await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Bob");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_update_with_builder() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll
+    .insert(doc!({ "name": "Rob", "age": 25, "email": "rob@test.com" }))
+    .await?;
+
+  let docs_updated = coll
+    .find_and_update(
+      Filter::field("name").eq("Rob").into(),
+      Update::set("nickname", "Bobcat")
+        .inc("age", 1)
+        .unset("email")
+        .into(),
+    )
+    .await?;
+  assert_eq!(docs_updated, 1);
+
+  let docs = coll
+    .find(Filter::field("nickname").eq("Bobcat").into())
+    .await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["age"], 26.0);
+  assert_eq!(docs[0]["email"], serde_json::Value::Null);
+  Ok(())
+}
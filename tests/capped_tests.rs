@@ -0,0 +1,71 @@
+#[cfg(feature = "async")]
+use futures_util::StreamExt;
+#[cfg(feature = "async")]
+use memquery::{
+  doc,
+  errors::Error,
+  options::{Capped, CollectionOptions, FindOptions},
+  query,
+  r#async::MemDb,
+};
+
+#[cfg(feature = "async")]
+fn capped(max_docs: u64) -> CollectionOptions {
+  CollectionOptions {
+    capped: Some(Capped {
+      max_docs: Some(max_docs),
+      max_bytes: None,
+    }),
+    ..Default::default()
+  }
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_max_docs_evicts_oldest() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb
+    .create_collection_with_options("Log", capped(3))
+    .await?;
+  let log = memdb.collection("Log").await?;
+  for n in 0..5 {
+    log.insert(doc!({ "n": n })).await?;
+  }
+  let numbers: Vec<_> = log
+    .find(query!({}))
+    .await?
+    .iter()
+    .map(|d| d["n"].clone())
+    .collect();
+  assert_eq!(numbers, vec![2, 3, 4]);
+
+  let options = FindOptions {
+    newest_first: true,
+    limit: Some(1),
+  };
+  let newest = log.find_with_options(query!({}), options).await?;
+  assert_eq!(newest[0]["n"], 4);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_tailable_stream() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb
+    .create_collection_with_options("Log", capped(10))
+    .await?;
+  let log = memdb.collection("Log").await?;
+  log.insert(doc!({ "level": "error", "n": 1 })).await?;
+
+  let mut stream = log.tail(query!({ "level": "error" })).await?;
+  assert_eq!(stream.next_document().await?["n"], 1);
+
+  log.insert(doc!({ "level": "info", "n": 2 })).await?;
+  log.insert(doc!({ "level": "error", "n": 3 })).await?;
+  assert_eq!(stream.next().await.unwrap()?["n"], 3);
+
+  memdb.delete_collection("Log").await?;
+  assert!(stream.next().await.is_none());
+  Ok(())
+}
@@ -0,0 +1,128 @@
+#[cfg(feature = "async")]
+use futures_util::StreamExt;
+#[cfg(feature = "async")]
+use memquery::{
+  change_stream::{OperationType, WatchOptions, CHANGE_HISTORY},
+  doc,
+  errors::Error,
+  query,
+  r#async::MemDb,
+  update,
+};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_watch_insert_update_delete() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  let mut changes = coll.watch(query!({})).await?;
+
+  coll
+    .insert(doc!({ "name": "Rob", "age": 25, "city": "NY" }))
+    .await?;
+  coll
+    .find_and_update(
+      query!({ "name": "Rob" }),
+      update!({ "$set": { "age": 26 }, "$unset": { "city": "" } }),
+    )
+    .await?;
+  coll.find_and_delete(query!({ "name": "Rob" })).await?;
+
+  let insert = changes.recv().await?;
+  assert_eq!(insert.operation_type, OperationType::Insert);
+  assert_eq!(insert.full_document.unwrap()["age"], 25);
+
+  let update = changes.recv().await?;
+  assert_eq!(update.operation_type, OperationType::Update);
+  let description = update.update_description.unwrap();
+  assert_eq!(description.updated_fields["age"], 26);
+  assert_eq!(description.removed_fields, vec!["city"]);
+
+  let delete = changes.recv().await?;
+  assert_eq!(delete.operation_type, OperationType::Delete);
+  assert_eq!(delete.full_document_before_change.unwrap()["name"], "Rob");
+  Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg(feature = "async")]
+async fn test_watch_stream_from_task() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  let changes = coll.watch(query!({ "age": { "$gte": 5 } })).await?;
+
+  let watcher = tokio::spawn(async move {
+    changes
+      .map(|event| event.unwrap().full_document.unwrap()["age"].clone())
+      .collect::<Vec<_>>()
+      .await
+  });
+  for age in 0..10 {
+    coll.insert(doc!({ "age": age })).await?;
+  }
+  memdb.delete_collection("TestCollection").await?;
+  assert_eq!(watcher.await.unwrap(), vec![5, 6, 7, 8, 9]);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_watch_resume_and_lag() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  let mut changes = coll.watch(query!({})).await?;
+
+  for age in 0..CHANGE_HISTORY + 5 {
+    coll.insert(doc!({ "age": age })).await?;
+  }
+  assert!(matches!(
+    changes.recv().await,
+    Err(Error::MQChangeStreamLagged(5))
+  ));
+  let event = changes.recv().await?;
+  assert_eq!(event.full_document.unwrap()["age"], 5);
+
+  let mut resumed = coll
+    .watch_with_options(
+      query!({}),
+      WatchOptions {
+        resume_after: Some(event.resume_token),
+      },
+    )
+    .await?;
+  assert_eq!(resumed.recv().await?.full_document.unwrap()["age"], 6);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_watch_transaction() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Pending").await?;
+  memdb.create_collection("Done").await?;
+  memdb
+    .collection("Pending")
+    .await?
+    .insert(doc!({ "name": "Rob" }))
+    .await?;
+  let mut done = memdb.collection("Done").await?.watch(query!({})).await?;
+
+  memdb
+    .transaction(&["Pending", "Done"], |tx| {
+      for document in tx
+        .collection("Pending")?
+        .find_and_delete(query!({ "name": "Rob" }))?
+      {
+        tx.collection("Done")?.insert(document)?;
+      }
+      Ok(())
+    })
+    .await?;
+  let event = done.recv().await?;
+  assert_eq!(event.operation_type, OperationType::Insert);
+  assert_eq!(event.full_document.unwrap()["name"], "Rob");
+  Ok(())
+}
@@ -0,0 +1,35 @@
+#[cfg(feature = "async")]
+use memquery::{
+  doc,
+  errors::{codes, Error},
+  query,
+  r#async::MemDb,
+};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_manage_collections() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  memdb
+    .collection("Users")
+    .await?
+    .insert(doc!({ "name": "Tom" }))
+    .await?;
+
+  let error = memdb.create_collection("Users").await.unwrap_err();
+  assert_eq!(error.code(), codes::NAMESPACE_EXISTS);
+  assert!(memdb.collection_exists("Users").await);
+
+  memdb.create_collection("People").await?;
+  assert!(memdb
+    .rename_collection("Users", "People", false)
+    .await
+    .is_err());
+  memdb.rename_collection("Users", "People", true).await?;
+
+  assert_eq!(memdb.list_collection_names().await, vec!["People"]);
+  let docs = memdb.collection("People").await?.find(query!({})).await?;
+  assert_eq!(docs[0]["name"], "Tom");
+  Ok(())
+}
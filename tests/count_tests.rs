@@ -0,0 +1,26 @@
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, options::CountOptions, query, r#async::MemDb};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_count_documents() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection("Users").await?;
+  for age in 20..30 {
+    users.insert(doc!({ "age": age })).await?;
+  }
+
+  let options = CountOptions {
+    skip: 1,
+    limit: Some(3),
+  };
+  assert_eq!(
+    users
+      .count_documents(query!({ "age": { "$gte": 25 } }), options)
+      .await?,
+    3
+  );
+  assert_eq!(users.estimated_document_count().await, 10);
+  Ok(())
+}
@@ -0,0 +1,50 @@
+#[cfg(feature = "async")]
+use futures_executor::block_on;
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, query, r#async::MemDb, update};
+
+#[test]
+#[cfg(feature = "async")]
+fn test_async_api_without_runtime() -> Result<(), Error> {
+  block_on(async {
+    let memdb = MemDb::new();
+    memdb.create_collection("TestCollection").await?;
+    let coll = memdb.collection("TestCollection").await?;
+    coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+    coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
+    coll
+      .find_and_update(
+        query!({ "name": "Rob" }),
+        update!({ "$set": { "age": 26 } }),
+      )
+      .await?;
+
+    let docs = coll.find(query!({ "age": { "$gt": 20 } })).await?;
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0]["age"], 26);
+    Ok(())
+  })
+}
+
+#[test]
+#[cfg(all(feature = "async", not(feature = "tokio")))]
+fn test_snapshot_without_runtime() -> Result<(), Error> {
+  let path = std::env::temp_dir().join("memquery_executor_snapshot.json");
+  block_on(async {
+    let memdb = MemDb::new();
+    memdb.create_collection("TestCollection").await?;
+    let coll = memdb.collection("TestCollection").await?;
+    coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+    memdb.save_to(&path).await?;
+
+    let restored = MemDb::new();
+    restored.load_from(&path).await?;
+    let docs = restored
+      .collection("TestCollection")
+      .await?
+      .find(query!({ "name": "Rob" }))
+      .await?;
+    assert_eq!(docs.len(), 1);
+    Ok(())
+  })
+}
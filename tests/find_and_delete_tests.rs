@@ -1,11 +1,11 @@
-#[cfg(not(feature = "sync"))]
-use memquery::{doc, errors::Error, memdb::MemDb, query};
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, query, r#async::MemDb};
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn simple_delete() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -21,10 +21,10 @@ async fn simple_delete() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn delete_all_docs() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -38,3 +38,22 @@ async fn delete_all_docs() -> Result<(), Error> {
 
   Ok(())
 }
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn invalid_query_deletes_nothing() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
+
+  assert!(coll
+    .find_and_delete(query!({ "age": { "$gtee": 20 } }))
+    .await
+    .is_err());
+
+  let docs_remaining = coll.find(query!({})).await?;
+  assert_eq!(docs_remaining.len(), 2);
+  Ok(())
+}
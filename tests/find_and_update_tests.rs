@@ -1,11 +1,11 @@
-#[cfg(not(feature = "sync"))]
-use memquery::{doc, errors::Error, memdb::MemDb, query, update};
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, query, r#async::MemDb, update};
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn simple_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -27,10 +27,10 @@ async fn simple_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn set_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -53,10 +53,11 @@ async fn set_op_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
+#[allow(clippy::redundant_pattern_matching)]
 async fn set_op_invalid_value_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -76,10 +77,11 @@ async fn set_op_invalid_value_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
+#[allow(clippy::redundant_pattern_matching)]
 async fn set_op_invalid_value_embedded_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -99,10 +101,10 @@ async fn set_op_invalid_value_embedded_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn unset_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -140,10 +142,10 @@ async fn unset_op_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn set_op_on_embedded_doc_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "name": "Rob", "age": 25, "profile": { "email": "rob@test.com" } }))
@@ -172,10 +174,10 @@ async fn set_op_on_embedded_doc_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn unset_op_on_embedded_doc_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "name": "Rob", "age": 25, "profile": { "email": "rob@test.com" } }))
@@ -217,10 +219,10 @@ async fn unset_op_on_embedded_doc_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn inc_positive_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -239,10 +241,10 @@ async fn inc_positive_op_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn inc_negative_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -261,10 +263,10 @@ async fn inc_negative_op_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn mul_positive_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -283,10 +285,10 @@ async fn mul_positive_op_update() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn mul_negative_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
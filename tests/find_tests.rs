@@ -1,11 +1,11 @@
-#[cfg(not(feature = "sync"))]
-use memquery::{doc, errors::Error, memdb::MemDb, query};
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, query, query::CompiledQuery, r#async::MemDb};
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_simple_query() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -19,10 +19,10 @@ async fn test_simple_query() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_simple_query_with_multiple_conditions() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -37,10 +37,10 @@ async fn test_simple_query_with_multiple_conditions() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_nomatch_query_with_multiple_conditions() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -53,10 +53,10 @@ async fn test_nomatch_query_with_multiple_conditions() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_query_match_with_and() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -72,10 +72,10 @@ async fn test_query_match_with_and() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_query_nomatch_with_and() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -90,10 +90,10 @@ async fn test_query_nomatch_with_and() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_query_match_with_or() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -108,10 +108,10 @@ async fn test_query_match_with_or() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_query_nomatch_with_or() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
   coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
@@ -126,10 +126,10 @@ async fn test_query_nomatch_with_or() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_eq_op() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -159,10 +159,10 @@ async fn test_eq_op() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_eq_nomatch_op() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -190,10 +190,10 @@ async fn test_eq_nomatch_op() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_eq_op_single_entry_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -222,10 +222,10 @@ async fn test_eq_op_single_entry_embedded_doc() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_eq_op_to_match_array_to_array() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -257,10 +257,10 @@ async fn test_eq_op_to_match_array_to_array() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_eq_op_to_nomatch_array_to_array() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -290,10 +290,10 @@ async fn test_eq_op_to_nomatch_array_to_array() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_eq_op_to_match_array_to_value() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -325,10 +325,10 @@ async fn test_eq_op_to_match_array_to_value() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_gt_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -358,10 +358,10 @@ async fn test_gt_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_gt_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -389,10 +389,10 @@ async fn test_gt_no_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_gt_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -422,10 +422,10 @@ async fn test_gt_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_gte_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -457,10 +457,10 @@ async fn test_gte_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_gte_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -488,10 +488,10 @@ async fn test_gte_no_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_gte_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -521,10 +521,10 @@ async fn test_gte_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_lt_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -553,10 +553,10 @@ async fn test_lt_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_lt_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -584,10 +584,10 @@ async fn test_lt_no_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_lt_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -618,10 +618,10 @@ async fn test_lt_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_lte_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -652,10 +652,10 @@ async fn test_lte_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_lte_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -683,10 +683,10 @@ async fn test_lte_no_match() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_lte_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -717,10 +717,10 @@ async fn test_lte_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_find_all_docs() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
   coll
     .insert(doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }))
@@ -746,3 +746,89 @@ async fn test_find_all_docs() -> Result<(), Error> {
 
   Ok(())
 }
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_query_comparison_and_equality_must_all_match() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
+
+  let docs = coll
+    .find(query!({ "age": { "$gt": 30 }, "name": "Bob" }))
+    .await?;
+  assert_eq!(docs.len(), 0);
+
+  let docs = coll
+    .find(query!({ "$or": [{ "age": 25 }, { "age": 20 }], "name": "Bob" }))
+    .await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Bob");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_query_compare_negative_and_float_numbers() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(doc!({ "name": "Rob", "balance": -5 })).await?;
+  coll.insert(doc!({ "name": "Bob", "balance": 2.5 })).await?;
+  coll.insert(doc!({ "name": "Tom", "balance": 10 })).await?;
+
+  let docs = coll.find(query!({ "balance": { "$gt": 3 } })).await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Tom");
+
+  let docs = coll.find(query!({ "balance": { "$gte": -1 } })).await?;
+  assert_eq!(docs.len(), 2);
+
+  let docs = coll.find(query!({ "balance": { "$lt": 2.5 } })).await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_compiled_query_reused() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+
+  let adults = CompiledQuery::new(&query!({ "age": { "$gte": 21 } }))?;
+  assert_eq!(coll.find_compiled(&adults).await?.len(), 1);
+
+  coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
+  coll.insert(doc!({ "name": "Tom", "age": 30 })).await?;
+  let docs = coll.find_compiled(&adults).await?;
+  assert_eq!(docs.len(), 2);
+  assert_eq!(docs[1]["name"], "Tom");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_query_in_nin_and_regex() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
+  coll.insert(doc!({ "name": "Tom", "age": 30 })).await?;
+
+  let docs = coll.find(query!({ "age": { "$in": [20, 30] } })).await?;
+  assert_eq!(docs.len(), 2);
+
+  let docs = coll.find(query!({ "age": { "$nin": [20, 30] } })).await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+
+  let docs = coll.find(query!({ "name": { "$regex": "ob$" } })).await?;
+  assert_eq!(docs.len(), 2);
+  Ok(())
+}
@@ -0,0 +1,40 @@
+#[cfg(feature = "async")]
+use memquery::{blocking, doc, errors::Error, query, r#async, update};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_blocking_and_async_share_collection() -> Result<(), Error> {
+  let memdb = blocking::MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+
+  let async_coll = r#async::Collection::from(coll.clone());
+  async_coll
+    .insert(doc!({ "name": "Bob", "age": 20 }))
+    .await?;
+  async_coll
+    .find_and_update(
+      query!({ "name": "Rob" }),
+      update!({ "$set": { "age": 26 } }),
+    )
+    .await?;
+
+  let docs = std::thread::spawn(move || coll.find(query!({ "age": { "$gt": 20 } })))
+    .join()
+    .unwrap()?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["age"], 26);
+
+  let async_memdb = r#async::MemDb::new();
+  async_memdb.create_collection("TestCollection").await?;
+  let coll = blocking::Collection::from(async_memdb.collection("TestCollection").await?);
+  coll.insert(doc!({ "name": "Tom" }))?;
+  let docs = async_memdb
+    .collection("TestCollection")
+    .await?
+    .find(query!({}))
+    .await?;
+  assert_eq!(docs.len(), 1);
+  Ok(())
+}
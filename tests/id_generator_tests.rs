@@ -0,0 +1,90 @@
+#[cfg(feature = "async")]
+use memquery::{
+  doc,
+  errors::Error,
+  id_generator::{AutoIncrement, ObjectId, Seeded},
+  query,
+  r#async::MemDb,
+  update,
+};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_insert_assigns_uuid_by_default() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(query!({ "name": "Rob" })).await?;
+  coll.insert(query!({ "_id": "rob", "name": "Rob" })).await?;
+
+  let docs = coll.find(query!({})).await?;
+  assert_eq!(docs[0]["_id"].as_str().map(|id| id.len()), Some(36));
+  assert_eq!(docs[1]["_id"], "rob");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_doc_macro_uses_id_generator() -> Result<(), Error> {
+  let memdb = MemDb::with_id_generator(AutoIncrement::new);
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection("Users").await?;
+  users.insert(doc!({ "name": "Rob" })).await?;
+
+  let docs = users.find(query!({ "name": "Rob" })).await?;
+  assert_eq!(docs[0]["_id"], 1);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_auto_increment_per_collection() -> Result<(), Error> {
+  let memdb = MemDb::with_id_generator(AutoIncrement::new);
+  memdb.create_collection("Users").await?;
+  memdb.create_collection("Orders").await?;
+  let users = memdb.collection("Users").await?;
+  let orders = memdb.collection("Orders").await?;
+  users.insert(query!({ "name": "Rob" })).await?;
+  users.insert(query!({ "name": "Bob" })).await?;
+  orders.insert(query!({ "item": "book" })).await?;
+
+  let docs = users.find(query!({ "_id": 2 })).await?;
+  assert_eq!(docs[0]["name"], "Bob");
+  let docs = orders.find(query!({})).await?;
+  assert_eq!(docs[0]["_id"], 1);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_seeded_ids_are_reproducible() -> Result<(), Error> {
+  let mut ids = Vec::new();
+  for _ in 0..2 {
+    let memdb = MemDb::with_id_generator(|| Seeded::new(7));
+    memdb.create_collection("TestCollection").await?;
+    let coll = memdb.collection("TestCollection").await?;
+    coll.insert(query!({ "name": "Rob" })).await?;
+    ids.push(coll.find(query!({})).await?[0]["_id"].clone());
+  }
+  assert_eq!(ids[0], ids[1]);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_replacement_keeps_generated_id() -> Result<(), Error> {
+  let memdb = MemDb::with_id_generator(ObjectId::new);
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(query!({ "name": "Rob" })).await?;
+  let id = coll.find(query!({})).await?[0]["_id"].clone();
+
+  coll
+    .find_and_update(query!({ "name": "Rob" }), update!({ "name": "Bob" }))
+    .await?;
+
+  let docs = coll.find(query!({ "_id": id })).await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Bob");
+  Ok(())
+}
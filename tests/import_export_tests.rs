@@ -0,0 +1,146 @@
+#[cfg(feature = "async")]
+use memquery::{
+  doc,
+  errors::Error,
+  import_export::{ErrorPolicy, ExportOptions, IdPolicy, ImportOptions},
+  index::IndexOptions,
+  query,
+  r#async::MemDb,
+};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_export_and_import_jsonl() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Source").await?;
+  memdb.create_collection("Target").await?;
+  let source = memdb.collection("Source").await?;
+  for age in 0..5 {
+    source.insert(doc!({ "name": "Rob", "age": age })).await?;
+  }
+
+  let mut output = Vec::new();
+  assert_eq!(
+    source
+      .export_jsonl(&mut output, ExportOptions::default())
+      .await?,
+    5
+  );
+  assert_eq!(
+    String::from_utf8(output.clone()).unwrap().lines().count(),
+    5
+  );
+
+  let target = memdb.collection("Target").await?;
+  let summary = target
+    .import_jsonl(&output[..], ImportOptions::default())
+    .await?;
+  assert_eq!(summary.inserted, 5);
+  assert!(summary.skipped.is_empty());
+  assert_eq!(
+    target.find(query!({})).await?,
+    source.find(query!({})).await?
+  );
+
+  let mut output = Vec::new();
+  let options = ExportOptions {
+    include_id: false,
+    ..Default::default()
+  };
+  source.export_jsonl(&mut output, options).await?;
+  assert!(!String::from_utf8(output).unwrap().contains("_id"));
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_export_and_import_json_array() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Source").await?;
+  memdb.create_collection("Target").await?;
+  let source = memdb.collection("Source").await?;
+
+  let mut output = Vec::new();
+  source
+    .export_json_array(&mut output, ExportOptions::default())
+    .await?;
+  assert_eq!(output, b"[]\n");
+
+  source
+    .insert(doc!({ "name": "Rob", "tags": ["a", "b"] }))
+    .await?;
+  source.insert(doc!({ "name": "Bob, Jr." })).await?;
+  let mut output = Vec::new();
+  source
+    .export_json_array(&mut output, ExportOptions::default())
+    .await?;
+  let exported: serde_json::Value = serde_json::from_slice(&output)?;
+  assert_eq!(exported.as_array().unwrap().len(), 2);
+
+  let target = memdb.collection("Target").await?;
+  let options = ImportOptions {
+    id: IdPolicy::Regenerate,
+    batch_size: 1,
+    ..Default::default()
+  };
+  assert_eq!(
+    target
+      .import_json_array(&output[..], options)
+      .await?
+      .inserted,
+    2
+  );
+  let imported = target.find(query!({})).await?;
+  assert_eq!(imported[1]["name"], "Bob, Jr.");
+  assert_ne!(imported[0]["_id"], source.find(query!({})).await?[0]["_id"]);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_import_error_policy() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection("Users").await?;
+  users
+    .create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  let input = r#"{"email": "rob@test.com"}
+{"email": "bob@test.com"
+{"email": "rob@test.com"}
+[1, 2]
+{"email": "tom@test.com"}
+"#;
+
+  let options = ImportOptions {
+    on_error: ErrorPolicy::Skip,
+    ..Default::default()
+  };
+  let summary = users.import_jsonl(input.as_bytes(), options).await?;
+  assert_eq!(summary.inserted, 2);
+  let lines: Vec<usize> = summary.skipped.iter().map(|s| s.line).collect();
+  assert_eq!(lines, vec![2, 3, 4]);
+  assert!(matches!(
+    summary.skipped[1].error,
+    Error::MQDuplicateKey { .. }
+  ));
+
+  users.find_and_delete(query!({})).await?;
+  let result = users
+    .import_jsonl(input.as_bytes(), ImportOptions::default())
+    .await;
+  assert!(matches!(result, Err(Error::MQImportError { line: 2, .. })));
+  assert_eq!(users.find(query!({})).await?.len(), 1);
+
+  let result = users
+    .import_json_array(&b"[{\"email\": \"x\"},\n 5]"[..], ImportOptions::default())
+    .await;
+  assert!(matches!(result, Err(Error::MQImportError { line: 2, .. })));
+  Ok(())
+}
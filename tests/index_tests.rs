@@ -0,0 +1,233 @@
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, index::IndexOptions, query, r#async::MemDb, update};
+#[cfg(feature = "async")]
+use serde_json::json;
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn duplicate_id_rejected() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(query!({ "_id": 1, "name": "Rob" })).await?;
+
+  match coll.insert(query!({ "_id": 1, "name": "Bob" })).await {
+    Err(Error::MQDuplicateKey { index, key }) => {
+      assert_eq!(index, "_id_");
+      assert_eq!(key, json!({ "_id": 1 }));
+    }
+    _ => assert_eq!("should get duplicate key error", "no error"),
+  }
+
+  let docs = coll.find(query!({})).await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn unique_index_rejects_duplicate() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  let name = coll
+    .create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  assert_eq!(name, "email_1");
+
+  coll
+    .insert(doc!({ "name": "Rob", "email": "rob@test.com" }))
+    .await?;
+  coll
+    .insert(doc!({ "name": "Bob", "email": "bob@test.com" }))
+    .await?;
+
+  match coll
+    .insert(doc!({ "name": "Tom", "email": "rob@test.com" }))
+    .await
+  {
+    Err(Error::MQDuplicateKey { index, key }) => {
+      assert_eq!(index, "email_1");
+      assert_eq!(key, json!({ "email": "rob@test.com" }));
+    }
+    _ => assert_eq!("should get duplicate key error", "no error"),
+  }
+
+  assert_eq!(coll.find(query!({})).await?.len(), 2);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn unique_index_on_existing_duplicates_fails() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+  coll.insert(doc!({ "name": "Bob", "age": 25 })).await?;
+
+  let result = coll
+    .create_index(
+      &["age"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await;
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  assert_eq!(coll.list_indexes().await.len(), 1);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn partial_unique_index() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll
+    .create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        partial_filter_expression: Some(query!({ "active": true })),
+        ..Default::default()
+      },
+    )
+    .await?;
+
+  coll
+    .insert(doc!({ "email": "rob@test.com", "active": true }))
+    .await?;
+  coll
+    .insert(doc!({ "email": "rob@test.com", "active": false }))
+    .await?;
+
+  let result = coll
+    .insert(doc!({ "email": "rob@test.com", "active": true }))
+    .await;
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn update_creating_duplicate_rejected() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll
+    .create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  coll
+    .insert(doc!({ "name": "Rob", "email": "rob@test.com" }))
+    .await?;
+  coll
+    .insert(doc!({ "name": "Bob", "email": "bob@test.com" }))
+    .await?;
+
+  let result = coll
+    .find_and_update(
+      query!({ "name": "Bob" }),
+      update!({ "$set": { "email": "rob@test.com" } }),
+    )
+    .await;
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+
+  let docs = coll.find(query!({ "name": "Bob" })).await?;
+  assert_eq!(docs[0]["email"], "bob@test.com");
+
+  // swapping to a key that is no longer used is allowed
+  coll
+    .find_and_update(
+      query!({ "name": "Rob" }),
+      update!({ "$set": { "email": "robert@test.com" } }),
+    )
+    .await?;
+  coll
+    .find_and_update(
+      query!({ "name": "Bob" }),
+      update!({ "$set": { "email": "rob@test.com" } }),
+    )
+    .await?;
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn update_multiple_documents_to_same_key_rejected() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll
+    .create_index(
+      &["code"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  coll.insert(doc!({ "code": 1, "group": "a" })).await?;
+  coll.insert(doc!({ "code": 2, "group": "a" })).await?;
+
+  let result = coll
+    .find_and_update(query!({ "group": "a" }), update!({ "$set": { "code": 3 } }))
+    .await;
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  assert_eq!(coll.find(query!({ "code": 3 })).await?.len(), 0);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn deleted_key_can_be_reused() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.insert(query!({ "_id": 1, "name": "Rob" })).await?;
+  coll.find_and_delete(query!({ "_id": 1 })).await?;
+  coll.insert(query!({ "_id": 1, "name": "Bob" })).await?;
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn drop_index() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll
+    .create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  coll.drop_index("email_1").await?;
+  coll.insert(doc!({ "email": "rob@test.com" })).await?;
+  coll.insert(doc!({ "email": "rob@test.com" })).await?;
+
+  assert!(matches!(
+    coll.drop_index("email_1").await,
+    Err(Error::MQIndexNotFound(_))
+  ));
+  assert!(coll.drop_index("_id_").await.is_err());
+  Ok(())
+}
@@ -0,0 +1,106 @@
+#[cfg(feature = "async")]
+use memquery::{
+  doc, errors::Error, id_generator::AutoIncrement, index::IndexOptions, query, r#async::MemDb,
+};
+#[cfg(feature = "async")]
+use std::path::PathBuf;
+
+#[cfg(feature = "async")]
+fn snapshot_path(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("memquery-{}-{}.json", name, uuid::Uuid::new_v4()))
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_save_and_load() -> Result<(), Error> {
+  let path = snapshot_path("roundtrip");
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  memdb.create_collection("Empty").await?;
+  let users = memdb.collection("Users").await?;
+  users
+    .create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  users
+    .insert(doc!({ "name": "Rob", "email": "rob@test.com" }))
+    .await?;
+  users
+    .insert(doc!({ "name": "Bob", "email": "bob@test.com" }))
+    .await?;
+  memdb.save_to(&path).await?;
+
+  let restored = MemDb::new();
+  restored.load_from(&path).await?;
+  std::fs::remove_file(&path)?;
+
+  let restored_users = restored.collection("Users").await?;
+  assert_eq!(
+    restored_users.find(query!({})).await?,
+    users.find(query!({})).await?
+  );
+  assert_eq!(
+    restored_users.list_indexes().await,
+    users.list_indexes().await
+  );
+  assert!(restored
+    .collection("Empty")
+    .await?
+    .find(query!({}))
+    .await?
+    .is_empty());
+
+  let result = restored_users
+    .insert(doc!({ "name": "Tom", "email": "rob@test.com" }))
+    .await;
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_load_errors() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+
+  let result = memdb.load_from(snapshot_path("missing")).await;
+  assert!(matches!(result, Err(Error::IOError(_))));
+
+  let path = snapshot_path("invalid");
+  std::fs::write(
+    &path,
+    r#"{"format": "memquery-snapshot", "version": 99, "collections": {}}"#,
+  )?;
+  assert!(memdb.load_from(&path).await.is_err());
+  std::fs::remove_file(&path)?;
+
+  assert!(memdb.collection("Users").await.is_ok());
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_auto_increment_continues_after_load() -> Result<(), Error> {
+  let path = snapshot_path("autoincrement");
+  let memdb = MemDb::with_id_generator(AutoIncrement::new);
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection("Users").await?;
+  users.insert(query!({ "name": "Rob" })).await?;
+  users.insert(query!({ "name": "Bob" })).await?;
+  memdb.save_to(&path).await?;
+
+  let restored = MemDb::with_id_generator(AutoIncrement::new);
+  restored.load_from(&path).await?;
+  std::fs::remove_file(&path)?;
+
+  let users = restored.collection("Users").await?;
+  users.insert(query!({ "name": "Tom" })).await?;
+  let docs = users.find(query!({ "name": "Tom" })).await?;
+  assert_eq!(docs[0]["_id"], 3);
+  Ok(())
+}
@@ -0,0 +1,23 @@
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, query, r#async::MemDb};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_stats() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection("Users").await?;
+  users.insert(doc!({ "name": "Tom" })).await?;
+  users.find(query!({ "name": "Tom" })).await?;
+
+  let stats = users.stats().await;
+  assert_eq!(stats.count, 1);
+  assert!(stats.size > 0);
+  assert_eq!(stats.operations.inserts, 1);
+  assert_eq!(stats.operations.queries, 1);
+
+  let db_stats = memdb.stats().await;
+  assert_eq!(db_stats.collections, 1);
+  assert_eq!(db_stats.data_size, stats.size);
+  Ok(())
+}
@@ -0,0 +1,102 @@
+#[cfg(feature = "bson")]
+use bson::{doc, oid::ObjectId, spec::BinarySubtype, Binary, Bson, DateTime, Decimal128};
+#[cfg(feature = "bson")]
+use memquery::{
+  blocking::MemDb,
+  errors::Error,
+  extended_json::date,
+  import_export::{ErrorPolicy, ExportOptions, ImportOptions},
+  index::IndexOptions,
+  query,
+};
+
+#[test]
+#[cfg(feature = "bson")]
+fn test_insert_and_find_bson() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Files")?;
+  let coll = memdb.collection("Files")?;
+  let id = ObjectId::new();
+  let document = doc! {
+    "_id": id,
+    "uploaded": DateTime::from_millis(1_593_561_600_000),
+    "size": "1024.50".parse::<Decimal128>().unwrap(),
+    "data": Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+    "views": 9_007_199_254_740_993_i64,
+  };
+  coll.insert_bson(document.clone())?;
+
+  let docs = coll.find_bson(query!({ "uploaded": { "$gte": date(1_593_561_600_000) } }))?;
+  assert_eq!(docs, vec![document]);
+  let docs = coll.find(query!({ "_id": { "$oid": id.to_hex() } }))?;
+  assert_eq!(docs[0]["size"]["$numberDecimal"], "1024.50");
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "bson")]
+fn test_export_and_import_bson() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Source")?;
+  memdb.create_collection("Target")?;
+  let source = memdb.collection("Source")?;
+  for age in 0..3 {
+    source.insert_bson(doc! { "_id": ObjectId::new(), "age": age })?;
+  }
+
+  let mut output = Vec::new();
+  let exported = source.export_bson(&mut output, ExportOptions::default())?;
+  assert_eq!(exported, 3);
+
+  output.extend(&[5, 0, 0, 0, 1]);
+  let target = memdb.collection("Target")?;
+  let options = ImportOptions {
+    on_error: ErrorPolicy::Skip,
+    ..Default::default()
+  };
+  let summary = target.import_bson(&output[..], options)?;
+  assert_eq!(summary.inserted, 3);
+  assert_eq!(summary.skipped.len(), 1);
+  assert_eq!(summary.skipped[0].line, 4);
+  assert_eq!(target.find(query!({}))?, source.find(query!({}))?);
+
+  output.truncate(output.len() - 6);
+  assert!(target
+    .import_bson(&output[..], ImportOptions::default())
+    .is_err());
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "bson")]
+fn test_dump_and_restore() -> Result<(), Error> {
+  let dir = std::env::temp_dir().join(format!("memquery-dump-{}", uuid::Uuid::new_v4()));
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  users.create_index(
+    &["email"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  users.insert_bson(doc! { "email": "rob@test.com", "joined": DateTime::now() })?;
+  memdb.dump_bson(&dir)?;
+  assert!(dir.join("Users.bson").exists());
+  assert!(dir.join("Users.metadata.json").exists());
+
+  let restored = MemDb::new();
+  restored.restore_bson(&dir)?;
+  let restored_users = restored.collection("Users")?;
+  assert_eq!(restored_users.list_indexes(), users.list_indexes());
+  let docs = restored_users.find_bson(query!({}))?;
+  assert_eq!(docs, users.find_bson(query!({}))?);
+  assert!(matches!(docs[0].get("joined"), Some(Bson::DateTime(_))));
+  assert!(restored_users
+    .insert_bson(doc! { "email": "rob@test.com" })
+    .is_err());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
@@ -0,0 +1,57 @@
+use memquery::{
+  blocking::MemDb,
+  builder::{Filter, Update, UpdateOps},
+  doc,
+  errors::Error,
+};
+
+#[test]
+fn test_find_with_filter() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25, "tags": ["a", "b"] }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 20, "tags": ["a"] }))?;
+  coll.insert(doc!({ "name": "Tom", "age": 30, "tags": ["b"] }))?;
+
+  let filter = Filter::field("age")
+    .gte(21)
+    .and(Filter::field("tags").all(vec!["a"]));
+  let docs = coll.find(filter.into())?;
+
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+
+  let docs = coll.find(
+    Filter::field("name")
+      .eq("Bob")
+      .or(Filter::field("name").regex("^t"))
+      .into(),
+  )?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Bob");
+  Ok(())
+}
+
+#[test]
+fn test_update_with_builder() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25, "email": "rob@test.com" }))?;
+
+  let docs_updated = coll.find_and_update(
+    Filter::field("name").eq("Rob").into(),
+    Update::set("nickname", "Bobcat")
+      .inc("age", 1)
+      .unset("email")
+      .into(),
+  )?;
+  assert_eq!(docs_updated, 1);
+
+  let docs = coll.find(Filter::field("nickname").eq("Bobcat").into())?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["age"], 26.0);
+  assert_eq!(docs[0]["email"], serde_json::Value::Null);
+  Ok(())
+}
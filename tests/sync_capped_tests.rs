@@ -0,0 +1,121 @@
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::Error,
+  options::{Capped, CollectionOptions, FindOptions},
+  query,
+};
+use serde_json::json;
+use std::time::Duration;
+
+fn capped(max_docs: Option<u64>, max_bytes: Option<u64>) -> CollectionOptions {
+  CollectionOptions {
+    capped: Some(Capped {
+      max_docs,
+      max_bytes,
+    }),
+    ..Default::default()
+  }
+}
+
+#[test]
+fn test_max_docs_evicts_oldest() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection_with_options("Log", capped(Some(3), None))?;
+  let log = memdb.collection("Log")?;
+  for n in 0..5 {
+    log.insert(doc!({ "n": n }))?;
+  }
+  let numbers: Vec<_> = log
+    .find(query!({}))?
+    .iter()
+    .map(|d| d["n"].clone())
+    .collect();
+  assert_eq!(numbers, vec![2, 3, 4]);
+  assert_eq!(log.options(), capped(Some(3), None));
+  Ok(())
+}
+
+#[test]
+fn test_max_bytes_evicts_oldest() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  let size = json!({ "_id": 0, "text": "aaaa" }).to_string().len() as u64;
+  memdb.create_collection_with_options("Log", capped(None, Some(size * 2)))?;
+  let log = memdb.collection("Log")?;
+  for n in 0..4 {
+    log.insert(json!({ "_id": n, "text": "aaaa" }))?;
+  }
+  let ids: Vec<_> = log
+    .find(query!({}))?
+    .iter()
+    .map(|d| d["_id"].clone())
+    .collect();
+  assert_eq!(ids, vec![2, 3]);
+
+  let result = log.insert(json!({ "_id": 9, "text": "a".repeat(size as usize * 2) }));
+  assert!(matches!(result, Err(Error::MQInvalidValue(_))));
+  assert_eq!(log.find(query!({}))?.len(), 2);
+  Ok(())
+}
+
+#[test]
+fn test_find_newest_first() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection_with_options("Log", capped(Some(10), None))?;
+  let log = memdb.collection("Log")?;
+  for n in 0..5 {
+    log.insert(doc!({ "n": n }))?;
+  }
+  let options = FindOptions {
+    newest_first: true,
+    limit: Some(2),
+  };
+  let numbers: Vec<_> = log
+    .find_with_options(query!({}), options)?
+    .iter()
+    .map(|d| d["n"].clone())
+    .collect();
+  assert_eq!(numbers, vec![4, 3]);
+  Ok(())
+}
+
+#[test]
+fn test_tailable_cursor() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection_with_options("Log", capped(Some(10), None))?;
+  let log = memdb.collection("Log")?;
+  log.insert(doc!({ "level": "error", "n": 1 }))?;
+  log.insert(doc!({ "level": "info", "n": 2 }))?;
+
+  let mut cursor = log.tail(query!({ "level": "error" }))?;
+  assert_eq!(cursor.next_document()?["n"], 1);
+  assert!(cursor.try_next()?.is_none());
+
+  let writer = log.clone();
+  let handle = std::thread::spawn(move || {
+    writer.insert(doc!({ "level": "info", "n": 3 })).unwrap();
+    writer.insert(doc!({ "level": "error", "n": 4 })).unwrap();
+  });
+  let document = cursor.next_timeout(Duration::from_secs(5))?.unwrap();
+  assert_eq!(document["n"], 4);
+  handle.join().unwrap();
+
+  memdb.delete_collection("Log")?;
+  assert!(cursor.next().is_none());
+  Ok(())
+}
+
+#[test]
+fn test_invalid_capped_options() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  assert!(memdb
+    .create_collection_with_options("Log", capped(None, None))
+    .is_err());
+  assert!(memdb
+    .create_collection_with_options("Log", capped(Some(0), None))
+    .is_err());
+  memdb.create_collection("Plain")?;
+  let plain = memdb.collection("Plain")?;
+  assert!(matches!(plain.tail(query!({})), Err(Error::MQInvalidOp(_))));
+  Ok(())
+}
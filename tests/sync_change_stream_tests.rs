@@ -0,0 +1,140 @@
+use memquery::{
+  blocking::MemDb,
+  change_stream::{OperationType, WatchOptions, CHANGE_HISTORY},
+  doc,
+  errors::Error,
+  query, update,
+};
+use serde_json::json;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_watch_insert_update_delete() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  let mut changes = coll.watch(query!({}))?;
+
+  coll.insert(json!({ "_id": 1, "name": "Rob", "age": 25, "city": "NY" }))?;
+  coll.find_and_update(
+    query!({ "name": "Rob" }),
+    update!({ "$set": { "age": 26 }, "$unset": { "city": "" } }),
+  )?;
+  coll.find_and_delete(query!({ "name": "Rob" }))?;
+
+  let insert = changes.recv()?;
+  assert_eq!(insert.operation_type, OperationType::Insert);
+  assert_eq!(insert.document_key, json!({ "_id": 1 }));
+  assert_eq!(insert.full_document.unwrap()["age"], 25);
+
+  let update = changes.recv()?;
+  assert_eq!(update.operation_type, OperationType::Update);
+  assert_eq!(update.full_document.unwrap()["age"], 26);
+  let description = update.update_description.unwrap();
+  assert_eq!(description.updated_fields["age"], 26);
+  assert_eq!(description.removed_fields, vec!["city"]);
+
+  let delete = changes.recv()?;
+  assert_eq!(delete.operation_type, OperationType::Delete);
+  assert_eq!(delete.full_document_before_change.unwrap()["name"], "Rob");
+  assert!(insert.resume_token < update.resume_token);
+  assert!(update.resume_token < delete.resume_token);
+  assert!(changes.try_recv()?.is_none());
+  Ok(())
+}
+
+#[test]
+fn test_watch_filter_and_resume() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  let mut changes = coll.watch(query!({ "age": { "$gte": 30 } }))?;
+
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Tom", "age": 30 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 35 }))?;
+
+  let first = changes.recv()?;
+  assert_eq!(first.full_document.unwrap()["name"], "Tom");
+  assert_eq!(changes.recv()?.full_document.unwrap()["name"], "Bob");
+  assert!(changes.recv_timeout(Duration::from_millis(10))?.is_none());
+
+  let mut resumed = coll.watch_with_options(
+    query!({}),
+    WatchOptions {
+      resume_after: Some(first.resume_token),
+    },
+  )?;
+  assert_eq!(resumed.recv()?.full_document.unwrap()["name"], "Bob");
+  Ok(())
+}
+
+#[test]
+fn test_watch_from_other_thread() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  let changes = coll.watch(query!({}))?;
+
+  let watcher = thread::spawn(move || changes.collect::<Result<Vec<_>, _>>().unwrap().len());
+  for age in 0..10 {
+    coll.insert(doc!({ "age": age }))?;
+  }
+  memdb.delete_collection("TestCollection")?;
+  assert_eq!(watcher.join().unwrap(), 10);
+  Ok(())
+}
+
+#[test]
+fn test_watch_lagging_consumer() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  let mut changes = coll.watch(query!({}))?;
+
+  for age in 0..CHANGE_HISTORY + 5 {
+    coll.insert(doc!({ "age": age }))?;
+  }
+  assert!(matches!(
+    changes.recv(),
+    Err(Error::MQChangeStreamLagged(5))
+  ));
+  assert_eq!(changes.recv()?.full_document.unwrap()["age"], 5);
+  Ok(())
+}
+
+#[test]
+fn test_watch_transaction() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Pending")?;
+  memdb.create_collection("Done")?;
+  memdb
+    .collection("Pending")?
+    .insert(doc!({ "name": "Rob" }))?;
+  let mut pending = memdb.collection("Pending")?.watch(query!({}))?;
+  let mut done = memdb.collection("Done")?.watch(query!({}))?;
+
+  let failed = memdb.transaction(&["Pending"], |tx| {
+    tx.collection("Pending")?
+      .find_and_delete(query!({ "name": "Rob" }))?;
+    tx.collection("Missing")?;
+    Ok(())
+  });
+  assert!(failed.is_err());
+  assert!(pending.try_recv()?.is_none());
+
+  memdb.transaction(&["Pending", "Done"], |tx| {
+    for document in tx
+      .collection("Pending")?
+      .find_and_delete(query!({ "name": "Rob" }))?
+    {
+      tx.collection("Done")?.insert(document)?;
+    }
+    Ok(())
+  })?;
+  assert_eq!(pending.recv()?.operation_type, OperationType::Delete);
+  assert_eq!(done.recv()?.operation_type, OperationType::Insert);
+  assert!(pending.try_recv()?.is_none());
+  Ok(())
+}
@@ -0,0 +1,93 @@
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::{codes, Error},
+  index::IndexOptions,
+  options::CollectionOptions,
+  query,
+};
+
+#[test]
+fn test_create_existing_collection_fails() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  memdb.collection("Users")?.insert(doc!({ "name": "Tom" }))?;
+
+  let error = memdb.create_collection("Users").unwrap_err();
+  assert_eq!(error.code(), codes::NAMESPACE_EXISTS);
+  assert_eq!(error.to_string(), "Collection Users already exists");
+  assert_eq!(memdb.collection("Users")?.find(query!({}))?.len(), 1);
+
+  memdb.replace_collection("Users", CollectionOptions::default())?;
+  assert!(memdb.collection("Users")?.find(query!({}))?.is_empty());
+  Ok(())
+}
+
+#[test]
+fn test_list_and_exists() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  assert!(memdb.list_collection_names().is_empty());
+  memdb.create_collection("Users")?;
+  memdb.create_collection("Orders")?;
+
+  assert_eq!(memdb.list_collection_names(), vec!["Orders", "Users"]);
+  assert!(memdb.collection_exists("Users"));
+  assert!(!memdb.collection_exists("Items"));
+
+  memdb.delete_collection("Users")?;
+  assert_eq!(memdb.list_collection_names(), vec!["Orders"]);
+  Ok(())
+}
+
+#[test]
+fn test_rename_keeps_documents_and_indexes() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  users.create_index(
+    &["name"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  users.insert(doc!({ "name": "Tom" }))?;
+
+  memdb.rename_collection("Users", "People", false)?;
+  assert!(!memdb.collection_exists("Users"));
+  let people = memdb.collection("People")?;
+  assert_eq!(people.find(query!({ "name": "Tom" }))?.len(), 1);
+  assert_eq!(
+    people.insert(doc!({ "name": "Tom" })).unwrap_err().code(),
+    codes::DUPLICATE_KEY
+  );
+
+  match memdb.rename_collection("Users", "Others", false) {
+    Err(Error::MQCollectionNotFound(name)) => assert_eq!(name, "Users"),
+    _ => panic!("missing collection was renamed"),
+  }
+  Ok(())
+}
+
+#[test]
+fn test_rename_to_existing_collection() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  memdb.create_collection("People")?;
+  memdb.collection("Users")?.insert(doc!({ "name": "Tom" }))?;
+  memdb
+    .collection("People")?
+    .insert(doc!({ "name": "Bob" }))?;
+
+  match memdb.rename_collection("Users", "People", false) {
+    Err(Error::MQCollectionExists(name)) => assert_eq!(name, "People"),
+    _ => panic!("existing collection was replaced"),
+  }
+
+  memdb.rename_collection("Users", "People", true)?;
+  assert_eq!(memdb.list_collection_names(), vec!["People"]);
+  let docs = memdb.collection("People")?.find(query!({}))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Tom");
+  Ok(())
+}
@@ -0,0 +1,80 @@
+use memquery::{
+  blocking::MemDb, clock::ManualClock, doc, errors::Error, extended_json::date,
+  index::IndexOptions, options::CountOptions, query,
+};
+use std::{sync::Arc, time::Duration};
+
+#[test]
+fn test_count_documents() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  for age in 20..30 {
+    users.insert(doc!({ "age": age }))?;
+  }
+
+  let adults = query!({ "age": { "$gte": 25 } });
+  assert_eq!(
+    users.count_documents(adults.clone(), CountOptions::default())?,
+    5
+  );
+  assert_eq!(
+    users.count_documents(query!({ "age": 40 }), CountOptions::default())?,
+    0
+  );
+  assert!(users
+    .count_documents(query!({ "age": { "$gtee": 1 } }), CountOptions::default())
+    .is_err());
+  Ok(())
+}
+
+#[test]
+fn test_count_documents_skip_and_limit() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  for age in 20..30 {
+    users.insert(doc!({ "age": age }))?;
+  }
+
+  let count = |skip, limit| {
+    users.count_documents(
+      query!({ "age": { "$gte": 25 } }),
+      CountOptions { skip, limit },
+    )
+  };
+  assert_eq!(count(2, None)?, 3);
+  assert_eq!(count(0, Some(2))?, 2);
+  assert_eq!(count(4, Some(2))?, 1);
+  assert_eq!(count(10, None)?, 0);
+  Ok(())
+}
+
+#[test]
+fn test_estimated_document_count() -> Result<(), Error> {
+  let clock = Arc::new(ManualClock::new(1_000_000));
+  let memdb = MemDb::new().with_clock(clock.clone());
+  memdb.create_collection("Sessions")?;
+  let sessions = memdb.collection("Sessions")?;
+  assert_eq!(sessions.estimated_document_count(), 0);
+  sessions.create_index(
+    &["created"],
+    IndexOptions {
+      expire_after: Some(Duration::from_secs(60)),
+      ..Default::default()
+    },
+  )?;
+  sessions.insert(doc!({ "created": date(1_000_000) }))?;
+  sessions.insert(doc!({ "created": date(1_050_000) }))?;
+  assert_eq!(sessions.estimated_document_count(), 2);
+
+  clock.advance(Duration::from_secs(61));
+  assert_eq!(sessions.estimated_document_count(), 2);
+  assert_eq!(
+    sessions.count_documents(query!({}), CountOptions::default())?,
+    1
+  );
+  sessions.purge_expired()?;
+  assert_eq!(sessions.estimated_document_count(), 1);
+  Ok(())
+}
@@ -0,0 +1,127 @@
+use memquery::{blocking::MemDb, errors::Error, query, Document};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Document)]
+#[document(collection = "users")]
+#[document(index(keys("last_name", "first_name"), name = "full_name"))]
+struct User {
+  id: Option<String>,
+  first_name: String,
+  last_name: String,
+  #[document(index(unique))]
+  email: String,
+  age: u32,
+  tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Document)]
+#[serde(rename_all = "camelCase")]
+struct Order {
+  #[serde(rename = "_id")]
+  order_id: String,
+  #[document(index)]
+  customer_name: String,
+  #[serde(skip)]
+  cached: bool,
+}
+
+fn user(first_name: &str, email: &str, age: u32) -> User {
+  User {
+    id: None,
+    first_name: first_name.to_string(),
+    last_name: "Smith".to_string(),
+    email: email.to_string(),
+    age,
+    tags: vec!["a".to_string()],
+  }
+}
+
+#[test]
+fn test_generated_constants() {
+  assert_eq!(User::COLLECTION_NAME, "users");
+  assert_eq!(User::ID_FIELD, "id");
+  assert_eq!(User::ID.path(), "_id");
+  assert_eq!(User::FIRST_NAME.path(), "first_name");
+  assert_eq!(Order::COLLECTION_NAME, "Order");
+  assert_eq!(Order::ID_FIELD, "_id");
+  assert_eq!(Order::ORDER_ID.path(), "_id");
+  assert_eq!(Order::CUSTOMER_NAME.path(), "customerName");
+
+  let indexes = User::indexes();
+  assert_eq!(indexes.len(), 2);
+  assert_eq!(indexes[0].keys, vec!["email"]);
+  assert!(indexes[0].options.unique);
+  assert_eq!(indexes[1].keys, vec!["last_name", "first_name"]);
+  assert_eq!(indexes[1].options.name, Some("full_name".to_string()));
+}
+
+#[test]
+fn test_id_generated_and_mapped() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  let users = memdb.create_collection_for::<User>()?;
+  users.insert(user("Rob", "rob@test.com", 25))?;
+
+  let stored = memdb.collection("users")?.find(query!({}))?;
+  assert!(stored[0]["_id"].is_string());
+  assert!(stored[0].get("id").is_none());
+
+  let rob = users
+    .find_one(User::EMAIL.eq("rob@test.com".to_string())?.into())?
+    .unwrap();
+  assert_eq!(
+    rob.id.as_ref(),
+    stored[0]["_id"].as_str().map(|s| s.to_string()).as_ref()
+  );
+
+  let by_id = users.find(User::ID.eq(rob.id.clone())?.into())?;
+  assert_eq!(by_id, vec![rob]);
+  Ok(())
+}
+
+#[test]
+fn test_typed_filters_and_indexes() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  let users = memdb.create_collection_for::<User>()?;
+  users.insert(user("Rob", "rob@test.com", 25))?;
+  users.insert(user("Bob", "bob@test.com", 20))?;
+
+  let found = users.find(
+    User::AGE
+      .gte(21)?
+      .and(User::TAGS.all(vec!["a".to_string()])?)
+      .into(),
+  )?;
+  assert_eq!(found.len(), 1);
+  assert_eq!(found[0].first_name, "Rob");
+
+  let result = users.insert(user("Tom", "rob@test.com", 30));
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+
+  let names: Vec<String> = memdb
+    .collection("users")?
+    .list_indexes()
+    .into_iter()
+    .map(|i| i.name)
+    .collect();
+  assert_eq!(names, vec!["_id_", "email_1", "full_name"]);
+  Ok(())
+}
+
+#[test]
+fn test_explicit_id_kept() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  let orders = memdb.create_collection_for::<Order>()?;
+  let order = Order {
+    order_id: "order-1".to_string(),
+    customer_name: "Rob".to_string(),
+    cached: false,
+  };
+  orders.insert(order.clone())?;
+
+  let found = memdb
+    .collection_for::<Order>()?
+    .find(Order::CUSTOMER_NAME.eq("Rob".to_string())?.into())?;
+  assert_eq!(found, vec![order.clone()]);
+  assert!(orders.insert(order).is_err());
+  Ok(())
+}
@@ -0,0 +1,84 @@
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::{codes, Error},
+  index::IndexOptions,
+  query, update,
+};
+use serde_json::json;
+
+#[test]
+fn test_invalid_operator_is_described() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+
+  let error = users.find(query!({ "age": { "$gtee": 21 } })).unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "Invalid operator: $gtee is not supported."
+  );
+  assert_eq!(error.code(), codes::BAD_VALUE);
+  Ok(())
+}
+
+#[test]
+fn test_type_mismatch_has_path() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  users.insert(doc!({ "name": "Tom", "age": "25", "profile": { "age": "25" } }))?;
+
+  let error = users.find(query!({ "age": { "$gt": 21 } })).unwrap_err();
+  assert_eq!(error.code(), codes::TYPE_MISMATCH);
+  assert_eq!(error.path(), Some("age"));
+  assert_eq!(
+    error.to_json(),
+    json!({
+      "code": 14,
+      "message": "Type mismatch at age: $gt cannot compare \"25\" with 21",
+      "path": "age",
+    })
+  );
+
+  let error = users
+    .find_and_update(query!({}), update!({ "$inc": { "name": 1 } }))
+    .unwrap_err();
+  assert_eq!(error.path(), Some("name"));
+
+  let error = users
+    .find_and_update(query!({}), update!({ "$inc": { "profile.age": 1 } }))
+    .unwrap_err();
+  assert_eq!(error.path(), Some("profile.age"));
+  Ok(())
+}
+
+#[test]
+fn test_errors_name_what_was_not_found() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  users.create_index(
+    &["email"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  users.insert(doc!({ "email": "tom@example.com" }))?;
+
+  let error = users
+    .insert(doc!({ "email": "tom@example.com" }))
+    .unwrap_err();
+  assert_eq!(error.code(), codes::DUPLICATE_KEY);
+  assert!(error.to_string().contains("tom@example.com"));
+
+  let error = memdb.collection("Orders").err().unwrap();
+  assert_eq!(error.to_string(), "Collection Orders not found");
+  assert_eq!(error.code(), codes::NAMESPACE_NOT_FOUND);
+  assert_eq!(error.to_json()["path"], json!(null));
+
+  let error = users.drop_index("age_1").unwrap_err();
+  assert_eq!(error.to_string(), "Index age_1 not found");
+  Ok(())
+}
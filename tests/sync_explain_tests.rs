@@ -0,0 +1,116 @@
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::Error,
+  index::IndexOptions,
+  planner::{ExplainOptions, ExplainVerbosity},
+  query,
+};
+
+#[test]
+fn explain_collection_scan() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
+  coll.insert(doc!({ "name": "Tom", "age": 30 }))?;
+
+  let explain = coll.explain(query!({ "age": { "$gte": 25 } }), ExplainOptions::default())?;
+
+  assert_eq!(explain["queryPlanner"]["winningPlan"]["stage"], "COLLSCAN");
+  assert_eq!(explain["executionStats"]["nReturned"], 2);
+  assert_eq!(explain["executionStats"]["totalDocsExamined"], 3);
+  assert_eq!(explain["executionStats"]["totalKeysExamined"], 0);
+  Ok(())
+}
+
+#[test]
+fn explain_index_scan() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.create_index(&["age"], IndexOptions::default())?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
+  coll.insert(doc!({ "name": "Tom", "age": 30 }))?;
+  coll.insert(doc!({ "name": "Victor", "age": 35 }))?;
+
+  let explain = coll.explain(
+    query!({ "$and": [{ "age": { "$gt": 20 } }, { "age": { "$lte": 30 } }] }),
+    ExplainOptions::default(),
+  )?;
+
+  let plan = &explain["queryPlanner"]["winningPlan"];
+  assert_eq!(plan["stage"], "FETCH");
+  assert_eq!(plan["inputStage"]["stage"], "IXSCAN");
+  assert_eq!(plan["inputStage"]["indexName"], "age_1");
+  assert_eq!(plan["inputStage"]["indexBounds"]["age"]["lower"], 20);
+  assert_eq!(
+    plan["inputStage"]["indexBounds"]["age"]["lowerInclusive"],
+    false
+  );
+  assert_eq!(plan["inputStage"]["indexBounds"]["age"]["upper"], 30);
+  assert_eq!(explain["executionStats"]["nReturned"], 2);
+  assert_eq!(explain["executionStats"]["totalDocsExamined"], 2);
+  Ok(())
+}
+
+#[test]
+fn explain_id_lookup_uses_id_index() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(query!({ "_id": 1, "name": "Rob" }))?;
+  coll.insert(query!({ "_id": 2, "name": "Bob" }))?;
+
+  let explain = coll.explain(
+    query!({ "_id": 2 }),
+    ExplainOptions {
+      verbosity: ExplainVerbosity::QueryPlanner,
+    },
+  )?;
+
+  assert_eq!(
+    explain["queryPlanner"]["winningPlan"]["inputStage"]["indexName"],
+    "_id_"
+  );
+  assert!(explain.get("executionStats").is_none());
+  Ok(())
+}
+
+#[test]
+fn index_scan_returns_same_documents() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "item": { "name": "ab" }, "qty": 15, "tags": ["A", "B", "C"] }))?;
+  coll.insert(doc!({ "item": { "name": "cd" }, "qty": 20, "tags": ["B"] }))?;
+  coll.insert(doc!({ "item": { "name": "ij" }, "qty": 25, "tags": ["A", "B"] }))?;
+  coll.insert(doc!({ "item": { "name": "xy" }, "qty": 30, "tags": ["B", "A"] }))?;
+
+  let queries = [
+    query!({ "qty": { "$gt": 20 } }),
+    query!({ "qty": { "$lt": 25 } }),
+    query!({ "qty": 20 }),
+    query!({ "tags": { "$eq": "A" } }),
+    query!({ "item.name": "ij" }),
+    query!({ "item.name": { "$gte": "cd" }, "qty": { "$lte": 25 } }),
+  ];
+
+  let mut expected = Vec::new();
+  for query in queries.iter() {
+    expected.push(coll.find(query.clone())?);
+  }
+
+  coll.create_index(&["qty"], IndexOptions::default())?;
+  coll.create_index(&["tags"], IndexOptions::default())?;
+  coll.create_index(&["item.name"], IndexOptions::default())?;
+
+  for (query, expected) in queries.iter().zip(expected.iter()) {
+    let explain = coll.explain(query.clone(), ExplainOptions::default())?;
+    assert_eq!(explain["queryPlanner"]["winningPlan"]["stage"], "FETCH");
+    assert_eq!(&coll.find(query.clone())?, expected);
+  }
+  Ok(())
+}
@@ -0,0 +1,115 @@
+use memquery::{
+  blocking::MemDb,
+  errors::Error,
+  extended_json::{date, number_long, ExtendedJsonMode},
+  import_export::{ExportOptions, ImportOptions},
+  index::IndexOptions,
+  query,
+};
+
+#[test]
+fn test_date_range_query() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Events")?;
+  let coll = memdb.collection("Events")?;
+  for (name, created) in [
+    ("june", "2020-06-30T23:59:59.999Z"),
+    ("july", "2020-07-01T00:00:00Z"),
+    ("august", "2020-08-15T10:00:00+02:00"),
+  ] {
+    coll.insert(query!({ "name": name, "created": { "$date": created } }))?;
+  }
+
+  let docs = coll.find(query!({ "created": {
+      "$gte": { "$date": "2020-07-01T00:00:00Z" },
+      "$lt": { "$date": { "$numberLong": "1598918400000" } },
+    } }))?;
+  assert_eq!(docs.len(), 2);
+
+  coll.create_index(&["created"], IndexOptions::default())?;
+  let docs = coll.find(query!({ "created": { "$lt": date(1_593_561_600_000) } }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "june");
+
+  let docs = coll.find(query!({ "created": { "$date": "2020-07-01T00:00:00.000Z" } }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "july");
+  Ok(())
+}
+
+#[test]
+fn test_typed_values_compare_by_value() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Stats")?;
+  let coll = memdb.collection("Stats")?;
+  coll.create_index(&["views"], IndexOptions::default())?;
+  coll.insert(query!({
+    "_id": { "$oid": "5f1d7f3e9c4b2a0011223344" },
+    "views": number_long(9_007_199_254_740_993),
+  }))?;
+  coll.insert(query!({ "_id": { "$oid": "5f1d7f3e9c4b2a0011223345" }, "views": 10 }))?;
+  coll.insert(query!({
+    "_id": { "$oid": "5f1d7f3e9c4b2a0011223346" },
+    "views": { "$numberDecimal": "10.5" },
+  }))?;
+
+  let docs = coll.find(query!({ "views": { "$gt": 10 } }))?;
+  assert_eq!(docs.len(), 2);
+  let docs = coll.find(query!({ "views": { "$gt": number_long(9_007_199_254_740_992) } }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["_id"]["$oid"], "5f1d7f3e9c4b2a0011223344");
+
+  let doc = coll.find_one(query!({ "_id": { "$oid": "5F1D7F3E9C4B2A0011223345" } }))?;
+  assert_eq!(doc.unwrap()["views"], 10);
+
+  coll.find_and_update(
+    query!({ "views": number_long(10) }),
+    query!({ "$set": { "seen": { "$date": "2021-01-01T00:00:00Z" } } }),
+  )?;
+  let docs = coll.find(query!({ "seen": date(1_609_459_200_000) }))?;
+  assert_eq!(docs.len(), 1);
+  assert!(coll
+    .find_and_update(query!({}), query!({ "$date": "2021-01-01T00:00:00Z" }))
+    .is_err());
+  Ok(())
+}
+
+#[test]
+fn test_import_and_export_extended_json() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let coll = memdb.collection("Users")?;
+  let input = concat!(
+    "{\"_id\":{\"$oid\":\"5f1d7f3e9c4b2a0011223344\"},\"age\":{\"$numberInt\":\"25\"},",
+    "\"joined\":{\"$date\":{\"$numberLong\":\"1593561600000\"}}}\n",
+    "{\"_id\":{\"$oid\":\"bad\"}}\n",
+  );
+  assert!(coll
+    .import_jsonl(input.as_bytes(), ImportOptions::default())
+    .is_err());
+  let docs = coll.find(query!({ "age": 25 }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["joined"]["$date"], "2020-07-01T00:00:00.000Z");
+
+  let mut output = Vec::new();
+  coll.export_jsonl(&mut output, ExportOptions::default())?;
+  assert_eq!(
+    String::from_utf8(output).unwrap(),
+    concat!(
+      "{\"_id\":{\"$oid\":\"5f1d7f3e9c4b2a0011223344\"},\"age\":25,",
+      "\"joined\":{\"$date\":\"2020-07-01T00:00:00.000Z\"}}\n"
+    )
+  );
+
+  let mut output = Vec::new();
+  let options = ExportOptions {
+    mode: ExtendedJsonMode::Canonical,
+    ..Default::default()
+  };
+  coll.export_jsonl(&mut output, options)?;
+  assert_eq!(
+    String::from_utf8(output).unwrap(),
+    input.lines().next().unwrap().to_string() + "\n"
+  );
+  Ok(())
+}
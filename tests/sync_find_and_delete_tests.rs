@@ -1,11 +1,9 @@
-#[cfg(feature = "sync")]
-use memquery::{doc, errors::Error, memdb::MemDb, query};
+use memquery::{blocking::MemDb, doc, errors::Error, query};
 
 #[test]
-#[cfg(feature = "sync")]
 fn simple_delete() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -21,10 +19,9 @@ fn simple_delete() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn delete_all_docs() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -38,3 +35,20 @@ fn delete_all_docs() -> Result<(), Error> {
 
   Ok(())
 }
+
+#[test]
+fn invalid_query_deletes_nothing() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
+
+  assert!(coll
+    .find_and_delete(query!({ "age": { "$gtee": 20 } }))
+    .is_err());
+
+  let docs_remaining = coll.find(query!({}))?;
+  assert_eq!(docs_remaining.len(), 2);
+  Ok(())
+}
@@ -1,11 +1,9 @@
-#[cfg(feature = "sync")]
-use memquery::{doc, errors::Error, memdb::MemDb, query, update};
+use memquery::{blocking::MemDb, doc, errors::Error, query, update};
 
 #[test]
-#[cfg(feature = "sync")]
 fn simple_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -25,10 +23,9 @@ fn simple_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn set_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -49,10 +46,9 @@ fn set_op_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn unset_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -84,10 +80,9 @@ fn unset_op_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn set_op_on_embedded_doc_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25, "profile": { "email": "rob@test.com" } }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20, "profile": { "email": "bob@test.com" }  }))?;
@@ -108,10 +103,9 @@ fn set_op_on_embedded_doc_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn unset_op_on_embedded_doc_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25, "profile": { "email": "rob@test.com" } }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20, "profile": { "email": "bob@test.com" }  }))?;
@@ -143,10 +137,9 @@ fn unset_op_on_embedded_doc_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn inc_positive_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -164,10 +157,9 @@ fn inc_positive_op_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn inc_negative_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -185,10 +177,9 @@ fn inc_negative_op_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn mul_positive_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -206,10 +197,9 @@ fn mul_positive_op_update() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn mul_negative_op_update() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
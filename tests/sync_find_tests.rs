@@ -1,11 +1,9 @@
-#[cfg(feature = "sync")]
-use memquery::{doc, errors::Error, memdb::MemDb, query};
+use memquery::{blocking::MemDb, doc, errors::Error, query, query::CompiledQuery};
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_simple_query() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -19,10 +17,9 @@ fn test_simple_query() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_simple_query_with_multiple_conditions() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -37,10 +34,9 @@ fn test_simple_query_with_multiple_conditions() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_nomatch_query_with_multiple_conditions() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -53,10 +49,9 @@ fn test_nomatch_query_with_multiple_conditions() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_query_match_with_and() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -70,10 +65,9 @@ fn test_query_match_with_and() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_query_nomatch_with_and() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -86,10 +80,9 @@ fn test_query_nomatch_with_and() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_query_match_with_or() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -102,10 +95,9 @@ fn test_query_match_with_or() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_query_nomatch_with_or() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
   coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
@@ -118,10 +110,9 @@ fn test_query_nomatch_with_or() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_eq_op() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -145,10 +136,9 @@ fn test_eq_op() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_eq_nomatch_op() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -170,10 +160,9 @@ fn test_eq_nomatch_op() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_eq_op_single_entry_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -196,10 +185,9 @@ fn test_eq_op_single_entry_embedded_doc() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_eq_op_to_match_array_to_array() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -223,10 +211,9 @@ fn test_eq_op_to_match_array_to_array() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_eq_op_to_nomatch_array_to_array() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -248,10 +235,9 @@ fn test_eq_op_to_nomatch_array_to_array() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_eq_op_to_match_array_to_value() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -277,10 +263,9 @@ fn test_eq_op_to_match_array_to_value() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_gt_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -304,10 +289,9 @@ fn test_gt_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_gt_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -329,10 +313,9 @@ fn test_gt_no_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_gt_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -354,10 +337,9 @@ fn test_gt_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_gte_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -383,10 +365,9 @@ fn test_gte_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_gte_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -408,10 +389,9 @@ fn test_gte_no_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_gte_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -433,10 +413,9 @@ fn test_gte_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_lt_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -459,10 +438,9 @@ fn test_lt_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_lt_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -484,10 +462,9 @@ fn test_lt_no_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_lt_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -510,10 +487,9 @@ fn test_lt_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_lte_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -538,10 +514,9 @@ fn test_lte_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_lte_no_match() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": "123" }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -563,10 +538,9 @@ fn test_lte_no_match() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_lte_match_embedded_doc() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -589,10 +563,9 @@ fn test_lte_match_embedded_doc() -> Result<(), Error> {
 }
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_find_all_docs() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
   coll.insert(
     doc!({ "item": { "name": "ab", "code": 123 }, "qty": 15, "tags": [ "A", "B", "C" ] }),
@@ -610,3 +583,81 @@ fn test_find_all_docs() -> Result<(), Error> {
 
   Ok(())
 }
+
+#[test]
+fn test_query_comparison_and_equality_must_all_match() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
+
+  let docs = coll.find(query!({ "age": { "$gt": 30 }, "name": "Bob" }))?;
+  assert_eq!(docs.len(), 0);
+
+  let docs = coll.find(query!({ "$or": [{ "age": 25 }, { "age": 20 }], "name": "Bob" }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Bob");
+  Ok(())
+}
+
+#[test]
+fn test_query_compare_negative_and_float_numbers() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "balance": -5 }))?;
+  coll.insert(doc!({ "name": "Bob", "balance": 2.5 }))?;
+  coll.insert(doc!({ "name": "Tom", "balance": 10 }))?;
+
+  let docs = coll.find(query!({ "balance": { "$gt": 3 } }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Tom");
+
+  let docs = coll.find(query!({ "balance": { "$gte": -1 } }))?;
+  assert_eq!(docs.len(), 2);
+
+  let docs = coll.find(query!({ "balance": { "$lt": 2.5 } }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+  Ok(())
+}
+
+#[test]
+fn test_compiled_query_reused() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+
+  let adults = CompiledQuery::new(&query!({ "age": { "$gte": 21 } }))?;
+  assert_eq!(coll.find_compiled(&adults)?.len(), 1);
+
+  coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
+  coll.insert(doc!({ "name": "Tom", "age": 30 }))?;
+  let docs = coll.find_compiled(&adults)?;
+  assert_eq!(docs.len(), 2);
+  assert_eq!(docs[1]["name"], "Tom");
+  Ok(())
+}
+
+#[test]
+fn test_query_in_nin_and_regex() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
+  coll.insert(doc!({ "name": "Tom", "age": 30 }))?;
+
+  let docs = coll.find(query!({ "age": { "$in": [20, 30] } }))?;
+  assert_eq!(docs.len(), 2);
+
+  let docs = coll.find(query!({ "age": { "$nin": [20, 30] } }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+
+  let docs = coll.find(query!({ "name": { "$regex": "ob$" } }))?;
+  assert_eq!(docs.len(), 2);
+  Ok(())
+}
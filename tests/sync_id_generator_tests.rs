@@ -0,0 +1,83 @@
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::Error,
+  id_generator::{AutoIncrement, ObjectId, Seeded},
+  query, update,
+};
+
+#[test]
+fn test_insert_assigns_uuid_by_default() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(query!({ "name": "Rob" }))?;
+  coll.insert(query!({ "_id": "rob", "name": "Rob" }))?;
+
+  let docs = coll.find(query!({}))?;
+  assert_eq!(docs[0]["_id"].as_str().map(|id| id.len()), Some(36));
+  assert_eq!(docs[1]["_id"], "rob");
+  Ok(())
+}
+
+#[test]
+fn test_doc_macro_uses_id_generator() -> Result<(), Error> {
+  let memdb = MemDb::with_id_generator(AutoIncrement::new);
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  users.insert(doc!({ "name": "Rob" }))?;
+  users.insert(doc!({ "name": "Bob" }))?;
+
+  let docs = users.find(query!({}))?;
+  assert_eq!(docs[0]["_id"], 1);
+  assert_eq!(docs[1]["_id"], 2);
+  Ok(())
+}
+
+#[test]
+fn test_auto_increment_per_collection() -> Result<(), Error> {
+  let memdb = MemDb::with_id_generator(AutoIncrement::new);
+  memdb.create_collection("Users")?;
+  memdb.create_collection("Orders")?;
+  let users = memdb.collection("Users")?;
+  let orders = memdb.collection("Orders")?;
+  users.insert(query!({ "name": "Rob" }))?;
+  users.insert(query!({ "name": "Bob" }))?;
+  orders.insert(query!({ "item": "book" }))?;
+
+  let docs = users.find(query!({ "_id": 2 }))?;
+  assert_eq!(docs[0]["name"], "Bob");
+  let docs = orders.find(query!({}))?;
+  assert_eq!(docs[0]["_id"], 1);
+  Ok(())
+}
+
+#[test]
+fn test_seeded_ids_are_reproducible() -> Result<(), Error> {
+  let mut ids = Vec::new();
+  for _ in 0..2 {
+    let memdb = MemDb::with_id_generator(|| Seeded::new(7));
+    memdb.create_collection("TestCollection")?;
+    let coll = memdb.collection("TestCollection")?;
+    coll.insert(query!({ "name": "Rob" }))?;
+    ids.push(coll.find(query!({}))?[0]["_id"].clone());
+  }
+  assert_eq!(ids[0], ids[1]);
+  Ok(())
+}
+
+#[test]
+fn test_replacement_keeps_generated_id() -> Result<(), Error> {
+  let memdb = MemDb::with_id_generator(ObjectId::new);
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(query!({ "name": "Rob" }))?;
+  let id = coll.find(query!({}))?[0]["_id"].clone();
+
+  coll.find_and_update(query!({ "name": "Rob" }), update!({ "name": "Bob" }))?;
+
+  let docs = coll.find(query!({ "_id": id }))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Bob");
+  Ok(())
+}
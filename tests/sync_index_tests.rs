@@ -0,0 +1,186 @@
+use memquery::{blocking::MemDb, doc, errors::Error, index::IndexOptions, query, update};
+use serde_json::json;
+
+#[test]
+fn duplicate_id_rejected() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(query!({ "_id": 1, "name": "Rob" }))?;
+
+  match coll.insert(query!({ "_id": 1, "name": "Bob" })) {
+    Err(Error::MQDuplicateKey { index, key }) => {
+      assert_eq!(index, "_id_");
+      assert_eq!(key, json!({ "_id": 1 }));
+    }
+    _ => assert_eq!("should get duplicate key error", "no error"),
+  }
+
+  let docs = coll.find(query!({}))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Rob");
+  Ok(())
+}
+
+#[test]
+fn unique_index_rejects_duplicate() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  let name = coll.create_index(
+    &["email"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  assert_eq!(name, "email_1");
+
+  coll.insert(doc!({ "name": "Rob", "email": "rob@test.com" }))?;
+  coll.insert(doc!({ "name": "Bob", "email": "bob@test.com" }))?;
+
+  match coll.insert(doc!({ "name": "Tom", "email": "rob@test.com" })) {
+    Err(Error::MQDuplicateKey { index, key }) => {
+      assert_eq!(index, "email_1");
+      assert_eq!(key, json!({ "email": "rob@test.com" }));
+    }
+    _ => assert_eq!("should get duplicate key error", "no error"),
+  }
+
+  assert_eq!(coll.find(query!({}))?.len(), 2);
+  Ok(())
+}
+
+#[test]
+fn unique_index_on_existing_duplicates_fails() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 25 }))?;
+
+  let result = coll.create_index(
+    &["age"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  );
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  assert_eq!(coll.list_indexes().len(), 1);
+  Ok(())
+}
+
+#[test]
+fn partial_unique_index() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.create_index(
+    &["email"],
+    IndexOptions {
+      unique: true,
+      partial_filter_expression: Some(query!({ "active": true })),
+      ..Default::default()
+    },
+  )?;
+
+  coll.insert(doc!({ "email": "rob@test.com", "active": true }))?;
+  coll.insert(doc!({ "email": "rob@test.com", "active": false }))?;
+
+  let result = coll.insert(doc!({ "email": "rob@test.com", "active": true }));
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  Ok(())
+}
+
+#[test]
+fn update_creating_duplicate_rejected() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.create_index(
+    &["email"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  coll.insert(doc!({ "name": "Rob", "email": "rob@test.com" }))?;
+  coll.insert(doc!({ "name": "Bob", "email": "bob@test.com" }))?;
+
+  let result = coll.find_and_update(
+    query!({ "name": "Bob" }),
+    update!({ "$set": { "email": "rob@test.com" } }),
+  );
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+
+  let docs = coll.find(query!({ "name": "Bob" }))?;
+  assert_eq!(docs[0]["email"], "bob@test.com");
+
+  // swapping to a key that is no longer used is allowed
+  coll.find_and_update(
+    query!({ "name": "Rob" }),
+    update!({ "$set": { "email": "robert@test.com" } }),
+  )?;
+  coll.find_and_update(
+    query!({ "name": "Bob" }),
+    update!({ "$set": { "email": "rob@test.com" } }),
+  )?;
+  Ok(())
+}
+
+#[test]
+fn update_multiple_documents_to_same_key_rejected() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.create_index(
+    &["code"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  coll.insert(doc!({ "code": 1, "group": "a" }))?;
+  coll.insert(doc!({ "code": 2, "group": "a" }))?;
+
+  let result = coll.find_and_update(query!({ "group": "a" }), update!({ "$set": { "code": 3 } }));
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  assert_eq!(coll.find(query!({ "code": 3 }))?.len(), 0);
+  Ok(())
+}
+
+#[test]
+fn deleted_key_can_be_reused() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.insert(query!({ "_id": 1, "name": "Rob" }))?;
+  coll.find_and_delete(query!({ "_id": 1 }))?;
+  coll.insert(query!({ "_id": 1, "name": "Bob" }))?;
+  Ok(())
+}
+
+#[test]
+fn drop_index() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.create_index(
+    &["email"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  coll.drop_index("email_1")?;
+  coll.insert(doc!({ "email": "rob@test.com" }))?;
+  coll.insert(doc!({ "email": "rob@test.com" }))?;
+
+  assert!(matches!(
+    coll.drop_index("email_1"),
+    Err(Error::MQIndexNotFound(_))
+  ));
+  assert!(coll.drop_index("_id_").is_err());
+  Ok(())
+}
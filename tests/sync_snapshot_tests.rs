@@ -0,0 +1,81 @@
+use memquery::{
+  blocking::MemDb, doc, errors::Error, id_generator::AutoIncrement, index::IndexOptions, query,
+};
+use std::path::PathBuf;
+
+fn snapshot_path(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("memquery-{}-{}.json", name, uuid::Uuid::new_v4()))
+}
+
+#[test]
+fn test_save_and_load() -> Result<(), Error> {
+  let path = snapshot_path("roundtrip");
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  memdb.create_collection("Empty")?;
+  let users = memdb.collection("Users")?;
+  users.create_index(
+    &["email"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  users.insert(doc!({ "name": "Rob", "email": "rob@test.com" }))?;
+  users.insert(doc!({ "name": "Bob", "email": "bob@test.com" }))?;
+  memdb.save_to(&path)?;
+
+  let restored = MemDb::new();
+  restored.load_from(&path)?;
+  std::fs::remove_file(&path)?;
+
+  let restored_users = restored.collection("Users")?;
+  assert_eq!(restored_users.find(query!({}))?, users.find(query!({}))?);
+  assert_eq!(restored_users.list_indexes(), users.list_indexes());
+  assert!(restored.collection("Empty")?.find(query!({}))?.is_empty());
+
+  let result = restored_users.insert(doc!({ "name": "Tom", "email": "rob@test.com" }));
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  Ok(())
+}
+
+#[test]
+fn test_load_errors() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+
+  let result = memdb.load_from(snapshot_path("missing"));
+  assert!(matches!(result, Err(Error::IOError(_))));
+
+  let path = snapshot_path("invalid");
+  std::fs::write(
+    &path,
+    r#"{"format": "memquery-snapshot", "version": 99, "collections": {}}"#,
+  )?;
+  assert!(memdb.load_from(&path).is_err());
+  std::fs::remove_file(&path)?;
+
+  assert!(memdb.collection("Users").is_ok());
+  Ok(())
+}
+
+#[test]
+fn test_auto_increment_continues_after_load() -> Result<(), Error> {
+  let path = snapshot_path("autoincrement");
+  let memdb = MemDb::with_id_generator(AutoIncrement::new);
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  users.insert(query!({ "name": "Rob" }))?;
+  users.insert(query!({ "name": "Bob" }))?;
+  memdb.save_to(&path)?;
+
+  let restored = MemDb::with_id_generator(AutoIncrement::new);
+  restored.load_from(&path)?;
+  std::fs::remove_file(&path)?;
+
+  let users = restored.collection("Users")?;
+  users.insert(query!({ "name": "Tom" }))?;
+  let docs = users.find(query!({ "name": "Tom" }))?;
+  assert_eq!(docs[0]["_id"], 3);
+  Ok(())
+}
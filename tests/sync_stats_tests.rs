@@ -0,0 +1,95 @@
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::Error,
+  index::IndexOptions,
+  query,
+  stats::{value_size, OperationStats},
+  update,
+};
+use serde_json::json;
+
+#[test]
+fn test_collection_stats() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  let empty = users.stats();
+  assert_eq!(empty.count, 0);
+  assert_eq!(empty.size, 0);
+  assert_eq!(empty.avg_obj_size, 0);
+  assert_eq!(empty.index_sizes.keys().collect::<Vec<_>>(), vec!["_id_"]);
+
+  users.create_index(&["name"], IndexOptions::default())?;
+  let tom = doc!({ "_id": 1, "name": "Tom", "tags": ["a", "b"] });
+  let bob = doc!({ "_id": 2, "name": "Bob", "tags": [] });
+  users.insert(tom.clone())?;
+  users.insert(bob.clone())?;
+
+  let stats = users.stats();
+  assert_eq!(stats.count, 2);
+  assert_eq!(stats.size, value_size(&tom) + value_size(&bob));
+  assert_eq!(stats.avg_obj_size, stats.size / 2);
+  assert!(stats.index_sizes["name_1"] > 0);
+  assert_eq!(
+    stats.total_index_size,
+    stats.index_sizes.values().sum::<u64>()
+  );
+  Ok(())
+}
+
+#[test]
+fn test_operation_counters() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  for age in 20..25 {
+    users.insert(doc!({ "age": age }))?;
+  }
+  users.find(query!({ "age": { "$gt": 22 } }))?;
+  users.find_one(query!({ "age": 20 }))?;
+  users.find_and_update(
+    query!({ "age": { "$lt": 22 } }),
+    update!({ "$inc": { "age": 1 } }),
+  )?;
+  users.find_and_delete(query!({ "age": 24 }))?;
+
+  assert_eq!(
+    users.stats().operations,
+    OperationStats {
+      inserts: 5,
+      queries: 2,
+      updates: 2,
+      deletes: 1,
+      docs_scanned: 5 + 5 + 5 + 5,
+    }
+  );
+  Ok(())
+}
+
+#[test]
+fn test_database_stats() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  memdb.create_collection("Orders")?;
+  memdb.collection("Users")?.insert(doc!({ "name": "Tom" }))?;
+  let orders = memdb.collection("Orders")?;
+  orders.insert(doc!({ "item": "pen" }))?;
+  orders.insert(doc!({ "item": "ink" }))?;
+
+  let stats = memdb.stats();
+  assert_eq!(stats.collections, 2);
+  assert_eq!(stats.objects, 3);
+  assert_eq!(
+    stats.data_size,
+    memdb.collection("Users")?.stats().size + orders.stats().size
+  );
+  assert_eq!(stats.indexes, 2);
+  assert_eq!(stats.operations.inserts, 3);
+
+  let value = json!(stats);
+  assert_eq!(value["objects"], 3);
+  assert_eq!(value["operations"]["docsScanned"], 0);
+  assert!(value["dataSize"].as_u64().unwrap() > 0);
+  Ok(())
+}
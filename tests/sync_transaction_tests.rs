@@ -0,0 +1,169 @@
+use memquery::{blocking::MemDb, doc, errors::Error, index::IndexOptions, query, update};
+use std::thread;
+
+#[test]
+fn test_transaction_moves_document() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Pending")?;
+  memdb.create_collection("Done")?;
+  let pending = memdb.collection("Pending")?;
+  pending.insert(doc!({ "task": "write", "owner": "Rob" }))?;
+  pending.insert(doc!({ "task": "test", "owner": "Bob" }))?;
+
+  let moved = memdb.transaction(&["Pending", "Done"], |tx| {
+    let tasks = tx
+      .collection("Pending")?
+      .find_and_delete(query!({ "owner": "Rob" }))?;
+    let mut done = tx.collection("Done")?;
+    for task in tasks.iter() {
+      done.insert(task.clone())?;
+    }
+    assert_eq!(done.find(query!({}))?.len(), 1);
+    Ok(tasks.len())
+  })?;
+
+  assert_eq!(moved, 1);
+  assert_eq!(pending.find(query!({}))?.len(), 1);
+  let done = memdb.collection("Done")?.find(query!({}))?;
+  assert_eq!(done.len(), 1);
+  assert_eq!(done[0]["task"], "write");
+  Ok(())
+}
+
+#[test]
+fn test_transaction_rolls_back_on_error() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Stock")?;
+  memdb.create_collection("Orders")?;
+  let stock = memdb.collection("Stock")?;
+  let orders = memdb.collection("Orders")?;
+  stock.insert(doc!({ "item": "pen", "qty": 5 }))?;
+  orders.create_index(
+    &["order"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  orders.insert(doc!({ "order": 1, "item": "pen" }))?;
+
+  let result = memdb.transaction(&["Stock", "Orders"], |tx| {
+    tx.collection("Stock")?.find_and_update(
+      query!({ "item": "pen" }),
+      update!({ "$inc": { "qty": -1 } }),
+    )?;
+    tx.collection("Orders")?
+      .insert(doc!({ "order": 2, "item": "pen" }))?;
+    tx.collection("Orders")?
+      .insert(doc!({ "order": 1, "item": "pen" }))
+  });
+
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  assert_eq!(stock.find(query!({}))?[0]["qty"], 5);
+  assert_eq!(orders.find(query!({}))?.len(), 1);
+  assert!(orders.find_one(query!({ "order": 2 }))?.is_none());
+
+  let result = memdb.transaction(&["Missing"], |_| Ok(()));
+  assert!(matches!(result, Err(Error::MQCollectionNotFound(_))));
+  let result = memdb.transaction(&["Stock"], |tx| tx.collection("Orders").map(|_| ()));
+  assert!(matches!(result, Err(Error::MQInvalidValue(_))));
+  Ok(())
+}
+
+#[test]
+fn test_transaction_does_not_lock_other_collections() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Stock")?;
+  memdb.create_collection("Audit")?;
+  let audit = memdb.collection("Audit")?;
+
+  memdb.transaction(&["Stock"], |tx| {
+    tx.collection("Stock")?.insert(doc!({ "item": "pen" }))?;
+    audit.insert(doc!({ "event": "stock" }))?;
+    memdb.collection("Audit")?.find(query!({})).map(|_| ())
+  })?;
+
+  assert_eq!(memdb.collection("Stock")?.find(query!({}))?.len(), 1);
+  assert_eq!(audit.find(query!({}))?.len(), 1);
+  Ok(())
+}
+
+#[test]
+fn test_transactions_in_threads() -> Result<(), Error> {
+  let memdb = std::sync::Arc::new(MemDb::new());
+  memdb.create_collection("A")?;
+  memdb.create_collection("B")?;
+  memdb.collection("A")?.insert(doc!({ "balance": 100 }))?;
+  memdb.collection("B")?.insert(doc!({ "balance": 100 }))?;
+
+  let handles: Vec<_> = (0..8)
+    .map(|i| {
+      let memdb = memdb.clone();
+      let (from, to) = if i % 2 == 0 { ("A", "B") } else { ("B", "A") };
+      thread::spawn(move || {
+        for _ in 0..50 {
+          memdb
+            .transaction(&[from, to], |tx| {
+              tx.collection(from)?
+                .find_and_update(query!({}), update!({ "$inc": { "balance": -1 } }))?;
+              tx.collection(to)?
+                .find_and_update(query!({}), update!({ "$inc": { "balance": 1 } }))
+            })
+            .unwrap();
+        }
+      })
+    })
+    .collect();
+  let reader = {
+    let memdb = memdb.clone();
+    thread::spawn(move || {
+      for _ in 0..50 {
+        let total = memdb
+          .transaction(&["A", "B"], |tx| {
+            let a = tx.collection("A")?.find_one(query!({}))?.unwrap();
+            let b = tx.collection("B")?.find_one(query!({}))?.unwrap();
+            Ok(a["balance"].as_f64().unwrap() + b["balance"].as_f64().unwrap())
+          })
+          .unwrap();
+        assert_eq!(total, 200.0);
+      }
+    })
+  };
+
+  for handle in handles {
+    handle.join().unwrap();
+  }
+  reader.join().unwrap();
+  assert_eq!(
+    memdb.collection("A")?.find(query!({}))?[0]["balance"],
+    100.0
+  );
+  assert_eq!(
+    memdb.collection("B")?.find(query!({}))?[0]["balance"],
+    100.0
+  );
+  Ok(())
+}
+
+#[test]
+fn test_transaction_rolls_back_on_panic() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection("Users")?;
+  users.insert(doc!({ "name": "Rob" }))?;
+
+  let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    memdb.transaction(&["Users"], |tx| -> Result<(), Error> {
+      tx.collection("Users")?
+        .find_and_delete(query!({ "name": "Rob" }))?;
+      panic!("closure failed");
+    })
+  }));
+  assert!(result.is_err());
+
+  assert_eq!(users.find(query!({ "name": "Rob" }))?.len(), 1);
+  memdb.create_collection("Orders")?;
+  users.insert(doc!({ "name": "Bob" }))?;
+  assert_eq!(memdb.collection("Users")?.find(query!({}))?.len(), 2);
+  Ok(())
+}
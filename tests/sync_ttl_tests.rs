@@ -0,0 +1,101 @@
+use memquery::{
+  blocking::MemDb, change_stream::OperationType, clock::ManualClock, doc, errors::Error,
+  extended_json::date, index::IndexOptions, query, update,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn ttl(seconds: u64) -> IndexOptions {
+  IndexOptions {
+    expire_after: Some(Duration::from_secs(seconds)),
+    ..Default::default()
+  }
+}
+
+#[test]
+fn test_expired_documents_are_invisible() -> Result<(), Error> {
+  let clock = Arc::new(ManualClock::new(0));
+  let memdb = MemDb::new().with_clock(clock.clone());
+  memdb.create_collection("Sessions")?;
+  let sessions = memdb.collection("Sessions")?;
+  sessions.create_index(&["lastSeen"], ttl(60))?;
+  sessions.insert(doc!({ "user": "Tom", "lastSeen": date(0) }))?;
+  sessions.insert(doc!({ "user": "Bob", "lastSeen": date(30_000) }))?;
+  sessions.insert(doc!({ "user": "Rob", "lastSeen": "never" }))?;
+
+  clock.advance(Duration::from_secs(59));
+  assert_eq!(sessions.find(query!({}))?.len(), 3);
+
+  clock.advance(Duration::from_secs(1));
+  let users: Vec<_> = sessions
+    .find(query!({}))?
+    .iter()
+    .map(|d| d["user"].clone())
+    .collect();
+  assert_eq!(users, vec!["Bob", "Rob"]);
+  assert!(sessions.find_one(query!({ "user": "Tom" }))?.is_none());
+  assert_eq!(
+    sessions.find_and_update(query!({}), update!({ "$set": { "seen": true } }))?,
+    2
+  );
+  assert_eq!(sessions.view().len(), 2);
+  Ok(())
+}
+
+#[test]
+fn test_expire_at_and_purge() -> Result<(), Error> {
+  let clock = Arc::new(ManualClock::new(0));
+  let memdb = MemDb::new().with_clock(clock.clone());
+  memdb.create_collection("Cache")?;
+  let cache = memdb.collection("Cache")?;
+  cache.create_index(&["expireAt"], ttl(0))?;
+  cache.insert(doc!({ "key": "a", "expireAt": date(1_000) }))?;
+  cache.insert(doc!({ "key": "b", "expireAt": [date(5_000), date(2_000)] }))?;
+  cache.insert(doc!({ "key": "c" }))?;
+  let mut changes = cache.watch(query!({}))?;
+
+  clock.set(2_000);
+  assert_eq!(cache.find(query!({}))?.len(), 1);
+  assert_eq!(cache.purge_expired()?, 2);
+  assert_eq!(cache.purge_expired()?, 0);
+  assert_eq!(changes.recv()?.operation_type, OperationType::Delete);
+  assert_eq!(changes.recv()?.operation_type, OperationType::Delete);
+  Ok(())
+}
+
+#[test]
+fn test_writes_sweep_expired_documents() -> Result<(), Error> {
+  let clock = Arc::new(ManualClock::new(0));
+  let memdb = MemDb::new().with_clock(clock.clone());
+  memdb.create_collection("Sessions")?;
+  let sessions = memdb.collection("Sessions")?;
+  sessions.create_index(
+    &["user"],
+    IndexOptions {
+      unique: true,
+      ..Default::default()
+    },
+  )?;
+  sessions.create_index(&["lastSeen"], ttl(10))?;
+  sessions.insert(doc!({ "user": "Tom", "lastSeen": date(0) }))?;
+
+  clock.advance(Duration::from_secs(10));
+  // expired session does not block new one with the same unique key
+  sessions.insert(doc!({ "user": "Tom", "lastSeen": date(10_000) }))?;
+  assert_eq!(memdb.purge_expired()?, 0);
+  assert_eq!(sessions.find(query!({ "user": "Tom" }))?.len(), 1);
+  Ok(())
+}
+
+#[test]
+fn test_invalid_ttl_index() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Sessions")?;
+  let sessions = memdb.collection("Sessions")?;
+  assert!(sessions.create_index(&["a", "b"], ttl(10)).is_err());
+  assert!(sessions.create_index(&["_id"], ttl(10)).is_err());
+  assert_eq!(sessions.create_index(&["a"], ttl(10))?, "a_1");
+  let index = &sessions.list_indexes()[1];
+  assert_eq!(index.expire_after, Some(Duration::from_secs(10)));
+  Ok(())
+}
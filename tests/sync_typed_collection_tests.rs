@@ -0,0 +1,99 @@
+use memquery::{blocking::MemDb, doc, errors::Error, query, update};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct User {
+  name: String,
+  age: u32,
+  #[serde(default)]
+  tags: Vec<String>,
+}
+
+fn user(name: &str, age: u32) -> User {
+  User {
+    name: name.to_string(),
+    age,
+    tags: Vec::new(),
+  }
+}
+
+#[test]
+fn test_insert_and_find_typed() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection_typed::<User>("Users")?;
+  users.insert(user("Rob", 25))?;
+  users.insert(user("Bob", 20))?;
+  users.insert(user("Tom", 30))?;
+
+  let found = users.find(query!({ "age": { "$gte": 25 } }))?;
+
+  assert_eq!(found, vec![user("Rob", 25), user("Tom", 30)]);
+  assert_eq!(
+    users.find_one(query!({ "name": "Bob" }))?,
+    Some(user("Bob", 20))
+  );
+  assert_eq!(users.find_one(query!({ "name": "Victor" }))?, None);
+  Ok(())
+}
+
+#[test]
+fn test_update_and_delete_typed() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let users = memdb.collection_typed::<User>("Users")?;
+  users.insert(user("Rob", 25))?;
+  users.insert(user("Bob", 20))?;
+
+  let updated = users.find_and_update(
+    query!({ "name": "Bob" }),
+    update!({ "$set": { "age": 21 } }),
+  )?;
+  assert_eq!(updated, 1);
+
+  let deleted = users.find_and_delete(query!({ "name": "Bob" }))?;
+  assert_eq!(deleted, vec![user("Bob", 21)]);
+  assert_eq!(users.find(query!({}))?, vec![user("Rob", 25)]);
+  Ok(())
+}
+
+#[test]
+fn test_mismatched_document_returns_error() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let coll = memdb.collection("Users")?;
+  coll.insert(doc!({ "name": "Rob", "age": "unknown" }))?;
+
+  let users = memdb.collection_typed::<User>("Users")?;
+  let result = users.find(query!({ "name": "Rob" }));
+
+  assert!(matches!(result, Err(Error::SerdeJsonError(_))));
+  Ok(())
+}
+
+#[test]
+fn test_mismatched_document_is_not_deleted() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users")?;
+  let coll = memdb.collection("Users")?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": "unknown" }))?;
+
+  let users = memdb.collection_typed::<User>("Users")?;
+  let result = users.find_and_delete(query!({}));
+
+  assert!(matches!(result, Err(Error::SerdeJsonError(_))));
+  assert_eq!(coll.find(query!({}))?.len(), 2);
+  Ok(())
+}
+
+#[test]
+fn test_insert_non_object_fails() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Numbers")?;
+  let numbers = memdb.collection_typed::<u32>("Numbers")?;
+
+  assert!(numbers.insert(5).is_err());
+  assert!(numbers.collection().find(query!({}))?.is_empty());
+  Ok(())
+}
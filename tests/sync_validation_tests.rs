@@ -0,0 +1,142 @@
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::Error,
+  options::{CollectionOptions, ValidationAction, ValidationLevel},
+  query, update,
+  validation::ValidationFailure,
+};
+use serde_json::{json, Value};
+
+fn people_validator() -> Value {
+  json!({
+    "$jsonSchema": {
+      "type": "object",
+      "required": ["name", "age"],
+      "properties": {
+        "name": { "type": "string" },
+        "age": { "type": "integer", "minimum": 0 }
+      }
+    }
+  })
+}
+
+fn failed_paths(result: Result<impl Sized, Error>) -> Vec<String> {
+  let mut paths: Vec<String> = match result {
+    Err(Error::MQValidationFailed { failures }) => failures
+      .into_iter()
+      .map(|ValidationFailure { path, .. }| path)
+      .collect(),
+    _ => panic!("document passed validation"),
+  };
+  paths.sort();
+  paths
+}
+
+#[test]
+fn test_insert_is_validated() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection_with_options(
+    "People",
+    CollectionOptions {
+      validator: Some(people_validator()),
+      ..Default::default()
+    },
+  )?;
+  let people = memdb.collection("People")?;
+  people.insert(doc!({ "name": "Tom", "age": 25 }))?;
+
+  assert_eq!(
+    failed_paths(people.insert(doc!({ "age": "25" }))),
+    vec!["age", "name"]
+  );
+  assert_eq!(people.find(query!({}))?.len(), 1);
+  Ok(())
+}
+
+#[test]
+fn test_update_is_validated() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection_with_options(
+    "People",
+    CollectionOptions {
+      validator: Some(json!({ "age": { "$gte": 0 } })),
+      ..Default::default()
+    },
+  )?;
+  let people = memdb.collection("People")?;
+  people.insert(doc!({ "name": "Tom", "age": 25 }))?;
+  people.insert(doc!({ "name": "Bob", "age": 30 }))?;
+
+  let result = people.find_and_update(query!({}), update!({ "$set": { "age": -1 } }));
+  assert_eq!(failed_paths(result), vec!["age"]);
+  assert_eq!(people.find(query!({ "age": { "$gt": 0 } }))?.len(), 2);
+  Ok(())
+}
+
+#[test]
+fn test_moderate_level_allows_updates_of_invalid_documents() -> Result<(), Error> {
+  let path =
+    std::env::temp_dir().join(format!("memquery-validation-{}.json", uuid::Uuid::new_v4()));
+  let memdb = MemDb::new();
+  memdb.create_collection("People")?;
+  let people = memdb.collection("People")?;
+  people.insert(doc!({ "name": "Tom" }))?;
+  people.insert(doc!({ "name": "Bob", "age": 30 }))?;
+  memdb.save_to(&path)?;
+
+  // validator added to collection that already has invalid documents
+  let mut snapshot: Value = serde_json::from_slice(&std::fs::read(&path)?)?;
+  snapshot["collections"]["People"]["options"] = json!({
+    "validator": people_validator(),
+    "validation_level": "moderate",
+  });
+  std::fs::write(&path, serde_json::to_vec(&snapshot)?)?;
+  let memdb = MemDb::new();
+  memdb.load_from(&path)?;
+  std::fs::remove_file(&path)?;
+  let people = memdb.collection("People")?;
+  assert_eq!(people.options().validation_level, ValidationLevel::Moderate);
+
+  people.find_and_update(
+    query!({ "name": "Tom" }),
+    update!({ "$set": { "nick": "T" } }),
+  )?;
+  let result = people.find_and_update(
+    query!({ "name": "Bob" }),
+    update!({ "$set": { "age": "30" } }),
+  );
+  assert_eq!(failed_paths(result), vec!["age"]);
+  Ok(())
+}
+
+#[test]
+fn test_warn_action_accepts_invalid_documents() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection_with_options(
+    "People",
+    CollectionOptions {
+      validator: Some(people_validator()),
+      validation_action: ValidationAction::Warn,
+      ..Default::default()
+    },
+  )?;
+  let people = memdb.collection("People")?;
+  people.insert(doc!({ "name": "Tom", "age": "25" }))?;
+  assert_eq!(people.find(query!({}))?.len(), 1);
+  Ok(())
+}
+
+#[test]
+fn test_invalid_validator() {
+  let memdb = MemDb::new();
+  let options = CollectionOptions {
+    validator: Some(json!({ "$jsonSchema": { "type": "text" } })),
+    ..Default::default()
+  };
+  assert!(matches!(
+    memdb.create_collection_with_options("People", options),
+    Err(Error::MQInvalidValue(_))
+  ));
+  assert!(memdb.collection("People").is_err());
+}
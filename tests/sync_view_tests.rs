@@ -0,0 +1,88 @@
+use memquery::{
+  blocking::MemDb, doc, errors::Error, import_export::ExportOptions, index::IndexOptions,
+  planner::ExplainOptions, query, update,
+};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_view_is_point_in_time() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  coll.create_index(&["age"], IndexOptions::default())?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 }))?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 }))?;
+
+  let view = coll.view();
+  coll.insert(doc!({ "name": "Tom", "age": 30 }))?;
+  coll.find_and_update(
+    query!({ "name": "Rob" }),
+    update!({ "$set": { "age": 26 } }),
+  )?;
+  coll.find_and_delete(query!({ "name": "Bob" }))?;
+
+  assert_eq!(view.len(), 2);
+  let docs = view.find(query!({ "age": { "$gte": 20 } }))?;
+  assert_eq!(docs.len(), 2);
+  assert_eq!(docs[0]["age"], 25);
+  assert_eq!(docs[1]["name"], "Bob");
+  let explain = view.explain(query!({ "age": 25 }), ExplainOptions::default())?;
+  assert_eq!(
+    explain["queryPlanner"]["winningPlan"]["inputStage"]["indexName"],
+    "age_1"
+  );
+
+  let mut output = Vec::new();
+  assert_eq!(view.export_jsonl(&mut output, ExportOptions::default())?, 2);
+  assert_eq!(coll.find(query!({}))?.len(), 2);
+  assert_eq!(
+    coll.find_one(query!({ "name": "Rob" }))?.unwrap()["age"],
+    26
+  );
+  Ok(())
+}
+
+#[test]
+fn test_database_view_is_consistent() -> Result<(), Error> {
+  let memdb = Arc::new(MemDb::new());
+  memdb.create_collection("A")?;
+  memdb.create_collection("B")?;
+  memdb.collection("A")?.insert(doc!({ "balance": 100 }))?;
+  memdb.collection("B")?.insert(doc!({ "balance": 100 }))?;
+
+  let writer = {
+    let memdb = memdb.clone();
+    thread::spawn(move || {
+      for _ in 0..100 {
+        memdb
+          .transaction(&["A", "B"], |tx| {
+            tx.collection("A")?
+              .find_and_update(query!({}), update!({ "$inc": { "balance": -1 } }))?;
+            tx.collection("B")?
+              .find_and_update(query!({}), update!({ "$inc": { "balance": 1 } }))
+          })
+          .unwrap();
+      }
+    })
+  };
+
+  for _ in 0..100 {
+    let view = memdb.view();
+    let a = view.collection("A")?.find_one(query!({}))?.unwrap();
+    let b = view.collection("B")?.find_one(query!({}))?.unwrap();
+    assert_eq!(
+      a["balance"].as_f64().unwrap() + b["balance"].as_f64().unwrap(),
+      200.0
+    );
+  }
+  writer.join().unwrap();
+
+  let view = memdb.view();
+  assert_eq!(view.collection_names(), vec!["A", "B"]);
+  assert!(matches!(
+    view.collection("C"),
+    Err(Error::MQCollectionNotFound(_))
+  ));
+  Ok(())
+}
@@ -0,0 +1,309 @@
+#[cfg(feature = "persistence")]
+use memquery::{
+  blocking::MemDb,
+  clock::ManualClock,
+  doc,
+  errors::Error,
+  extended_json::date,
+  id_generator::AutoIncrement,
+  index::IndexOptions,
+  options::{Capped, CollectionOptions},
+  query,
+  wal::{FsyncPolicy, PersistenceOptions, LOG_FILE},
+};
+#[cfg(feature = "persistence")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "persistence")]
+use std::{sync::Arc, time::Duration};
+
+#[cfg(feature = "persistence")]
+fn db_dir(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("memquery-{}-{}", name, uuid::Uuid::new_v4()))
+}
+
+#[cfg(feature = "persistence")]
+fn log_lines(dir: &Path) -> usize {
+  std::fs::read_to_string(dir.join(LOG_FILE))
+    .unwrap()
+    .lines()
+    .count()
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_replay_after_restart() -> Result<(), Error> {
+  let dir = db_dir("replay");
+  {
+    let memdb = MemDb::with_id_generator(AutoIncrement::new)
+      .with_persistence(PersistenceOptions::new(&dir))?;
+    memdb.create_collection("Users")?;
+    memdb.create_collection("Temp")?;
+    let users = memdb.collection("Users")?;
+    users.create_index(
+      &["email"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )?;
+    users.insert(query!({ "name": "Rob", "email": "rob@test.com", "age": 25 }))?;
+    users.insert(query!({ "name": "Bob", "email": "bob@test.com", "age": 30 }))?;
+    users.insert(query!({ "name": "Tom", "email": "tom@test.com", "age": 35 }))?;
+    users.find_and_update(query!({ "name": "Rob" }), query!({ "$set": { "age": 26 } }))?;
+    users.find_and_delete(query!({ "name": "Bob" }))?;
+    memdb.delete_collection("Temp")?;
+  }
+
+  let mut options = PersistenceOptions::new(&dir);
+  options.fsync = FsyncPolicy::Never;
+  let memdb = MemDb::with_id_generator(AutoIncrement::new).with_persistence(options)?;
+  let users = memdb.collection("Users")?;
+  let docs = users.find(query!({}))?;
+  assert_eq!(docs.len(), 2);
+  assert_eq!(docs[0]["_id"], 1);
+  assert_eq!(docs[0]["age"], 26);
+  assert_eq!(docs[1]["name"], "Tom");
+  assert!(memdb.collection("Temp").is_err());
+
+  users.insert(query!({ "name": "Ann", "email": "ann@test.com" }))?;
+  assert_eq!(users.find(query!({ "name": "Ann" }))?[0]["_id"], 4);
+
+  let result = users.insert(query!({ "name": "Ann", "email": "tom@test.com" }));
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_compaction() -> Result<(), Error> {
+  let dir = db_dir("compaction");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+    memdb.create_collection("Users")?;
+    let users = memdb.collection("Users")?;
+    for age in 0..10 {
+      users.insert(doc!({ "age": age }))?;
+    }
+    users.find_and_delete(query!({ "age": { "$gte": 5 } }))?;
+    memdb.compact()?;
+    assert_eq!(log_lines(&dir), 0);
+
+    users.insert(doc!({ "age": 100 }))?;
+    assert_eq!(log_lines(&dir), 1);
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+  let users = memdb.collection("Users")?;
+  assert_eq!(users.find(query!({}))?.len(), 6);
+  memdb.compact()?;
+  assert_eq!(log_lines(&dir), 0);
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+  assert_eq!(memdb.collection("Users")?.find(query!({}))?.len(), 6);
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_incomplete_last_entry_is_ignored() -> Result<(), Error> {
+  let dir = db_dir("torn");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+    memdb.create_collection("Users")?;
+    let users = memdb.collection("Users")?;
+    users.insert(doc!({ "name": "Rob" }))?;
+  }
+  let mut log = std::fs::read_to_string(dir.join(LOG_FILE))?;
+  log.push_str(r#"{"lsn":2,"collection":"Users","op":"ins"#);
+  std::fs::write(dir.join(LOG_FILE), log)?;
+
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+    let users = memdb.collection("Users")?;
+    assert_eq!(users.find(query!({}))?.len(), 1);
+    users.insert(doc!({ "name": "Bob" }))?;
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+  let users = memdb.collection("Users")?;
+  assert_eq!(users.find(query!({}))?.len(), 2);
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_deleted_collection_is_not_logged() -> Result<(), Error> {
+  let dir = db_dir("deleted");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+    memdb.create_collection("Users")?;
+    let stale = memdb.collection("Users")?;
+    stale.insert(doc!({ "name": "Rob" }))?;
+    memdb.replace_collection("Users", CollectionOptions::default())?;
+    stale.insert(doc!({ "name": "Bob" }))?;
+    memdb.collection("Users")?.insert(doc!({ "name": "Tom" }))?;
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+  let docs = memdb.collection("Users")?.find(query!({}))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Tom");
+
+  let result = memdb.with_persistence(PersistenceOptions::new(&dir));
+  assert!(result.is_err());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_transaction_replay() -> Result<(), Error> {
+  let dir = db_dir("transaction");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+    memdb.create_collection("Stock")?;
+    memdb.create_collection("Orders")?;
+    memdb
+      .collection("Stock")?
+      .insert(doc!({ "item": "pen", "qty": 5 }))?;
+    assert_eq!(log_lines(&dir), 3);
+
+    memdb.transaction(&["Stock", "Orders"], |tx| {
+      tx.collection("Stock")?
+        .find_and_update(query!({ "item": "pen" }), query!({ "$inc": { "qty": -2 } }))?;
+      tx.collection("Orders")?
+        .insert(doc!({ "item": "pen", "qty": 2 }))
+    })?;
+    assert_eq!(log_lines(&dir), 4);
+
+    let result = memdb.transaction(&["Orders"], |tx| {
+      tx.collection("Orders")?
+        .insert(doc!({ "item": "ink", "qty": 1 }))?;
+      Err::<(), _>(Error::MQError(String::from("Out of stock.")))
+    });
+    assert!(result.is_err());
+    assert_eq!(log_lines(&dir), 4);
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir))?;
+  assert_eq!(memdb.collection("Stock")?.find(query!({}))?[0]["qty"], 3.0);
+  let orders = memdb.collection("Orders")?.find(query!({}))?;
+  assert_eq!(orders.len(), 1);
+  assert_eq!(orders[0]["item"], "pen");
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_purged_documents_are_not_replayed() -> Result<(), Error> {
+  let dir = db_dir("ttl");
+  {
+    let clock = Arc::new(ManualClock::new(0));
+    let memdb = MemDb::new()
+      .with_clock(clock.clone())
+      .with_persistence(PersistenceOptions::new(&dir))?;
+    memdb.create_collection("Cache")?;
+    let cache = memdb.collection("Cache")?;
+    cache.create_index(
+      &["expireAt"],
+      IndexOptions {
+        expire_after: Some(Duration::from_secs(0)),
+        ..Default::default()
+      },
+    )?;
+    cache.insert(doc!({ "key": "a", "expireAt": date(1_000) }))?;
+    cache.insert(doc!({ "key": "b", "expireAt": date(5_000) }))?;
+    clock.set(1_000);
+    assert_eq!(cache.purge_expired()?, 1);
+  }
+
+  // reopened with clock before both dates, removal does not depend on time of replay
+  let memdb = MemDb::new()
+    .with_clock(Arc::new(ManualClock::new(0)))
+    .with_persistence(PersistenceOptions::new(&dir))?;
+  let docs = memdb.collection("Cache")?.find(query!({}))?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["key"], "b");
+  assert_eq!(
+    memdb.collection("Cache")?.list_indexes()[1].expire_after,
+    Some(Duration::from_secs(0))
+  );
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_capped_collection_replay() -> Result<(), Error> {
+  let dir = db_dir("capped");
+  let options = CollectionOptions {
+    capped: Some(Capped {
+      max_docs: Some(2),
+      max_bytes: None,
+    }),
+    ..Default::default()
+  };
+  {
+    let memdb = MemDb::new().with_persistence(PersistenceOptions::new(&dir))?;
+    memdb.create_collection_with_options("Log", options.clone())?;
+    let log = memdb.collection("Log")?;
+    for n in 0..4 {
+      log.insert(doc!({ "n": n }))?;
+    }
+  }
+
+  let memdb = MemDb::new().with_persistence(PersistenceOptions::new(&dir))?;
+  let log = memdb.collection("Log")?;
+  assert_eq!(log.options(), options);
+  let numbers: Vec<_> = log
+    .find(query!({}))?
+    .iter()
+    .map(|d| d["n"].clone())
+    .collect();
+  assert_eq!(numbers, vec![2, 3]);
+  log.insert(doc!({ "n": 4 }))?;
+  assert_eq!(log.find(query!({}))?.len(), 2);
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "persistence")]
+fn test_rename_collection_replay() -> Result<(), Error> {
+  let dir = db_dir("rename");
+  {
+    let memdb = MemDb::new().with_persistence(PersistenceOptions::new(&dir))?;
+    memdb.create_collection("Users")?;
+    memdb.create_collection("People")?;
+    memdb.collection("Users")?.insert(doc!({ "name": "Tom" }))?;
+    memdb
+      .collection("People")?
+      .insert(doc!({ "name": "Bob" }))?;
+    memdb.rename_collection("Users", "People", true)?;
+    memdb
+      .collection("People")?
+      .insert(doc!({ "name": "Ann" }))?;
+  }
+
+  let memdb = MemDb::new().with_persistence(PersistenceOptions::new(&dir))?;
+  assert_eq!(memdb.list_collection_names(), vec!["People"]);
+  let mut names: Vec<_> = memdb
+    .collection("People")?
+    .find(query!({}))?
+    .iter()
+    .map(|d| d["name"].clone())
+    .collect();
+  names.sort_by_key(|n| n.to_string());
+  assert_eq!(names, vec!["Ann", "Tom"]);
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
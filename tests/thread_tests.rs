@@ -1,13 +1,10 @@
-#[cfg(feature = "sync")]
-use memquery::{doc, errors::Error, memdb::MemDb, query, update};
-#[cfg(feature = "sync")]
+use memquery::{blocking::MemDb, doc, errors::Error, query, update};
 use std::thread;
 
 #[test]
-#[cfg(feature = "sync")]
 fn test_simple_query_in_thread() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
 
   let coll1 = coll.clone();
@@ -36,10 +33,9 @@ fn test_simple_query_in_thread() -> Result<(), Error> {
   Ok(())
 }
 #[test]
-#[cfg(feature = "sync")]
 fn test_find_and_update_in_threads() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection");
+  memdb.create_collection("TestCollection")?;
   let coll = memdb.collection("TestCollection")?;
 
   let coll_t1 = coll.clone();
@@ -77,3 +73,54 @@ fn test_find_and_update_in_threads() -> Result<(), Error> {
   assert_eq!(docs[0]["voice"], "meow");
   Ok(())
 }
+
+#[test]
+fn test_writes_are_not_starved_by_readers() -> Result<(), Error> {
+  use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+  use std::sync::Arc;
+  use std::time::{Duration, Instant};
+
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection")?;
+  let coll = memdb.collection("TestCollection")?;
+  for age in 0..1000 {
+    coll.insert(doc!({ "name": "Rob", "age": age }))?;
+  }
+
+  let done = Arc::new(AtomicBool::new(false));
+  let reads = Arc::new(AtomicUsize::new(0));
+  let readers: Vec<_> = (0..4)
+    .map(|_| {
+      let coll = coll.clone();
+      let done = done.clone();
+      let reads = reads.clone();
+      thread::spawn(move || {
+        while !done.load(Ordering::SeqCst) {
+          let _ = coll.find(query!({ "age": { "$gte": 0 } }));
+          reads.fetch_add(1, Ordering::SeqCst);
+        }
+      })
+    })
+    .collect();
+
+  while reads.load(Ordering::SeqCst) == 0 {
+    thread::yield_now();
+  }
+  for age in 0..20 {
+    let reads_before = reads.load(Ordering::SeqCst);
+    let started = Instant::now();
+    coll.insert(doc!({ "name": "Bob", "age": age }))?;
+    // readers are still running, insert waits only for copies of collection in progress
+    assert!(started.elapsed() < Duration::from_millis(500));
+    while reads.load(Ordering::SeqCst) == reads_before {
+      thread::yield_now();
+    }
+  }
+  done.store(true, Ordering::SeqCst);
+  for reader in readers {
+    let _ = reader.join();
+  }
+
+  assert_eq!(coll.find(query!({ "name": "Bob" }))?.len(), 20);
+  Ok(())
+}
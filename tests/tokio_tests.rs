@@ -1,11 +1,11 @@
-#[cfg(not(feature = "sync"))]
-use memquery::{doc, errors::Error, memdb::MemDb, query, update};
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, query, r#async::MemDb, update};
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
 async fn test_simple_query_in_tasks() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
 
   let coll_t1 = coll.clone();
@@ -35,10 +35,11 @@ async fn test_simple_query_in_tasks() -> Result<(), Error> {
 }
 
 #[tokio::test]
-#[cfg(not(feature = "sync"))]
+#[cfg(feature = "async")]
+#[allow(clippy::manual_unwrap_or, clippy::manual_unwrap_or_default)]
 async fn test_find_and_update_in_tasks() -> Result<(), Error> {
   let memdb = MemDb::new();
-  memdb.create_collection("TestCollection").await;
+  memdb.create_collection("TestCollection").await?;
   let coll = memdb.collection("TestCollection").await?;
 
   let coll_t1 = coll.clone();
@@ -84,3 +85,55 @@ async fn test_find_and_update_in_tasks() -> Result<(), Error> {
   assert_eq!(docs[0]["voice"], "meow");
   Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[cfg(feature = "async")]
+async fn test_writes_are_not_starved_by_readers() -> Result<(), Error> {
+  use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+  use std::sync::Arc;
+  use std::time::{Duration, Instant};
+
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  for age in 0..1000 {
+    coll.insert(doc!({ "name": "Rob", "age": age })).await?;
+  }
+
+  let done = Arc::new(AtomicBool::new(false));
+  let reads = Arc::new(AtomicUsize::new(0));
+  let readers: Vec<_> = (0..4)
+    .map(|_| {
+      let coll = coll.clone();
+      let done = done.clone();
+      let reads = reads.clone();
+      tokio::spawn(async move {
+        while !done.load(Ordering::SeqCst) {
+          let _ = coll.find(query!({ "age": { "$gte": 0 } })).await;
+          reads.fetch_add(1, Ordering::SeqCst);
+        }
+      })
+    })
+    .collect();
+
+  while reads.load(Ordering::SeqCst) == 0 {
+    tokio::task::yield_now().await;
+  }
+  for age in 0..20 {
+    let reads_before = reads.load(Ordering::SeqCst);
+    let started = Instant::now();
+    coll.insert(doc!({ "name": "Bob", "age": age })).await?;
+    // readers are still running, insert waits only for copies of collection in progress
+    assert!(started.elapsed() < Duration::from_millis(500));
+    while reads.load(Ordering::SeqCst) == reads_before {
+      tokio::task::yield_now().await;
+    }
+  }
+  done.store(true, Ordering::SeqCst);
+  for reader in readers {
+    reader.await.unwrap();
+  }
+
+  assert_eq!(coll.find(query!({ "name": "Bob" })).await?.len(), 20);
+  Ok(())
+}
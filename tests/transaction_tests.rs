@@ -0,0 +1,125 @@
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, index::IndexOptions, query, r#async::MemDb, update};
+#[cfg(feature = "async")]
+use std::sync::Arc;
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_transaction_moves_document() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Pending").await?;
+  memdb.create_collection("Done").await?;
+  let pending = memdb.collection("Pending").await?;
+  pending
+    .insert(doc!({ "task": "write", "owner": "Rob" }))
+    .await?;
+  pending
+    .insert(doc!({ "task": "test", "owner": "Bob" }))
+    .await?;
+
+  let moved = memdb
+    .transaction(&["Pending", "Done"], |tx| {
+      let tasks = tx
+        .collection("Pending")?
+        .find_and_delete(query!({ "owner": "Rob" }))?;
+      let mut done = tx.collection("Done")?;
+      for task in tasks.iter() {
+        done.insert(task.clone())?;
+      }
+      Ok(tasks.len())
+    })
+    .await?;
+
+  assert_eq!(moved, 1);
+  assert_eq!(pending.find(query!({})).await?.len(), 1);
+  let done = memdb.collection("Done").await?.find(query!({})).await?;
+  assert_eq!(done.len(), 1);
+  assert_eq!(done[0]["task"], "write");
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_transaction_rolls_back_on_error() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Stock").await?;
+  memdb.create_collection("Orders").await?;
+  let stock = memdb.collection("Stock").await?;
+  let orders = memdb.collection("Orders").await?;
+  stock.insert(doc!({ "item": "pen", "qty": 5 })).await?;
+  orders
+    .create_index(
+      &["order"],
+      IndexOptions {
+        unique: true,
+        ..Default::default()
+      },
+    )
+    .await?;
+  orders.insert(doc!({ "order": 1, "item": "pen" })).await?;
+
+  let result = memdb
+    .transaction(&["Stock", "Orders"], |tx| {
+      tx.collection("Stock")?.find_and_update(
+        query!({ "item": "pen" }),
+        update!({ "$inc": { "qty": -1 } }),
+      )?;
+      tx.collection("Orders")?
+        .insert(doc!({ "order": 2, "item": "pen" }))?;
+      tx.collection("Orders")?
+        .insert(doc!({ "order": 1, "item": "pen" }))
+    })
+    .await;
+
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+  assert_eq!(stock.find(query!({})).await?[0]["qty"], 5);
+  assert_eq!(orders.find(query!({})).await?.len(), 1);
+  Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg(feature = "async")]
+async fn test_transactions_in_tasks() -> Result<(), Error> {
+  let memdb = Arc::new(MemDb::new());
+  memdb.create_collection("A").await?;
+  memdb.create_collection("B").await?;
+  memdb
+    .collection("A")
+    .await?
+    .insert(doc!({ "balance": 100 }))
+    .await?;
+  memdb
+    .collection("B")
+    .await?
+    .insert(doc!({ "balance": 100 }))
+    .await?;
+
+  let handles: Vec<_> = (0..8)
+    .map(|i| {
+      let memdb = memdb.clone();
+      let (from, to) = if i % 2 == 0 { ("A", "B") } else { ("B", "A") };
+      tokio::spawn(async move {
+        for _ in 0..50 {
+          memdb
+            .transaction(&[from, to], |tx| {
+              tx.collection(from)?
+                .find_and_update(query!({}), update!({ "$inc": { "balance": -1 } }))?;
+              tx.collection(to)?
+                .find_and_update(query!({}), update!({ "$inc": { "balance": 1 } }))
+            })
+            .await
+            .unwrap();
+        }
+      })
+    })
+    .collect();
+  for handle in handles {
+    handle.await.unwrap();
+  }
+
+  let a = memdb.collection("A").await?.find(query!({})).await?;
+  let b = memdb.collection("B").await?.find(query!({})).await?;
+  assert_eq!(a[0]["balance"], 100.0);
+  assert_eq!(b[0]["balance"], 100.0);
+  Ok(())
+}
@@ -0,0 +1,62 @@
+#[cfg(feature = "async")]
+use memquery::{
+  clock::ManualClock, doc, errors::Error, extended_json::date, index::IndexOptions, query,
+  r#async::MemDb,
+};
+#[cfg(feature = "async")]
+use std::{sync::Arc, time::Duration};
+
+#[cfg(feature = "async")]
+fn ttl(seconds: u64) -> IndexOptions {
+  IndexOptions {
+    expire_after: Some(Duration::from_secs(seconds)),
+    ..Default::default()
+  }
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_expired_documents_are_invisible() -> Result<(), Error> {
+  let clock = Arc::new(ManualClock::new(0));
+  let memdb = MemDb::new().with_clock(clock.clone());
+  memdb.create_collection("Sessions").await?;
+  let sessions = memdb.collection("Sessions").await?;
+  sessions.create_index(&["lastSeen"], ttl(60)).await?;
+  sessions
+    .insert(doc!({ "user": "Tom", "lastSeen": date(0) }))
+    .await?;
+  sessions
+    .insert(doc!({ "user": "Bob", "lastSeen": date(30_000) }))
+    .await?;
+
+  clock.advance(Duration::from_secs(60));
+  let docs = sessions.find(query!({})).await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["user"], "Bob");
+  assert_eq!(sessions.purge_expired().await?, 1);
+  assert_eq!(memdb.purge_expired().await?, 0);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn test_ttl_monitor_removes_expired_documents() -> Result<(), Error> {
+  let clock = Arc::new(ManualClock::new(0));
+  let memdb = MemDb::new().with_clock(clock.clone());
+  memdb.create_collection("Cache").await?;
+  let cache = memdb.collection("Cache").await?;
+  cache.create_index(&["expireAt"], ttl(0)).await?;
+  cache
+    .insert(doc!({ "key": "a", "expireAt": date(1_000) }))
+    .await?;
+  let mut changes = cache.watch(query!({})).await?;
+
+  let monitor = memdb.spawn_ttl_monitor(Duration::from_millis(10));
+  clock.set(1_000);
+  let event = changes.recv().await?;
+  assert_eq!(event.full_document_before_change.unwrap()["key"], "a");
+
+  drop(memdb);
+  monitor.await.unwrap();
+  Ok(())
+}
@@ -0,0 +1,114 @@
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, query, r#async::MemDb, update};
+#[cfg(feature = "async")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct User {
+  name: String,
+  age: u32,
+  #[serde(default)]
+  tags: Vec<String>,
+}
+
+#[cfg(feature = "async")]
+fn user(name: &str, age: u32) -> User {
+  User {
+    name: name.to_string(),
+    age,
+    tags: Vec::new(),
+  }
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_insert_and_find_typed() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection_typed::<User>("Users").await?;
+  users.insert(user("Rob", 25)).await?;
+  users.insert(user("Bob", 20)).await?;
+  users.insert(user("Tom", 30)).await?;
+
+  let found = users.find(query!({ "age": { "$gte": 25 } })).await?;
+
+  assert_eq!(found, vec![user("Rob", 25), user("Tom", 30)]);
+  assert_eq!(
+    users.find_one(query!({ "name": "Bob" })).await?,
+    Some(user("Bob", 20))
+  );
+  assert_eq!(users.find_one(query!({ "name": "Victor" })).await?, None);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_update_and_delete_typed() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let users = memdb.collection_typed::<User>("Users").await?;
+  users.insert(user("Rob", 25)).await?;
+  users.insert(user("Bob", 20)).await?;
+
+  let updated = users
+    .find_and_update(
+      query!({ "name": "Bob" }),
+      update!({ "$set": { "age": 21 } }),
+    )
+    .await?;
+  assert_eq!(updated, 1);
+
+  let deleted = users.find_and_delete(query!({ "name": "Bob" })).await?;
+  assert_eq!(deleted, vec![user("Bob", 21)]);
+  assert_eq!(users.find(query!({})).await?, vec![user("Rob", 25)]);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_mismatched_document_returns_error() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let coll = memdb.collection("Users").await?;
+  coll
+    .insert(doc!({ "name": "Rob", "age": "unknown" }))
+    .await?;
+
+  let users = memdb.collection_typed::<User>("Users").await?;
+  let result = users.find(query!({ "name": "Rob" })).await;
+
+  assert!(matches!(result, Err(Error::SerdeJsonError(_))));
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_mismatched_document_is_not_deleted() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  let coll = memdb.collection("Users").await?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+  coll
+    .insert(doc!({ "name": "Bob", "age": "unknown" }))
+    .await?;
+
+  let users = memdb.collection_typed::<User>("Users").await?;
+  let result = users.find_and_delete(query!({})).await;
+
+  assert!(matches!(result, Err(Error::SerdeJsonError(_))));
+  assert_eq!(coll.find(query!({})).await?.len(), 2);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_insert_non_object_fails() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Numbers").await?;
+  let numbers = memdb.collection_typed::<u32>("Numbers").await?;
+
+  assert!(numbers.insert(5).await.is_err());
+  assert!(numbers.collection().find(query!({})).await?.is_empty());
+  Ok(())
+}
@@ -0,0 +1,38 @@
+#[cfg(feature = "async")]
+use memquery::{doc, errors::Error, options::CollectionOptions, query, r#async::MemDb, update};
+#[cfg(feature = "async")]
+use serde_json::json;
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_changes_are_validated() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  let options = CollectionOptions {
+    validator: Some(json!({
+      "$jsonSchema": {
+        "required": ["name"],
+        "properties": { "age": { "type": "integer", "minimum": 0 } }
+      }
+    })),
+    ..Default::default()
+  };
+  memdb
+    .create_collection_with_options("People", options)
+    .await?;
+  let people = memdb.collection("People").await?;
+  people.insert(doc!({ "name": "Tom", "age": 25 })).await?;
+
+  match people.insert(doc!({ "age": 30 })).await {
+    Err(Error::MQValidationFailed { failures }) => {
+      assert_eq!(failures[0].path, "name");
+      assert_eq!(failures[0].rule, "required");
+    }
+    _ => panic!("document without name was inserted"),
+  }
+  let result = people
+    .find_and_update(query!({}), update!({ "$set": { "age": "25" } }))
+    .await;
+  assert!(matches!(result, Err(Error::MQValidationFailed { .. })));
+  assert_eq!(people.find(query!({ "age": 25 })).await?.len(), 1);
+  Ok(())
+}
@@ -0,0 +1,60 @@
+#[cfg(feature = "async")]
+use memquery::{
+  doc, errors::Error, import_export::ExportOptions, index::IndexOptions, query, r#async::MemDb,
+  update,
+};
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_view_is_point_in_time() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("TestCollection").await?;
+  let coll = memdb.collection("TestCollection").await?;
+  coll.create_index(&["age"], IndexOptions::default()).await?;
+  coll.insert(doc!({ "name": "Rob", "age": 25 })).await?;
+  coll.insert(doc!({ "name": "Bob", "age": 20 })).await?;
+
+  let view = coll.view().await;
+  coll.insert(doc!({ "name": "Tom", "age": 30 })).await?;
+  coll
+    .find_and_update(
+      query!({ "name": "Rob" }),
+      update!({ "$set": { "age": 26 } }),
+    )
+    .await?;
+  coll.find_and_delete(query!({ "name": "Bob" })).await?;
+
+  let docs = view.find(query!({ "age": { "$gte": 20 } }))?;
+  assert_eq!(docs.len(), 2);
+  assert_eq!(docs[0]["age"], 25);
+  assert_eq!(docs[1]["name"], "Bob");
+  assert_eq!(view.list_indexes().len(), 2);
+
+  let mut output = Vec::new();
+  assert_eq!(
+    view.export_json_array(&mut output, ExportOptions::default())?,
+    2
+  );
+  assert_eq!(coll.find(query!({})).await?.len(), 2);
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_database_view() -> Result<(), Error> {
+  let memdb = MemDb::new();
+  memdb.create_collection("Users").await?;
+  memdb.create_collection("Orders").await?;
+  let users = memdb.collection("Users").await?;
+  users.insert(doc!({ "name": "Rob" })).await?;
+
+  let view = memdb.view().await;
+  users.insert(doc!({ "name": "Bob" })).await?;
+  memdb.delete_collection("Orders").await?;
+
+  assert_eq!(view.collection_names(), vec!["Orders", "Users"]);
+  assert_eq!(view.collection("Users")?.len(), 1);
+  assert!(view.collection("Orders")?.is_empty());
+  assert_eq!(users.find(query!({})).await?.len(), 2);
+  Ok(())
+}
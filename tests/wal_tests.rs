@@ -0,0 +1,241 @@
+#[cfg(all(feature = "persistence", feature = "async"))]
+use memquery::{
+  doc,
+  errors::Error,
+  id_generator::AutoIncrement,
+  index::IndexOptions,
+  options::CollectionOptions,
+  query,
+  r#async::MemDb,
+  wal::{FsyncPolicy, PersistenceOptions, LOG_FILE},
+};
+#[cfg(all(feature = "persistence", feature = "async"))]
+use std::path::{Path, PathBuf};
+
+#[cfg(all(feature = "persistence", feature = "async"))]
+fn db_dir(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("memquery-{}-{}", name, uuid::Uuid::new_v4()))
+}
+
+#[cfg(all(feature = "persistence", feature = "async"))]
+fn log_lines(dir: &Path) -> usize {
+  std::fs::read_to_string(dir.join(LOG_FILE))
+    .unwrap()
+    .lines()
+    .count()
+}
+
+#[tokio::test]
+#[cfg(all(feature = "persistence", feature = "async"))]
+async fn test_replay_after_restart() -> Result<(), Error> {
+  let dir = db_dir("replay");
+  {
+    let memdb = MemDb::with_id_generator(AutoIncrement::new)
+      .with_persistence(PersistenceOptions::new(&dir))
+      .await?;
+    memdb.create_collection("Users").await?;
+    memdb.create_collection("Temp").await?;
+    let users = memdb.collection("Users").await?;
+    users
+      .create_index(
+        &["email"],
+        IndexOptions {
+          unique: true,
+          ..Default::default()
+        },
+      )
+      .await?;
+    users
+      .insert(query!({ "name": "Rob", "email": "rob@test.com", "age": 25 }))
+      .await?;
+    users
+      .insert(query!({ "name": "Bob", "email": "bob@test.com", "age": 30 }))
+      .await?;
+    users
+      .insert(query!({ "name": "Tom", "email": "tom@test.com", "age": 35 }))
+      .await?;
+    users
+      .find_and_update(query!({ "name": "Rob" }), query!({ "$set": { "age": 26 } }))
+      .await?;
+    users.find_and_delete(query!({ "name": "Bob" })).await?;
+    memdb.delete_collection("Temp").await?;
+  }
+
+  let mut options = PersistenceOptions::new(&dir);
+  options.fsync = FsyncPolicy::Never;
+  let memdb = MemDb::with_id_generator(AutoIncrement::new)
+    .with_persistence(options)
+    .await?;
+  let users = memdb.collection("Users").await?;
+  let docs = users.find(query!({})).await?;
+  assert_eq!(docs.len(), 2);
+  assert_eq!(docs[0]["_id"], 1);
+  assert_eq!(docs[0]["age"], 26);
+  assert_eq!(docs[1]["name"], "Tom");
+  assert!(memdb.collection("Temp").await.is_err());
+
+  users
+    .insert(query!({ "name": "Ann", "email": "ann@test.com" }))
+    .await?;
+  assert_eq!(users.find(query!({ "name": "Ann" })).await?[0]["_id"], 4);
+
+  let result = users
+    .insert(query!({ "name": "Ann", "email": "tom@test.com" }))
+    .await;
+  assert!(matches!(result, Err(Error::MQDuplicateKey { .. })));
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(all(feature = "persistence", feature = "async"))]
+async fn test_compaction() -> Result<(), Error> {
+  let dir = db_dir("compaction");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+    memdb.create_collection("Users").await?;
+    let users = memdb.collection("Users").await?;
+    for age in 0..10 {
+      users.insert(doc!({ "age": age })).await?;
+    }
+    users
+      .find_and_delete(query!({ "age": { "$gte": 5 } }))
+      .await?;
+    memdb.compact().await?;
+    assert_eq!(log_lines(&dir), 0);
+
+    users.insert(doc!({ "age": 100 })).await?;
+    assert_eq!(log_lines(&dir), 1);
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+  let users = memdb.collection("Users").await?;
+  assert_eq!(users.find(query!({})).await?.len(), 6);
+  memdb.compact().await?;
+  assert_eq!(log_lines(&dir), 0);
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+  assert_eq!(
+    memdb
+      .collection("Users")
+      .await?
+      .find(query!({}))
+      .await?
+      .len(),
+    6
+  );
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(all(feature = "persistence", feature = "async"))]
+async fn test_incomplete_last_entry_is_ignored() -> Result<(), Error> {
+  let dir = db_dir("torn");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+    memdb.create_collection("Users").await?;
+    let users = memdb.collection("Users").await?;
+    users.insert(doc!({ "name": "Rob" })).await?;
+  }
+  let mut log = std::fs::read_to_string(dir.join(LOG_FILE))?;
+  log.push_str(r#"{"lsn":2,"collection":"Users","op":"ins"#);
+  std::fs::write(dir.join(LOG_FILE), log)?;
+
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+    let users = memdb.collection("Users").await?;
+    assert_eq!(users.find(query!({})).await?.len(), 1);
+    users.insert(doc!({ "name": "Bob" })).await?;
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+  let users = memdb.collection("Users").await?;
+  assert_eq!(users.find(query!({})).await?.len(), 2);
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(all(feature = "persistence", feature = "async"))]
+async fn test_deleted_collection_is_not_logged() -> Result<(), Error> {
+  let dir = db_dir("deleted");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+    memdb.create_collection("Users").await?;
+    let stale = memdb.collection("Users").await?;
+    stale.insert(doc!({ "name": "Rob" })).await?;
+    memdb
+      .replace_collection("Users", CollectionOptions::default())
+      .await?;
+    stale.insert(doc!({ "name": "Bob" })).await?;
+    memdb
+      .collection("Users")
+      .await?
+      .insert(doc!({ "name": "Tom" }))
+      .await?;
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+  let docs = memdb.collection("Users").await?.find(query!({})).await?;
+  assert_eq!(docs.len(), 1);
+  assert_eq!(docs[0]["name"], "Tom");
+
+  let result = memdb.with_persistence(PersistenceOptions::new(&dir)).await;
+  assert!(result.is_err());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[tokio::test]
+#[cfg(all(feature = "persistence", feature = "async"))]
+async fn test_transaction_replay() -> Result<(), Error> {
+  let dir = db_dir("transaction");
+  {
+    let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+    memdb.create_collection("Stock").await?;
+    memdb.create_collection("Orders").await?;
+    memdb
+      .collection("Stock")
+      .await?
+      .insert(doc!({ "item": "pen", "qty": 5 }))
+      .await?;
+    assert_eq!(log_lines(&dir), 3);
+
+    memdb
+      .transaction(&["Stock", "Orders"], |tx| {
+        tx.collection("Stock")?
+          .find_and_update(query!({ "item": "pen" }), query!({ "$inc": { "qty": -2 } }))?;
+        tx.collection("Orders")?
+          .insert(doc!({ "item": "pen", "qty": 2 }))
+      })
+      .await?;
+    assert_eq!(log_lines(&dir), 4);
+
+    let result = memdb
+      .transaction(&["Orders"], |tx| {
+        tx.collection("Orders")?
+          .insert(doc!({ "item": "ink", "qty": 1 }))?;
+        Err::<(), _>(Error::MQError(String::from("Out of stock.")))
+      })
+      .await;
+    assert!(result.is_err());
+    assert_eq!(log_lines(&dir), 4);
+  }
+
+  let memdb = MemDb::open(PersistenceOptions::new(&dir)).await?;
+  assert_eq!(
+    memdb.collection("Stock").await?.find(query!({})).await?[0]["qty"],
+    3.0
+  );
+  let orders = memdb.collection("Orders").await?.find(query!({})).await?;
+  assert_eq!(orders.len(), 1);
+  assert_eq!(orders[0]["item"], "pen");
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
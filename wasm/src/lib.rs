@@ -4,7 +4,12 @@ use lazy_static::lazy_static;
 pub use memory::{
   alloc, dealloc, json_from_ptr, json_to_ptr, result_to_ptr, string_from_ptr, string_to_ptr,
 };
-use memquery::{doc, memdb::MemDb};
+use memquery::{
+  blocking::MemDb,
+  doc,
+  errors::Error,
+  options::{CollectionOptions, CountOptions},
+};
 use serde_json::json;
 
 lazy_static! {
@@ -12,9 +17,49 @@ lazy_static! {
 }
 
 #[no_mangle]
-pub fn create_collection(ptr: *mut u8, len: usize) {
+pub fn create_collection(ptr: *mut u8, len: usize) -> *mut u8 {
   let name = unsafe { string_from_ptr(ptr, len) };
-  MEMDB.create_collection(&name);
+  match MEMDB.create_collection(&name) {
+    Ok(_) => unsafe { result_to_ptr(Ok(&json!({}))) },
+    Err(e) => unsafe { result_to_ptr(Err(e)) },
+  }
+}
+
+#[no_mangle]
+pub fn replace_collection(ptr: *mut u8, len: usize) -> *mut u8 {
+  let name = unsafe { string_from_ptr(ptr, len) };
+  match MEMDB.replace_collection(&name, CollectionOptions::default()) {
+    Ok(_) => unsafe { result_to_ptr(Ok(&json!({}))) },
+    Err(e) => unsafe { result_to_ptr(Err(e)) },
+  }
+}
+
+#[no_mangle]
+pub fn list_collection_names() -> *mut u8 {
+  let names = MEMDB.list_collection_names();
+  unsafe { result_to_ptr(Ok(&json!(names))) }
+}
+
+#[no_mangle]
+pub fn collection_exists(ptr: *mut u8, len: usize) -> u8 {
+  let coll_name = unsafe { string_from_ptr(ptr, len) };
+  MEMDB.collection_exists(&coll_name) as u8
+}
+
+#[no_mangle]
+pub fn rename_collection(
+  from_ptr: *mut u8,
+  from_len: usize,
+  to_ptr: *mut u8,
+  to_len: usize,
+  drop_target: u8,
+) -> *mut u8 {
+  let from = unsafe { string_from_ptr(from_ptr, from_len) };
+  let to = unsafe { string_from_ptr(to_ptr, to_len) };
+  match MEMDB.rename_collection(&from, &to, drop_target != 0) {
+    Ok(_) => unsafe { result_to_ptr(Ok(&json!({}))) },
+    Err(e) => unsafe { result_to_ptr(Err(e)) },
+  }
 }
 
 #[no_mangle]
@@ -146,3 +191,60 @@ pub fn find_and_delete(
     Err(e) => unsafe { result_to_ptr(Err(e)) },
   }
 }
+
+#[no_mangle]
+pub fn collection_stats(ptr: *mut u8, len: usize) -> *mut u8 {
+  let coll_name = unsafe { string_from_ptr(ptr, len) };
+  let coll = match MEMDB.collection(&coll_name) {
+    Ok(c) => c,
+    Err(e) => return unsafe { result_to_ptr(Err(e)) },
+  };
+
+  unsafe { result_to_ptr(Ok(&json!(coll.stats()))) }
+}
+
+#[no_mangle]
+pub fn stats() -> *mut u8 {
+  unsafe { result_to_ptr(Ok(&json!(MEMDB.stats()))) }
+}
+
+#[no_mangle]
+pub fn count_documents(
+  coll_name_ptr: *mut u8,
+  coll_len: usize,
+  query_ptr: *mut u8,
+  query_len: usize,
+  options_ptr: *mut u8,
+  options_len: usize,
+) -> *mut u8 {
+  let coll_name = unsafe { string_from_ptr(coll_name_ptr, coll_len) };
+  let query = match unsafe { json_from_ptr(query_ptr, query_len) } {
+    Ok(q) => q,
+    Err(e) => return unsafe { result_to_ptr(Err(e)) },
+  };
+  let options = match unsafe { json_from_ptr(options_ptr, options_len) }
+    .and_then(|o| serde_json::from_value::<CountOptions>(o).map_err(Error::from))
+  {
+    Ok(o) => o,
+    Err(e) => return unsafe { result_to_ptr(Err(e)) },
+  };
+
+  let coll = match MEMDB.collection(&coll_name) {
+    Ok(c) => c,
+    Err(e) => return unsafe { result_to_ptr(Err(e)) },
+  };
+
+  match coll.count_documents(query, options) {
+    Ok(count) => unsafe { result_to_ptr(Ok(&json!(count))) },
+    Err(e) => unsafe { result_to_ptr(Err(e)) },
+  }
+}
+
+#[no_mangle]
+pub fn estimated_document_count(ptr: *mut u8, len: usize) -> *mut u8 {
+  let coll_name = unsafe { string_from_ptr(ptr, len) };
+  match MEMDB.collection(&coll_name) {
+    Ok(coll) => unsafe { result_to_ptr(Ok(&json!(coll.estimated_document_count()))) },
+    Err(e) => unsafe { result_to_ptr(Err(e)) },
+  }
+}
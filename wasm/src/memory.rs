@@ -39,9 +39,10 @@ pub unsafe fn json_to_ptr(jsvalue: &Value) -> *mut u8 {
   string_to_ptr(&jsvalstr)
 }
 
+/// Writes `{"value": ...}` or `{"error": {"code": ..., "message": ..., "path": ...}}`.
 pub unsafe fn result_to_ptr(result: Result<&Value, Error>) -> *mut u8 {
   match result {
     Ok(v) => json_to_ptr(&json!({ "value": v })),
-    Err(e) => json_to_ptr(&json!({ "error": json!(e.to_string()) })),
+    Err(e) => json_to_ptr(&json!({ "error": e.to_json() })),
   }
 }